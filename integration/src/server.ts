@@ -11,11 +11,11 @@ export function createServer(): McpServer {
 
   server.tool(
     "score_alert",
-    "Score a whale alert. Returns { score, tier, factors }.",
+    "Score a whale alert. Returns { score, tier, factors }, as scored by wwatcher when the alert carries one.",
     { alert: z.string().describe("Full alert JSON string") },
     async ({ alert }) => {
       const parsed: WhalertAlert = JSON.parse(alert);
-      const result = scoreAlert(parsed);
+      const result = parsed.scoring ?? scoreAlert(parsed);
       return { content: [{ type: "text", text: JSON.stringify(result) }] };
     },
   );
@@ -1,7 +1,11 @@
 /** Matches the JSON schema from wwatcher's build_alert_payload() in src/alerts/mod.rs */
 export interface WhalertAlert {
   platform: string;
-  alert_type: "WHALE_ENTRY" | "WHALE_EXIT";
+  /**
+   * WHALE_ENTRY / WHALE_EXIT, or <BAND>_ENTRY / <BAND>_EXIT (e.g. "MEGA_WHALE_ENTRY")
+   * when size bands are configured; check the suffix or `action` for direction
+   */
+  alert_type: "WHALE_ACCUMULATION" | "FRESH_WALLET_ENTRY" | `${string}_ENTRY` | `${string}_EXIT`;
   action: "BUY" | "SELL";
   value: number;
   price: number;
@@ -10,12 +14,141 @@ export interface WhalertAlert {
   timestamp: string;
   market_title: string | null;
   outcome: string | null;
+  trade_id?: string;
+  /** Size band the order falls in, when bands are configured */
+  band?: string;
+  /** Dedup key: one alert per platform and order_id */
+  order_id?: string;
   wallet_id?: string;
   wallet_activity?: WalletActivity;
   market_context?: MarketContext;
   whale_profile?: WhaleProfile;
   order_book?: OrderBook;
   top_holders?: TopHolders;
+  /** Score computed by wwatcher with the user's configured weights */
+  scoring?: AlertScore;
+  /** Whether the trade moved the market; "measuring" until wwatcher's follow-up */
+  price_impact?: PriceImpact;
+  /** What makes the trade unusual for its market, most severe first */
+  anomalies?: Anomaly[];
+  /** Set on FRESH_WALLET_ENTRY: a new wallet's outsized long-shot buy */
+  fresh_wallet?: FreshWallet;
+  /** Linked wallets that look like the same trader */
+  wallet_cluster?: WalletCluster;
+}
+
+export interface FreshWallet {
+  severity: "medium" | "high";
+  /** From the wallet's first activity, or its funding if earlier */
+  age_hours: number;
+  lifetime_trades: number;
+  reason: string;
+}
+
+export interface WalletCluster {
+  id: string;
+  wallets: number;
+  combined_value: number;
+}
+
+export interface Anomaly {
+  code:
+    | "SIZE_VS_MARKET"
+    | "SIZE_VS_CATEGORY"
+    | "FLOW_SPIKE"
+    | "WALLET_FLOW"
+    | "HEAVY_ACTOR"
+    | "REPEAT_ACTOR"
+    | "EXTREME_ODDS"
+    | "LONG_SHOT";
+  severity: "low" | "medium" | "high";
+  reason: string;
+  z_score?: number;
+}
+
+/** COORDINATED_FLOW payload: several wallets on one outcome and side */
+export interface CoordinatedFlowAlert {
+  platform: string;
+  alert_type: "COORDINATED_FLOW";
+  action: "BUY" | "SELL";
+  value: number;
+  timestamp: string;
+  market_title: string | null;
+  market_id: string;
+  outcome: string | null;
+  coordinated_flow: {
+    wallet_count: number;
+    combined_value: number;
+    first_trade_at: string;
+    last_trade_at: string;
+    spread_secs: number;
+    window_minutes: number;
+    wallets: FlowWallet[];
+  };
+}
+
+/**
+ * PRICE_IMPACT follow-up: sent once per alert with a price impact "measuring",
+ * when the impact window closes (or the move reverts). Not a new alert: match it
+ * to the alert it follows by platform and order_id.
+ */
+export interface PriceImpactPayload {
+  platform: string;
+  alert_type: "PRICE_IMPACT";
+  trade_id: string;
+  order_id: string;
+  /** The alerted trade's side, upper-cased (Kalshi: "YES" or "NO") */
+  action: string;
+  value: number;
+  market_title: string | null;
+  outcome: string | null;
+  timestamp: string;
+  /** Final measurement: status is "held", "reverted" or "no_move" */
+  price_impact: PriceImpact;
+}
+
+/** Sent when a funding lookup that finished after the alert changed its fresh wallet verdict */
+export interface FreshWalletUpdatePayload {
+  platform: string;
+  alert_type: "FRESH_WALLET_UPDATE";
+  /** The stored alert's type now: FRESH_WALLET_ENTRY, or its type without the flag */
+  updated_alert_type: string;
+  trade_id: string;
+  order_id: string;
+  action: string;
+  value: number;
+  market_title: string | null;
+  outcome: string | null;
+  timestamp: string;
+  wallet_id: string | null;
+  funded_by: string;
+  funded_at: string | null;
+  /** null when the wallet is no longer flagged */
+  fresh_wallet: FreshWallet | null;
+}
+
+/** Any JSON body wwatcher POSTs to the webhook; switch on alert_type */
+export type WebhookPayload = WhalertAlert | CoordinatedFlowAlert | PriceImpactPayload | FreshWalletUpdatePayload;
+
+export interface FlowWallet {
+  wallet_id: string;
+  value: number;
+  trades: number;
+  first_trade_at: string;
+}
+
+export interface PriceImpact {
+  price_before: number | null;
+  slippage: number | null;
+  /** price_before, else mid_at_alert when no recent trade was seen */
+  baseline: number | null;
+  mid_at_alert: number | null;
+  mid_move: number | null;
+  mid_after: number | null;
+  final_move: number | null;
+  recovery_secs: number | null;
+  snapshots: number;
+  status: "measuring" | "held" | "reverted" | "no_move" | "unavailable";
 }
 
 export interface WalletActivity {
@@ -47,6 +180,12 @@ export interface WhaleProfile {
   win_rate?: number | null;
   positions_count?: number | null;
   markets_traded?: number | null;
+  /** Sender of the wallet's first USDC, with a Polygon RPC endpoint configured */
+  funded_by?: string | null;
+  first_seen_at?: string | null;
+  lifetime_trades?: number | null;
+  /** First USDC transfer, with a Polygon RPC endpoint configured */
+  funded_at?: string | null;
 }
 
 /** Order book depth from CLOB/Kalshi orderbook API */
@@ -1,75 +1,429 @@
-use crate::types;
+//! Anomalies: what makes a trade unusual for its market.
+//!
+//! Ingest records every order it sees into hourly per-market buckets
+//! (`market_baselines`). At alert time the trade is compared with the last
+//! `BASELINE_DAYS` of its market and category: trade size as a z-score of
+//! log value (trade sizes are roughly log-normal, so the median is the
+//! geometric mean), and the current hour's flow against the typical hour.
+//! Size checks need `MIN_TRADES` recorded trades and flow checks `MIN_HOURS`
+//! of history, so a new market raises no baseline anomalies.
+
 use colored::*;
+use serde::{Deserialize, Serialize};
 
+use super::display::format_number;
 use super::sound;
+use crate::categories::CategoryRegistry;
+use crate::config::FreshWalletPolicy;
+use crate::platforms::NormalizedTrade;
+use crate::types;
+use crate::whale_profile::{WhaleProfile, ACTIVITY_LIMIT};
+
+/// Days of history a baseline covers
+pub const BASELINE_DAYS: i64 = 7;
+/// Trades a baseline needs before size anomalies are raised
+const MIN_TRADES: u64 = 20;
+/// Hours of history a baseline needs before flow anomalies are raised
+const MIN_HOURS: u64 = 6;
+/// Floor on the log-size spread, so a market of identical trades doesn't make every trade an outlier
+const MIN_LOG_SD: f64 = 0.25;
+/// z-scores at which a finding is medium and high severity
+const Z_MEDIUM: f64 = 2.0;
+const Z_HIGH: f64 = 3.0;
+/// A fresh wallet's buy is high severity within a day of funding or on its first trade
+const FRESH_HIGH_HOURS: f64 = 24.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    fn from_z(z: f64) -> Option<Self> {
+        if z >= Z_HIGH {
+            Some(Severity::High)
+        } else if z >= Z_MEDIUM {
+            Some(Severity::Medium)
+        } else {
+            None
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Severity::Low => Color::Yellow,
+            Severity::Medium => Color::BrightYellow,
+            Severity::High => Color::BrightRed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AnomalyCode {
+    /// Trade far above the market's usual trade size
+    SizeVsMarket,
+    /// Trade far above the category's usual trade size
+    SizeVsCategory,
+    /// The market's flow this hour is far above its typical hour
+    FlowSpike,
+    /// The wallet alone moved more than the market's typical hour
+    WalletFlow,
+    HeavyActor,
+    RepeatActor,
+    /// Buying a side priced above 95%
+    ExtremeOdds,
+    /// Buying a side priced below 5%
+    LongShot,
+}
+
+impl AnomalyCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AnomalyCode::SizeVsMarket => "SIZE_VS_MARKET",
+            AnomalyCode::SizeVsCategory => "SIZE_VS_CATEGORY",
+            AnomalyCode::FlowSpike => "FLOW_SPIKE",
+            AnomalyCode::WalletFlow => "WALLET_FLOW",
+            AnomalyCode::HeavyActor => "HEAVY_ACTOR",
+            AnomalyCode::RepeatActor => "REPEAT_ACTOR",
+            AnomalyCode::ExtremeOdds => "EXTREME_ODDS",
+            AnomalyCode::LongShot => "LONG_SHOT",
+        }
+    }
+}
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub code: AnomalyCode,
+    pub severity: Severity,
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub z_score: Option<f64>,
+}
+
+/// A buy flagged by `fresh_wallet`. Raised as its own alert type
+/// (`FRESH_WALLET_ENTRY`) rather than among the anomalies.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FreshWallet {
+    pub severity: Severity,
+    /// Hours from the wallet's first activity (or earlier funding) to the trade
+    pub age_hours: f64,
+    pub lifetime_trades: u32,
+    pub reason: String,
+}
+
+/// Running sums over a baseline's hourly buckets, as stored
+#[derive(Debug, Clone, Default)]
+pub struct BaselineSums {
+    pub trades: u64,
+    /// Sum of ln(value) and of its square over every trade
+    pub log_sum: f64,
+    pub log_sq_sum: f64,
+    /// Hours covered before the current one, counting hours without trades
+    pub hours: u64,
+    /// Sum of hourly volume and of its square over those hours
+    pub flow_sum: f64,
+    pub flow_sq_sum: f64,
+    /// Volume so far in the current hour
+    pub current_flow: f64,
+}
+
+fn mean_sd(n: f64, sum: f64, sq_sum: f64) -> (f64, f64) {
+    let mean = sum / n;
+    (mean, (sq_sum / n - mean * mean).max(0.0).sqrt())
+}
+
+impl BaselineSums {
+    /// Remove a trade that is already counted, so it isn't compared with itself
+    pub fn without(mut self, value: f64) -> Self {
+        if self.trades > 0 && value > 0.0 {
+            let ln = value.ln();
+            self.trades -= 1;
+            self.log_sum -= ln;
+            self.log_sq_sum -= ln * ln;
+        }
+        self
+    }
+
+    /// Median trade (geometric mean) and z-score of `value`, once there are enough trades
+    fn size(&self, value: f64) -> Option<(f64, f64)> {
+        if self.trades < MIN_TRADES || value <= 0.0 {
+            return None;
+        }
+        let (mean, sd) = mean_sd(self.trades as f64, self.log_sum, self.log_sq_sum);
+        Some((mean.exp(), (value.ln() - mean) / sd.max(MIN_LOG_SD)))
+    }
+
+    /// Typical hourly flow and its spread, once there is enough history
+    fn hourly(&self) -> Option<(f64, f64)> {
+        (self.hours >= MIN_HOURS).then(|| mean_sd(self.hours as f64, self.flow_sum, self.flow_sq_sum))
+    }
+}
+
+/// Baselines a trade is compared with
+#[derive(Debug, Clone, Default)]
+pub struct Baselines {
+    pub market: BaselineSums,
+    /// `None` until the market's category is known
+    pub category: Option<(String, BaselineSums)>,
+}
+
+/// Category a market's baseline is kept under: the title's category when it
+/// has one, else the venue's own category
+pub fn baseline_category(registry: &CategoryRegistry, trade: &NormalizedTrade) -> Option<String> {
+    trade
+        .market_title
+        .as_deref()
+        .and_then(|title| registry.categorize(title))
+        .map(|(category, _)| category)
+        .or_else(|| trade.category.as_ref().map(|native| native.to_lowercase()))
+}
+
+fn size_anomaly(code: AnomalyCode, scope: &str, value: f64, sums: &BaselineSums) -> Option<Anomaly> {
+    let (median, z) = sums.size(value)?;
+    Some(Anomaly {
+        code,
+        severity: Severity::from_z(z)?,
+        reason: format!(
+            "${} is {:.0}x the median trade in {} (${}, z {:.1})",
+            format_number(value as u64),
+            value / median,
+            scope,
+            format_number(median as u64),
+            z
+        ),
+        z_score: Some(z),
+    })
+}
+
+/// Find what's unusual about a trade. `value` is the order's value, and
+/// `bought_odds` the price of the side bought (`None` for exits).
+/// Accumulation alerts skip the size checks: their value is a sum of orders.
 pub fn detect_anomalies(
-    price: f64,
-    size: f64,
     value: f64,
+    bought_odds: Option<f64>,
+    accumulated: bool,
     wallet_activity: Option<&types::WalletActivity>,
-) {
+    baselines: &Baselines,
+) -> Vec<Anomaly> {
     let mut anomalies = Vec::new();
 
+    if !accumulated {
+        anomalies.extend(size_anomaly(AnomalyCode::SizeVsMarket, "this market", value, &baselines.market));
+        if let Some((name, sums)) = baselines.category.as_ref() {
+            anomalies.extend(size_anomaly(AnomalyCode::SizeVsCategory, name, value, sums));
+        }
+    }
+
+    let hourly = baselines.market.hourly();
+    if let Some((mean, sd)) = hourly {
+        let flow = baselines.market.current_flow;
+        // The spread is floored at the mean, so a spike is at least 3x the typical hour
+        let z = (flow - mean) / sd.max(mean).max(1.0);
+        if let Some(severity) = Severity::from_z(z) {
+            anomalies.push(Anomaly {
+                code: AnomalyCode::FlowSpike,
+                severity,
+                reason: format!(
+                    "${} traded this hour against a typical ${} (z {:.1})",
+                    format_number(flow as u64),
+                    format_number(mean as u64),
+                    z
+                ),
+                z_score: Some(z),
+            });
+        }
+    }
+
     if let Some(activity) = wallet_activity {
         if activity.is_heavy_actor {
-            anomalies.push(format!(
-                "HEAVY ACTOR: {} transactions worth ${:.2} in last 24h",
-                activity.transactions_last_day, activity.total_value_day
-            ));
-        }
-        if activity.is_repeat_actor && !activity.is_heavy_actor {
-            anomalies.push(format!(
-                "Repeat actor: {} transactions in last hour",
-                activity.transactions_last_hour
-            ));
+            anomalies.push(Anomaly {
+                code: AnomalyCode::HeavyActor,
+                severity: Severity::Medium,
+                reason: format!(
+                    "{} transactions worth ${} in the last 24h",
+                    activity.transactions_last_day,
+                    format_number(activity.total_value_day as u64)
+                ),
+                z_score: None,
+            });
+        } else if activity.is_repeat_actor {
+            anomalies.push(Anomaly {
+                code: AnomalyCode::RepeatActor,
+                severity: Severity::Low,
+                reason: format!("{} transactions in the last hour", activity.transactions_last_hour),
+                z_score: None,
+            });
         }
-        if activity.total_value_hour > 200000.0 {
-            anomalies.push(format!(
-                "Coordinated activity: ${:.0} volume in past hour",
-                activity.total_value_hour
-            ));
+        if let Some((mean, _)) = hourly.filter(|&(mean, _)| mean > 0.0 && activity.total_value_hour > mean) {
+            anomalies.push(Anomaly {
+                code: AnomalyCode::WalletFlow,
+                severity: if activity.total_value_hour > 3.0 * mean { Severity::High } else { Severity::Medium },
+                reason: format!(
+                    "wallet traded ${} this hour, more than the market's typical ${}",
+                    format_number(activity.total_value_hour as u64),
+                    format_number(mean as u64)
+                ),
+                z_score: None,
+            });
         }
     }
 
-    if price > 0.95 {
-        anomalies.push(format!(
-            "Extreme confidence bet ({:.1}% probability)",
-            price * 100.0
-        ));
-    } else if price < 0.05 {
-        anomalies.push(format!(
-            "Contrarian position ({:.1}% probability)",
-            price * 100.0
-        ));
+    match bought_odds {
+        Some(odds) if odds > 0.95 => anomalies.push(Anomaly {
+            code: AnomalyCode::ExtremeOdds,
+            severity: Severity::Low,
+            reason: format!("bought at {:.1}% odds", odds * 100.0),
+            z_score: None,
+        }),
+        Some(odds) if odds < 0.05 => anomalies.push(Anomaly {
+            code: AnomalyCode::LongShot,
+            severity: Severity::Low,
+            reason: format!("bought at {:.1}% odds", odds * 100.0),
+            z_score: None,
+        }),
+        _ => {}
     }
 
-    if size > 100000.0 {
-        anomalies.push("Exceptionally large position size".to_string());
-    }
+    anomalies.sort_by_key(|a| std::cmp::Reverse(a.severity));
+    anomalies
+}
 
-    if value > 100000.0 {
-        anomalies.push(format!("Major capital deployment: ${:.0}", value));
+/// Flag a buy by a wallet new to Polymarket and with little history, the
+/// classic insider pattern. `ts` is the trade's Unix time. Age counts from
+/// the wallet's first activity, or from its funding when a Polygon lookup
+/// found that earlier. Wallets with neither are never flagged.
+pub fn fresh_wallet(
+    value: f64,
+    bought_odds: Option<f64>,
+    ts: i64,
+    profile: &WhaleProfile,
+    policy: &FreshWalletPolicy,
+) -> Option<FreshWallet> {
+    let odds = bought_odds?;
+    let age = profile.age_hours(ts)?;
+    let trades = profile.lifetime_trades?;
+    if value < policy.min_value || odds > policy.max_odds || age > policy.max_age_hours as f64 || trades > policy.max_trades {
+        return None;
+    }
+    // A full page of activity means the count is a floor, not the wallet's history
+    if trades >= ACTIVITY_LIMIT {
+        return None;
     }
 
-    if price > 0.90 && size > 50000.0 {
-        anomalies.push("High conviction in likely outcome".to_string());
+    let age_text = if age < 48.0 { format!("{:.0}h", age) } else { format!("{:.0} days", age / 24.0) };
+    let since = if profile.aged_from_funding() { "funded" } else { "first active" };
+    Some(FreshWallet {
+        severity: if age <= FRESH_HIGH_HOURS || trades <= 1 { Severity::High } else { Severity::Medium },
+        age_hours: (age * 10.0).round() / 10.0,
+        lifetime_trades: trades,
+        reason: format!(
+            "wallet {} {} ago, {} trade{} in its history, bought ${} at {:.0}% odds",
+            since,
+            age_text,
+            trades,
+            if trades == 1 { "" } else { "s" },
+            format_number(value as u64),
+            odds * 100.0
+        ),
+    })
+}
+
+pub fn print_anomalies(anomalies: &[Anomaly]) {
+    if anomalies.is_empty() {
+        return;
     }
+    sound::play_anomaly_sound();
 
-    if price < 0.20 && value > 50000.0 {
-        anomalies.push(
-            "Significant bet on unlikely outcome - possible hedge or information asymmetry"
-                .to_string(),
-        );
+    println!();
+    println!("{}", "[ANOMALY INDICATORS]".bright_red().bold());
+    for anomaly in anomalies {
+        let label = format!("{} ({:?})", anomaly.code.as_str(), anomaly.severity).to_uppercase();
+        println!("  - {} {}", label.color(anomaly.severity.color()), anomaly.reason.yellow());
     }
+}
+
+pub fn print_fresh_wallet(flag: &FreshWallet) {
+    sound::play_anomaly_sound();
+
+    println!();
+    println!("{}", "[FRESH WALLET]".bright_red().bold());
+    let label = format!("{:?} severity", flag.severity).to_uppercase();
+    println!("  {} {}", label.color(flag.severity.color()), flag.reason.yellow());
+}
 
-    if !anomalies.is_empty() {
-        sound::play_anomaly_sound();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        println!();
-        println!("{}", "[ANOMALY INDICATORS]".bright_red().bold());
-        for anomaly in anomalies {
-            println!("  - {}", anomaly.yellow());
+    fn sums(values: &[f64]) -> BaselineSums {
+        let mut sums = BaselineSums::default();
+        for &v in values {
+            sums.trades += 1;
+            sums.log_sum += v.ln();
+            sums.log_sq_sum += v.ln() * v.ln();
         }
+        sums
+    }
+
+    #[test]
+    fn size_is_judged_against_the_markets_own_trades() {
+        let small: Vec<f64> = (0..30).map(|i| 1_000.0 + 100.0 * (i % 5) as f64).collect();
+        let large: Vec<f64> = small.iter().map(|v| v * 100.0).collect();
+        let baselines = |values: &[f64]| Baselines { market: sums(values), category: None };
+
+        // $50k is huge in a $1k market and ordinary in a $100k one
+        let found = detect_anomalies(50_000.0, Some(0.5), false, None, &baselines(&small));
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].code, found[0].severity), (AnomalyCode::SizeVsMarket, Severity::High));
+        assert!(detect_anomalies(50_000.0, Some(0.5), false, None, &baselines(&large)).is_empty());
+
+        // Too little history to judge, and accumulated value isn't a trade size
+        assert!(detect_anomalies(50_000.0, Some(0.5), false, None, &baselines(&small[..10])).is_empty());
+        assert!(detect_anomalies(50_000.0, Some(0.5), true, None, &baselines(&small)).is_empty());
+    }
+
+    #[test]
+    fn fresh_wallet_needs_a_young_wallet_and_an_outsized_long_shot() {
+        let policy = FreshWalletPolicy::default();
+        let profile = |age_hours: i64, trades: u32| WhaleProfile {
+            wallet_id: "0xa".into(),
+            portfolio_value: None,
+            positions_count: None,
+            leaderboard_rank: None,
+            leaderboard_profit: None,
+            win_rate: None,
+            markets_traded: None,
+            funded_by: None,
+            first_seen_at: Some(-age_hours * 3600),
+            lifetime_trades: Some(trades),
+            funded_at: Some(-age_hours * 3600),
+        };
+
+        let flagged = fresh_wallet(30_000.0, Some(0.2), 0, &profile(5, 2), &policy).unwrap();
+        assert_eq!((flagged.severity, flagged.age_hours, flagged.lifetime_trades), (Severity::High, 5.0, 2));
+        let older = fresh_wallet(30_000.0, Some(0.2), 0, &profile(48, 3), &policy).unwrap();
+        assert_eq!(older.severity, Severity::Medium);
+
+        // Too old, too active, too small, a favorite, or an exit
+        assert!(fresh_wallet(30_000.0, Some(0.2), 0, &profile(100, 2), &policy).is_none());
+        assert!(fresh_wallet(30_000.0, Some(0.2), 0, &profile(5, 40), &policy).is_none());
+        assert!(fresh_wallet(5_000.0, Some(0.2), 0, &profile(5, 2), &policy).is_none());
+        assert!(fresh_wallet(30_000.0, Some(0.8), 0, &profile(5, 2), &policy).is_none());
+        assert!(fresh_wallet(30_000.0, None, 0, &profile(5, 2), &policy).is_none());
+        // Without a funding lookup, age counts from the first activity
+        let unfunded = WhaleProfile { funded_at: None, ..profile(5, 2) };
+        let flagged = fresh_wallet(30_000.0, Some(0.2), 0, &unfunded, &policy).unwrap();
+        assert_eq!(flagged.age_hours, 5.0);
+        assert!(flagged.reason.starts_with("wallet first active 5h ago"));
+        // Funding found well before the first trade makes the wallet older
+        let funded_earlier = WhaleProfile { funded_at: Some(-100 * 3600), ..profile(5, 2) };
+        assert!(fresh_wallet(30_000.0, Some(0.2), 0, &funded_earlier, &policy).is_none());
+        // Neither known
+        let unknown = WhaleProfile { first_seen_at: None, ..unfunded };
+        assert!(fresh_wallet(30_000.0, Some(0.2), 0, &unknown, &policy).is_none());
     }
 }
@@ -1,11 +1,13 @@
 use colored::*;
 
-use crate::alerts::{MarketContext, OrderBookSummary, TopHoldersSummary};
-use crate::platforms::{kalshi, polymarket};
+use crate::alerts::{Accumulation, CoordinatedFlow, ImpactStatus, MarketContext, OrderBookSummary, PriceImpact, TopHoldersSummary};
+use crate::alerts::scoring::{AlertScore, Tier};
+use crate::platforms::{kalshi, NormalizedTrade, Platform};
+use crate::threshold::{LiquidityRatios, SizeBand};
 use crate::types::{self, WhaleReturnScenario};
 use crate::whale_profile::WhaleProfile;
 
-use super::anomaly;
+use super::anomaly::{self, Anomaly};
 use super::sound;
 
 pub fn print_market_context(ctx: &MarketContext) {
@@ -51,11 +53,40 @@ pub fn print_market_context(ctx: &MarketContext) {
     }
 }
 
+/// Print the alert banner in the style of the trade's venue
+pub fn print_trade_alert(
+    trade: &NormalizedTrade,
+    value: f64,
+    wallet_activity: Option<&types::WalletActivity>,
+    band: Option<&SizeBand>,
+    anomalies: &[Anomaly],
+) {
+    match trade.platform {
+        Platform::Polymarket => print_whale_alert(trade.platform.name(), trade, value, wallet_activity, band, anomalies),
+        Platform::Kalshi => print_kalshi_alert(trade, value, wallet_activity, band, anomalies),
+    }
+}
+
+/// Header tagged with the size band's name, in the band's color
+fn band_header(header: ColoredString, band: Option<&SizeBand>) -> ColoredString {
+    match band {
+        Some(band) => {
+            let color = band.color.as_deref().unwrap_or("bright red").replace('_', " ");
+            format!("[{}] {}", band.name.to_uppercase(), &*header)
+                .color(color.as_str())
+                .bold()
+        }
+        None => header,
+    }
+}
+
 pub fn print_whale_alert(
     platform: &str,
-    trade: &polymarket::Trade,
+    trade: &NormalizedTrade,
     value: f64,
     wallet_activity: Option<&types::WalletActivity>,
+    band: Option<&SizeBand>,
+    anomalies: &[Anomaly],
 ) {
     let is_sell = trade.side.to_uppercase() == "SELL";
 
@@ -97,7 +128,7 @@ pub fn print_whale_alert(
         format!("[ALERT] LARGE TRANSACTION DETECTED - {}", platform)
     };
 
-    println!("{}", header.bright_red().bold());
+    println!("{}", band_header(header.bright_red().bold(), band));
     println!("{}", "=".repeat(70).dimmed());
 
     if let Some(ref title) = trade.market_title {
@@ -124,7 +155,7 @@ pub fn print_whale_alert(
     } else {
         println!(
             "Market:     Unknown (ID: {})",
-            &trade.market[..20.min(trade.market.len())]
+            &trade.market_id[..20.min(trade.market_id.len())]
         );
     }
 
@@ -135,6 +166,7 @@ pub fn print_whale_alert(
         format!("${:.2}", value).bright_yellow().bold()
     );
     println!("Contracts:  {:.2} @ ${:.4} each", trade.size, trade.price);
+    print_fills(trade);
     let action_text = if is_sell {
         format!("{} shares", trade.side.to_uppercase()).bright_red()
     } else {
@@ -179,17 +211,105 @@ pub fn print_whale_alert(
         }
     }
 
-    anomaly::detect_anomalies(trade.price, trade.size, value, wallet_activity);
+    anomaly::print_anomalies(anomalies);
 
     println!("Asset ID: {}", trade.asset_id.dimmed());
     println!("{}", "=".repeat(70).dimmed());
     println!();
 }
 
+/// Banner for a wallet building (or unwinding) a position through many smaller trades
+pub fn print_accumulation_alert(trade: &NormalizedTrade, acc: &Accumulation) {
+    sound::play_triple_beep();
+    println!();
+
+    let header = if trade.side.to_uppercase() == "SELL" {
+        format!("[ACCUMULATION] WHALE UNWINDING POSITION - {}", trade.platform.name())
+    } else {
+        format!("[ACCUMULATION] WHALE BUILDING POSITION - {}", trade.platform.name())
+    };
+    println!("{}", header.bright_magenta().bold());
+    println!("{}", "=".repeat(70).dimmed());
+
+    if let Some(ref title) = trade.market_title {
+        println!("Question:   {}", title.bright_white().bold());
+    }
+    if let Some(ref outcome) = trade.outcome {
+        println!("Position:   {} '{}'", trade.side.to_uppercase(), outcome);
+    }
+
+    println!();
+    println!("{}", "ACCUMULATED FLOW".dimmed());
+    println!(
+        "Total:      {}",
+        format!("${:.2}", acc.total_value).bright_yellow().bold()
+    );
+    println!(
+        "Trades:     {} in {} min window (none over the alert threshold alone)",
+        acc.trades, acc.window_minutes
+    );
+    println!("Contracts:  {:.2} @ ${:.4} avg", trade.size, trade.price);
+    println!("From:       {}", acc.first_trade_at);
+    println!("To:         {}", trade.timestamp);
+    if let Some(ref wallet_id) = trade.wallet_id {
+        println!("Wallet:     {}", wallet_id);
+    }
+    println!("{}", "=".repeat(70).dimmed());
+    println!();
+}
+
+pub fn print_coordinated_flow(flow: &CoordinatedFlow) {
+    sound::play_triple_beep();
+    println!();
+    println!(
+        "{}",
+        format!("[COORDINATED FLOW] {} WALLETS ON ONE OUTCOME - {}", flow.wallets.len(), flow.platform)
+            .bright_magenta()
+            .bold()
+    );
+    println!("{}", "=".repeat(70).dimmed());
+    if let Some(ref title) = flow.market_title {
+        println!("Question:   {}", title.bright_white().bold());
+    }
+    if let Some(ref outcome) = flow.outcome {
+        println!("Position:   {} '{}'", flow.action, outcome);
+    }
+    println!(
+        "Combined:   {}",
+        format!("${:.2}", flow.combined_value).bright_yellow().bold()
+    );
+    println!(
+        "Spread:     {}m {:02}s between first and last trade (window {} min)",
+        flow.spread_secs / 60,
+        flow.spread_secs % 60,
+        flow.window_minutes
+    );
+    for wallet in &flow.wallets {
+        println!(
+            "  - {} ${} in {} trade(s), from {}",
+            wallet.wallet_id,
+            format_number(wallet.value as u64),
+            wallet.trades,
+            wallet.first_trade_at
+        );
+    }
+    println!("{}", "=".repeat(70).dimmed());
+    println!();
+}
+
+/// Note when the alert is several fills merged into one order
+fn print_fills(trade: &NormalizedTrade) {
+    if trade.fills > 1 {
+        println!("Fills:      {} (one order, VWAP price)", trade.fills.to_string().bright_white());
+    }
+}
+
 pub fn print_kalshi_alert(
-    trade: &kalshi::Trade,
+    trade: &NormalizedTrade,
     value: f64,
     wallet_activity: Option<&types::WalletActivity>,
+    band: Option<&SizeBand>,
+    anomalies: &[Anomaly],
 ) {
     // Kalshi taker_side is "yes" or "no", never "sell"
     // We cannot detect exits from the public Kalshi trade API
@@ -249,14 +369,17 @@ pub fn print_kalshi_alert(
             .bold()
     };
 
-    println!("{}", header);
+    println!("{}", band_header(header, band));
     println!("{}", "=".repeat(70).dimmed());
 
     if let Some(ref title) = trade.market_title {
         println!("Question:   {}", title.bright_white().bold());
     }
 
-    let bet_details = kalshi::parse_ticker_details(&trade.ticker, &trade.taker_side);
+    let bet_details = trade
+        .outcome
+        .clone()
+        .unwrap_or_else(|| kalshi::parse_ticker_details(&trade.market_id, &trade.side));
     let bet_color = if is_sell {
         bet_details.bright_red().bold()
     } else {
@@ -266,8 +389,8 @@ pub fn print_kalshi_alert(
 
     let direction_text = format!(
         "{} (buying {} outcome)",
-        trade.taker_side.to_uppercase(),
-        trade.taker_side.to_uppercase()
+        trade.side.to_uppercase(),
+        trade.side.to_uppercase()
     );
     println!("Direction:  {}", direction_text.bright_magenta());
 
@@ -279,16 +402,18 @@ pub fn print_kalshi_alert(
     );
     println!(
         "Contracts:  {} @ ${:.2} avg",
-        trade.count,
-        value / trade.count as f64
+        trade.size,
+        value / trade.size
     );
+    print_fills(trade);
     println!(
         "Odds:       YES: {:.1}% | NO: {:.1}%",
-        trade.yes_price, trade.no_price
+        trade.price * 100.0,
+        (1.0 - trade.price) * 100.0
     );
-    println!("Timestamp:  {}", trade.created_time);
+    println!("Timestamp:  {}", trade.timestamp);
     println!();
-    println!("{}", format!("Ticker: {}", trade.ticker).dimmed());
+    println!("{}", format!("Ticker: {}", trade.market_id).dimmed());
 
     if let Some(activity) = wallet_activity {
         println!();
@@ -316,8 +441,7 @@ pub fn print_kalshi_alert(
         }
     }
 
-    let avg_price = (trade.yes_price + trade.no_price) / 2.0;
-    anomaly::detect_anomalies(avg_price / 100.0, trade.count as f64, value, wallet_activity);
+    anomaly::print_anomalies(anomalies);
 
     println!("{}", "=".repeat(70).dimmed());
     println!();
@@ -409,6 +533,47 @@ pub fn print_returning_whale(scenario: &WhaleReturnScenario, platform: &str) {
     }
 }
 
+/// Trade size against the market's, and the relative triggers it fired
+pub fn print_liquidity(ratios: &LiquidityRatios, triggers: &[&str]) {
+    if ratios.is_empty() {
+        return;
+    }
+    println!();
+    println!("{}", "[RELATIVE SIZE]".bright_blue().bold());
+    println!("Share:      {}", ratios.describe());
+    if !triggers.is_empty() {
+        println!(
+            "Triggered:  {} {}",
+            triggers.join(", ").bright_yellow().bold(),
+            "(under the threshold)".dimmed()
+        );
+    }
+}
+
+/// Price impact as of the alert. Follow-ups print their own line when done.
+pub fn print_price_impact(impact: &PriceImpact) {
+    if impact.status == ImpactStatus::Unavailable {
+        return;
+    }
+    let cents = |v: f64| format!("{:.1}¢", v * 100.0);
+    println!();
+    println!("{}", "[PRICE IMPACT]".bright_blue().bold());
+    if let (Some(before), Some(slippage)) = (impact.price_before, impact.slippage) {
+        println!("Before:     {} (last trade)  |  Slippage: {:+.1}¢", cents(before), slippage * 100.0);
+    }
+    if let (Some(mid), Some(moved)) = (impact.mid_at_alert, impact.mid_move) {
+        let moved = format!("{:+.1}¢", moved * 100.0);
+        let moved = if impact.mid_move.unwrap_or_default() >= 0.01 { moved.bright_yellow().bold() } else { moved.normal() };
+        match impact.price_before {
+            Some(_) => println!("Mid now:    {} ({} its way)", cents(mid), moved),
+            None => println!("Mid now:    {} (no recent trade, following moves from here)", cents(mid)),
+        }
+    }
+    if impact.status == ImpactStatus::Measuring {
+        println!("{}", "Following the book to see if the move holds...".dimmed());
+    }
+}
+
 pub fn print_order_book(ob: &OrderBookSummary) {
     println!();
     println!("{}", "[ORDER BOOK]".bright_blue().bold());
@@ -468,6 +633,32 @@ pub fn print_top_holders(th: &TopHoldersSummary) {
     println!("  Top {} control {:.1}% of shares", th.top_holders.len(), top5_pct);
 }
 
+/// `--explain` line for a trade a filter rejected
+pub fn print_rejection(trade: &NormalizedTrade, filter: &str, reason: &str) {
+    println!(
+        "{} {} ${} {} - {}: {}",
+        "[FILTERED]".dimmed(),
+        trade.platform.name(),
+        format_number(trade.value() as u64),
+        trade.market_title.as_deref().unwrap_or(&trade.market_id),
+        filter.yellow(),
+        reason
+    );
+}
+
+pub fn print_score(score: &AlertScore) {
+    println!();
+    let tier = match score.tier {
+        Tier::High => "HIGH".bright_red().bold(),
+        Tier::Medium => "MEDIUM".bright_yellow().bold(),
+        Tier::Low => "LOW".dimmed(),
+    };
+    println!("{} {} ({})", "[SCORE]".bright_cyan().bold(), score.score, tier);
+    for factor in &score.factors {
+        println!("  - {}", factor);
+    }
+}
+
 pub fn print_whale_profile(profile: &WhaleProfile) {
     println!();
     println!("{}", "[WHALE PROFILE]".bright_green().bold());
@@ -522,6 +713,21 @@ pub fn print_whale_profile(profile: &WhaleProfile) {
     if let Some(markets) = profile.markets_traded {
         println!("Markets:      {}", markets);
     }
+
+    if let Some(trades) = profile.lifetime_trades {
+        println!("Trades:       {}", trades);
+    }
+
+    let date = |ts: i64| chrono::DateTime::from_timestamp(ts, 0).map(|dt| dt.format("%Y-%m-%d %H:%M UTC").to_string());
+    if let Some(first_seen) = profile.first_seen_at.and_then(date) {
+        println!("First seen:   {}", first_seen);
+    }
+    if let Some(ref funder) = profile.funded_by {
+        match profile.funded_at.and_then(date) {
+            Some(funded) => println!("Funded:       {} by {}", funded, funder),
+            None => println!("Funded by:    {}", funder),
+        }
+    }
 }
 
 pub fn format_number(n: u64) -> String {
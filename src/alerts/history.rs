@@ -4,8 +4,9 @@ use rusqlite::Connection;
 use super::AlertData;
 use crate::db;
 
-/// Log an alert to the SQLite database
-pub fn log_alert(alert: &AlertData, conn: &Connection) {
+/// Log an alert to the SQLite database. Returns false if this order was already
+/// logged or couldn't be stored.
+pub fn log_alert(alert: &AlertData, conn: &Connection) -> bool {
     let wallet_activity_json = alert.wallet_activity.map(|wa| {
         serde_json::json!({
             "transactions_last_hour": wa.transactions_last_hour,
@@ -31,14 +32,21 @@ pub fn log_alert(alert: &AlertData, conn: &Connection) {
         .to_string()
     });
 
+    let scoring_json = alert.score.and_then(|score| serde_json::to_string(score).ok());
+    let price_impact_json = alert.price_impact.and_then(|impact| serde_json::to_string(impact).ok());
+    let anomalies_json = (!alert.anomalies.is_empty()).then(|| serde_json::to_string(alert.anomalies).ok()).flatten();
+
     db::insert_alert(
         conn,
         alert.platform,
-        alert.alert_type(),
+        alert.trade_id,
+        alert.order_id,
+        &alert.alert_type(),
         &alert.side.to_uppercase(),
         alert.value,
         alert.price,
         alert.size,
+        alert.fill_count,
         alert.market_title,
         None,
         alert.outcome,
@@ -46,7 +54,10 @@ pub fn log_alert(alert: &AlertData, conn: &Connection) {
         alert.timestamp,
         market_context_json.as_deref(),
         wallet_activity_json.as_deref(),
-    );
+        scoring_json.as_deref(),
+        price_impact_json.as_deref(),
+        anomalies_json.as_deref(),
+    )
 }
 
 pub fn show_alert_history(
@@ -108,6 +119,28 @@ pub fn show_alert_history(
                 println!("Outcome: {}", out);
             }
             println!("Action: {} | Value: ${:.2}", action, value);
+            if let Some(scoring) = alert.get("scoring") {
+                let score = scoring.get("score").and_then(|v| v.as_u64()).unwrap_or(0);
+                let tier = scoring.get("tier").and_then(|v| v.as_str()).unwrap_or("low");
+                println!("Score:  {} ({})", score, tier.to_uppercase());
+            }
+            if let Some(impact) = alert
+                .get("price_impact")
+                .and_then(|v| serde_json::from_value::<super::PriceImpact>(v.clone()).ok())
+            {
+                println!("Impact: {}", impact.summary());
+            }
+            if let Some(anomalies) = alert
+                .get("anomalies")
+                .and_then(|v| serde_json::from_value::<Vec<super::anomaly::Anomaly>>(v.clone()).ok())
+            {
+                for anomaly in anomalies {
+                    println!("Anomaly: {} ({:?}) {}", anomaly.code.as_str(), anomaly.severity, anomaly.reason);
+                }
+            }
+            if let Some(fills) = alert.get("fill_count").and_then(|v| v.as_u64()).filter(|&n| n > 1) {
+                println!("Fills:  {}", fills);
+            }
 
             if let Some(wallet_activity) = alert.get("wallet_activity") {
                 if let Some(txns_hour) = wallet_activity
@@ -119,6 +152,13 @@ pub fn show_alert_history(
                     }
                 }
             }
+            if let Some(cluster) = alert
+                .get("wallet_id")
+                .and_then(|v| v.as_str())
+                .and_then(|wallet_id| crate::clusters::cluster_of(conn, wallet_id))
+            {
+                println!("Cluster: {}", cluster.summary());
+            }
 
             println!();
         }
@@ -136,6 +176,52 @@ pub fn show_alert_history(
             "Filter by platform: {} --platform polymarket",
             "wwatcher history".bright_cyan()
         );
+        println!(
+            "Coordinated flows: {} --coordinated",
+            "wwatcher history".bright_cyan()
+        );
+    }
+
+    Ok(())
+}
+
+/// List stored coordinated flows, newest first
+pub fn show_coordinated_flows(
+    limit: usize,
+    platform_filter: &str,
+    as_json: bool,
+    conn: &Connection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let flows = db::query_coordinated_flows(conn, limit, platform_filter)?;
+
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&flows)?);
+        return Ok(());
+    }
+    if flows.is_empty() {
+        println!("No coordinated flows found matching filters.");
+        return Ok(());
+    }
+
+    println!("{}", "COORDINATED FLOWS".bright_cyan().bold());
+    println!("Showing {} most recent flows", flows.len());
+    println!();
+    for (i, flow) in flows.iter().enumerate() {
+        println!(
+            "{}",
+            format!("#{} | {} | {} wallets | ${:.2}", i + 1, flow.platform, flow.wallets.len(), flow.combined_value)
+                .bright_yellow()
+        );
+        println!("Time:   {} to {}", flow.first_trade_at.dimmed(), flow.last_trade_at.dimmed());
+        println!("Market: {}", flow.market_title.as_deref().unwrap_or(&flow.market_id));
+        if let Some(ref outcome) = flow.outcome {
+            println!("Outcome: {}", outcome);
+        }
+        println!("Action: {} | Spread: {}m {:02}s", flow.action, flow.spread_secs / 60, flow.spread_secs % 60);
+        for wallet in &flow.wallets {
+            println!("  - {} ${:.2} ({} trades)", wallet.wallet_id, wallet.value, wallet.trades);
+        }
+        println!();
     }
 
     Ok(())
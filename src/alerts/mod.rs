@@ -1,9 +1,15 @@
 pub mod anomaly;
 pub mod display;
 pub mod history;
+pub mod rules;
+pub mod scoring;
 pub mod sound;
 pub mod webhook;
 
+use serde::{Deserialize, Serialize};
+
+use crate::clusters::WalletCluster;
+use crate::threshold::{LiquidityRatios, SizeBand};
 use crate::types;
 use crate::whale_profile::WhaleProfile;
 
@@ -45,15 +51,173 @@ pub struct TopHolder {
     pub value: f64,
 }
 
+/// Trades behind a `WHALE_ACCUMULATION` alert: one wallet's flow into one
+/// market outcome that crossed the accumulation threshold
+#[derive(Debug, Clone)]
+pub struct Accumulation {
+    /// Trades counted, including the one that crossed the threshold
+    pub trades: u32,
+    pub total_value: f64,
+    pub window_minutes: u64,
+    /// Time of the earliest trade counted
+    pub first_trade_at: String,
+}
+
+/// Several distinct wallets trading the same market outcome, on the same
+/// side, within the coordination window (`COORDINATED_FLOW`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoordinatedFlow {
+    pub platform: String,
+    pub market_id: String,
+    pub market_title: Option<String>,
+    pub outcome: Option<String>,
+    pub action: String,
+    /// Participants in order of their first trade
+    pub wallets: Vec<FlowWallet>,
+    pub combined_value: f64,
+    pub first_trade_at: String,
+    pub last_trade_at: String,
+    /// Seconds between the first and the last trade
+    pub spread_secs: i64,
+    pub window_minutes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowWallet {
+    pub wallet_id: String,
+    /// Value of the wallet's alerted trades in the flow
+    pub value: f64,
+    pub trades: u32,
+    /// Time of the wallet's first trade in the flow
+    pub first_trade_at: String,
+}
+
+/// Webhook payload for a coordinated flow
+pub fn build_flow_payload(flow: &CoordinatedFlow) -> serde_json::Value {
+    serde_json::json!({
+        "platform": flow.platform,
+        "alert_type": "COORDINATED_FLOW",
+        "action": flow.action,
+        "value": flow.combined_value,
+        "timestamp": flow.last_trade_at,
+        "market_title": flow.market_title.as_deref().map(webhook::escape_special_chars),
+        "market_id": flow.market_id,
+        "outcome": flow.outcome.as_deref().map(webhook::escape_special_chars),
+        "coordinated_flow": {
+            "wallet_count": flow.wallets.len(),
+            "combined_value": flow.combined_value,
+            "first_trade_at": flow.first_trade_at,
+            "last_trade_at": flow.last_trade_at,
+            "spread_secs": flow.spread_secs,
+            "window_minutes": flow.window_minutes,
+            "wallets": flow.wallets,
+        },
+    })
+}
+
+/// Whether a trade moved the market. Prices are 0.0-1.0, and moves are in
+/// the trade's direction: positive when a buy pushed the price up or a sell
+/// pushed it down.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceImpact {
+    /// Last trade on the asset before the order, if one was seen recently
+    pub price_before: Option<f64>,
+    /// Average price paid beyond `price_before`
+    pub slippage: Option<f64>,
+    /// Price moves are measured from: `price_before`, or `mid_at_alert`
+    /// when no recent trade was seen
+    #[serde(default)]
+    pub baseline: Option<f64>,
+    /// Order book mid when the alert was enriched, and its move from `baseline`
+    pub mid_at_alert: Option<f64>,
+    pub mid_move: Option<f64>,
+    /// Mid at the last snapshot after the alert, and its move from `baseline`
+    pub mid_after: Option<f64>,
+    pub final_move: Option<f64>,
+    /// Seconds after the alert until the mid gave back half its move
+    pub recovery_secs: Option<u64>,
+    /// Follow-up snapshots taken
+    pub snapshots: u32,
+    pub status: ImpactStatus,
+}
+
+impl PriceImpact {
+    /// One line for the terminal, e.g. "held: mid +4.0¢ at alert, +3.5¢ after; slippage +1.2¢"
+    pub fn summary(&self) -> String {
+        let cents = |v: Option<f64>| v.map(|v| format!("{:+.1}¢", v * 100.0)).unwrap_or_else(|| "?".into());
+        let mut line = match self.status {
+            ImpactStatus::Measuring => format!("measuring: mid {} at alert", cents(self.mid_move)),
+            ImpactStatus::Held => format!(
+                "held: mid {} at alert, {} after",
+                cents(self.mid_move),
+                cents(self.final_move)
+            ),
+            ImpactStatus::Reverted => format!(
+                "reverted after {}s: mid {} at alert, {} after",
+                self.recovery_secs.unwrap_or_default(),
+                cents(self.mid_move),
+                cents(self.final_move)
+            ),
+            ImpactStatus::NoMove => "no move".into(),
+            ImpactStatus::Unavailable => "unavailable".into(),
+        };
+        if self.slippage.is_some() {
+            line.push_str(&format!("; slippage {}", cents(self.slippage)));
+        }
+        line
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImpactStatus {
+    /// Follow-up snapshots still to come
+    Measuring,
+    /// The move lasted the whole window
+    Held,
+    /// The mid gave back half the move
+    Reverted,
+    /// Under a cent of movement
+    NoMove,
+    /// No order book to measure from
+    Unavailable,
+}
+
+/// How a filter treated an alert that went out
+#[derive(Debug, Clone, Serialize)]
+pub struct FilterDecision {
+    pub filter: String,
+    /// "passed", or "skipped" when the filter doesn't apply to the trade
+    pub decision: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl FilterDecision {
+    pub fn passed(filter: impl Into<String>, detail: Option<String>) -> Self {
+        Self { filter: filter.into(), decision: "passed", detail }
+    }
+
+    pub fn skipped(filter: impl Into<String>, detail: String) -> Self {
+        Self { filter: filter.into(), decision: "skipped", detail: Some(detail) }
+    }
+}
+
 /// Shared alert data structure used by webhook, logging, and display
 pub struct AlertData<'a> {
     pub platform: &'a str,
+    /// Platform trade ID
+    pub trade_id: Option<&'a str>,
+    /// Order identity (`NormalizedTrade::order_id`), the alert's dedup key
+    pub order_id: Option<&'a str>,
     pub market_title: Option<&'a str>,
     pub outcome: Option<&'a str>,
     pub side: &'a str,
     pub value: f64,
     pub price: f64,
     pub size: f64,
+    /// Fills merged into this order
+    pub fill_count: u32,
     pub timestamp: &'a str,
     pub wallet_id: Option<&'a str>,
     pub wallet_activity: Option<&'a types::WalletActivity>,
@@ -61,6 +225,26 @@ pub struct AlertData<'a> {
     pub whale_profile: Option<&'a WhaleProfile>,
     pub order_book: Option<&'a OrderBookSummary>,
     pub top_holders: Option<&'a TopHoldersSummary>,
+    /// Set on accumulation alerts
+    pub accumulation: Option<&'a Accumulation>,
+    pub score: Option<&'a scoring::AlertScore>,
+    /// Filters the alert went through, in order
+    pub filter_decisions: &'a [FilterDecision],
+    /// Threshold the trade was held to
+    pub threshold: Option<u64>,
+    /// Size band the trade falls in, if any are configured
+    pub band: Option<&'a SizeBand>,
+    /// Trade size against the market's
+    pub liquidity: Option<&'a LiquidityRatios>,
+    /// Relative triggers that let the trade through under its threshold
+    pub relative_triggers: &'a [&'a str],
+    pub price_impact: Option<&'a PriceImpact>,
+    /// What makes the trade unusual for its market, most severe first
+    pub anomalies: &'a [anomaly::Anomaly],
+    /// Set when a freshly funded wallet made an outsized long-shot buy
+    pub fresh_wallet: Option<&'a anomaly::FreshWallet>,
+    /// Cluster of linked wallets the trader's wallet belongs to
+    pub wallet_cluster: Option<&'a WalletCluster>,
 }
 
 impl<'a> AlertData<'a> {
@@ -68,8 +252,22 @@ impl<'a> AlertData<'a> {
         self.side.to_uppercase() == "SELL"
     }
 
-    pub fn alert_type(&self) -> &'static str {
-        if self.is_sell() { "WHALE_EXIT" } else { "WHALE_ENTRY" }
+    /// `WHALE_ENTRY`/`WHALE_EXIT`, with the size band's name in place of
+    /// `WHALE` when the trade is in one, `WHALE_ACCUMULATION`, or
+    /// `FRESH_WALLET_ENTRY` for a flagged fresh wallet's buy
+    pub fn alert_type(&self) -> String {
+        if self.accumulation.is_some() {
+            return "WHALE_ACCUMULATION".into();
+        }
+        if self.fresh_wallet.is_some() {
+            return "FRESH_WALLET_ENTRY".into();
+        }
+        let prefix = self.band.map(SizeBand::alert_prefix).unwrap_or_else(|| "WHALE".into());
+        if self.is_sell() {
+            format!("{}_EXIT", prefix)
+        } else {
+            format!("{}_ENTRY", prefix)
+        }
     }
 }
 
@@ -97,11 +295,49 @@ pub fn build_alert_payload(alert: &AlertData, escape_text: bool) -> serde_json::
         "price": alert.price,
         "price_percent": (alert.price * 100.0).round() as i32,
         "size": alert.size,
+        "fill_count": alert.fill_count,
         "timestamp": alert.timestamp,
         "market_title": market_title,
         "outcome": outcome,
     });
 
+    if let Some(trade_id) = alert.trade_id {
+        payload["trade_id"] = json!(trade_id);
+    }
+    if let Some(order_id) = alert.order_id {
+        payload["order_id"] = json!(order_id);
+    }
+
+    if let Some(threshold) = alert.threshold {
+        payload["threshold"] = json!(threshold);
+    }
+
+    if let Some(band) = alert.band {
+        payload["band"] = json!(band.name);
+    }
+
+    if let Some(liquidity) = alert.liquidity.filter(|l| !l.is_empty()) {
+        payload["liquidity_ratios"] = json!(liquidity);
+    }
+
+    if !alert.relative_triggers.is_empty() {
+        payload["relative_triggers"] = json!(alert.relative_triggers);
+    }
+
+    if let Some(impact) = alert.price_impact {
+        payload["price_impact"] = json!(impact);
+    }
+
+    if !alert.anomalies.is_empty() {
+        payload["anomalies"] = json!(alert.anomalies);
+    }
+    if let Some(flag) = alert.fresh_wallet {
+        payload["fresh_wallet"] = json!(flag);
+    }
+    if let Some(cluster) = alert.wallet_cluster {
+        payload["wallet_cluster"] = json!(cluster);
+    }
+
     if let Some(wallet) = alert.wallet_id {
         payload["wallet_id"] = json!(wallet);
     }
@@ -117,6 +353,23 @@ pub fn build_alert_payload(alert: &AlertData, escape_text: bool) -> serde_json::
         });
     }
 
+    if let Some(acc) = alert.accumulation {
+        payload["accumulation"] = json!({
+            "trades": acc.trades,
+            "total_value": acc.total_value,
+            "window_minutes": acc.window_minutes,
+            "first_trade_at": acc.first_trade_at,
+        });
+    }
+
+    if let Some(score) = alert.score {
+        payload["scoring"] = json!(score);
+    }
+
+    if !alert.filter_decisions.is_empty() {
+        payload["filter_decisions"] = json!(alert.filter_decisions);
+    }
+
     if let Some(ctx) = alert.market_context {
         payload["market_context"] = json!({
             "yes_price": ctx.yes_price,
@@ -138,6 +391,11 @@ pub fn build_alert_payload(alert: &AlertData, escape_text: bool) -> serde_json::
         if let Some(w) = wp.win_rate { wp_json["win_rate"] = json!(w); }
         if let Some(m) = wp.markets_traded { wp_json["markets_traded"] = json!(m); }
         if let Some(c) = wp.positions_count { wp_json["positions_count"] = json!(c); }
+        if let Some(ref f) = wp.funded_by { wp_json["funded_by"] = json!(f); }
+        let rfc3339 = |ts: i64| chrono::DateTime::from_timestamp(ts, 0).map(|dt| dt.to_rfc3339());
+        if let Some(t) = wp.first_seen_at.and_then(rfc3339) { wp_json["first_seen_at"] = json!(t); }
+        if let Some(n) = wp.lifetime_trades { wp_json["lifetime_trades"] = json!(n); }
+        if let Some(t) = wp.funded_at.and_then(rfc3339) { wp_json["funded_at"] = json!(t); }
         payload["whale_profile"] = wp_json;
     }
 
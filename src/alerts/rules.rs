@@ -0,0 +1,256 @@
+//! Declarative alert filters from the `filters` config list, checked against
+//! each enriched, scored alert before it is displayed, stored or sent.
+//!
+//! A rule is a JSON object with one condition, or a combination of them:
+//!
+//! ```json
+//! { "name": "proven buyers", "all": [
+//!     { "directions": ["buy"] },
+//!     { "any": [{ "max_leaderboard_rank": 100 }, { "min_win_rate": 0.65 }] }
+//! ] }
+//! ```
+//!
+//! Conditions match the MCP server's `check_preferences`: one on whale
+//! profile data fails when the alert has no profile.
+
+use serde::{Deserialize, Serialize};
+
+use super::scoring::Tier;
+use super::{AlertData, MarketContext};
+
+/// A named rule from config. An alert must pass every rule to go out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterRule {
+    pub name: String,
+    #[serde(flatten)]
+    pub rule: Rule,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// Every rule passes
+    All(Vec<Rule>),
+    /// At least one rule passes
+    Any(Vec<Rule>),
+    /// The rule fails
+    Not(Box<Rule>),
+    /// Trade value in USD is at least this
+    MinValue(f64),
+    /// Whale win rate (0.0-1.0) is at least this
+    MinWinRate(f64),
+    /// Whale leaderboard rank is this or better
+    MaxLeaderboardRank(u32),
+    /// The side being bought trades at or below these odds. Exits always pass.
+    MaxOdds(f64),
+    /// Platform is one of these
+    Platforms(Vec<String>),
+    /// Market has one of these tags
+    Categories(Vec<String>),
+    /// Action is one of these (`buy`, `sell`; Kalshi: `yes`, `no`)
+    Directions(Vec<String>),
+    /// Alert tier is at least this
+    TierFilter(Tier),
+}
+
+impl FilterRule {
+    /// `Err` says why the alert failed the rule
+    pub fn check(&self, alert: &AlertData) -> Result<(), String> {
+        self.rule.check(alert)
+    }
+}
+
+/// Name of the first rule the alert fails, and why
+pub fn first_rejection<'r>(rules: &'r [FilterRule], alert: &AlertData) -> Option<(&'r str, String)> {
+    rules
+        .iter()
+        .find_map(|r| r.check(alert).err().map(|reason| (r.name.as_str(), reason)))
+}
+
+impl Rule {
+    pub fn check(&self, alert: &AlertData) -> Result<(), String> {
+        match self {
+            Rule::All(rules) => rules.iter().try_for_each(|r| r.check(alert)),
+            Rule::Any(rules) => {
+                let mut reasons = Vec::new();
+                for rule in rules {
+                    match rule.check(alert) {
+                        Ok(()) => return Ok(()),
+                        Err(reason) => reasons.push(reason),
+                    }
+                }
+                Err(format!("none of: {}", reasons.join("; ")))
+            }
+            Rule::Not(rule) => match rule.check(alert) {
+                Ok(()) => Err(format!("matched excluded rule {}", rule.describe())),
+                Err(_) => Ok(()),
+            },
+            Rule::MinValue(min) => require(alert.value >= *min, || {
+                format!("value ${:.0} under ${:.0}", alert.value, min)
+            }),
+            Rule::MinWinRate(min) => match alert.whale_profile.and_then(|wp| wp.win_rate) {
+                Some(rate) => require(rate >= *min, || {
+                    format!("win rate {:.0}% under {:.0}%", rate * 100.0, min * 100.0)
+                }),
+                None => Err("win rate unknown".into()),
+            },
+            Rule::MaxLeaderboardRank(max) => match alert.whale_profile.and_then(|wp| wp.leaderboard_rank) {
+                Some(rank) => require(rank <= *max, || format!("leaderboard rank #{} below #{}", rank, max)),
+                None => Err("not on the leaderboard".into()),
+            },
+            Rule::MaxOdds(max) => match bought_side_price(alert.side, alert.outcome, alert.price, alert.market_context) {
+                Some(odds) => require(odds <= *max, || {
+                    format!("side bought at {:.0}% odds, over {:.0}%", odds * 100.0, max * 100.0)
+                }),
+                None => Ok(()),
+            },
+            Rule::Platforms(platforms) => require(
+                platforms.iter().any(|p| p.eq_ignore_ascii_case(alert.platform)),
+                || format!("platform {} not in [{}]", alert.platform, platforms.join(", ")),
+            ),
+            Rule::Categories(categories) => {
+                let tags = alert.market_context.map(|ctx| ctx.tags.as_slice()).unwrap_or_default();
+                require(
+                    categories.iter().any(|c| tags.iter().any(|t| t.eq_ignore_ascii_case(c))),
+                    || format!("market tags [{}] not in [{}]", tags.join(", "), categories.join(", ")),
+                )
+            }
+            Rule::Directions(directions) => require(
+                directions.iter().any(|d| d.eq_ignore_ascii_case(alert.side)),
+                || format!("action {} not in [{}]", alert.side.to_uppercase(), directions.join(", ")),
+            ),
+            Rule::TierFilter(min) => match alert.score {
+                Some(score) => require(tier_rank(score.tier) >= tier_rank(*min), || {
+                    format!("tier {} under {}", score.tier, min)
+                }),
+                None => Err("not scored".into()),
+            },
+        }
+    }
+
+    /// Short form for explanations
+    fn describe(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+fn require(ok: bool, reason: impl FnOnce() -> String) -> Result<(), String> {
+    if ok { Ok(()) } else { Err(reason()) }
+}
+
+fn tier_rank(tier: Tier) -> u8 {
+    match tier {
+        Tier::Low => 0,
+        Tier::Medium => 1,
+        Tier::High => 2,
+    }
+}
+
+/// Current odds of the side a trade takes, or `None` for an exit (SELL).
+/// Uses the market's YES/NO prices when the side is one of them, otherwise
+/// the price the trade paid.
+pub fn bought_side_price(side: &str, outcome: Option<&str>, price: f64, ctx: Option<&MarketContext>) -> Option<f64> {
+    if side.eq_ignore_ascii_case("SELL") {
+        return None;
+    }
+    // Kalshi sides are YES/NO; Polymarket buys name the outcome
+    let bought = if side.eq_ignore_ascii_case("BUY") { outcome.unwrap_or_default() } else { side };
+    match ctx {
+        Some(ctx) if bought.eq_ignore_ascii_case("yes") => Some(ctx.yes_price),
+        Some(ctx) if bought.eq_ignore_ascii_case("no") => Some(ctx.no_price),
+        // Kalshi prices are the YES price whichever side was taken
+        _ if side.eq_ignore_ascii_case("NO") => Some(1.0 - price),
+        _ => Some(price),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::scoring::AlertScore;
+
+    fn alert<'a>(side: &'a str, score: &'a AlertScore) -> AlertData<'a> {
+        AlertData {
+            platform: "Polymarket",
+            trade_id: None,
+            order_id: None,
+            market_title: None,
+            outcome: Some("Yes"),
+            side,
+            value: 80_000.0,
+            price: 0.9,
+            size: 88_888.0,
+            fill_count: 1,
+            timestamp: "2026-10-01T12:00:00Z",
+            wallet_id: None,
+            wallet_activity: None,
+            market_context: None,
+            whale_profile: None,
+            order_book: None,
+            top_holders: None,
+            accumulation: None,
+            score: Some(score),
+            filter_decisions: &[],
+            threshold: None,
+            band: None,
+            liquidity: None,
+            relative_triggers: &[],
+            price_impact: None,
+            anomalies: &[],
+            fresh_wallet: None,
+            wallet_cluster: None,
+        }
+    }
+
+    #[test]
+    fn parses_named_rules_and_explains_rejections() {
+        let rules: Vec<FilterRule> = serde_json::from_str(
+            r#"[
+                {"name": "sizable", "min_value": 50000},
+                {"name": "proven or big", "any": [{"min_win_rate": 0.6}, {"min_value": 100000}]},
+                {"name": "no near-certainties", "max_odds": 0.85},
+                {"name": "medium tier and up", "tier_filter": "medium"},
+                {"name": "not Kalshi", "not": {"platforms": ["kalshi"]}}
+            ]"#,
+        )
+        .unwrap();
+        let score = AlertScore { score: 40, tier: Tier::Medium, factors: vec![] };
+
+        let (name, reason) = first_rejection(&rules, &alert("BUY", &score)).unwrap();
+        assert_eq!(name, "proven or big");
+        assert_eq!(reason, "none of: win rate unknown; value $80000 under $100000");
+
+        // Odds don't apply to an exit, and the other rules pass
+        let rules = &rules[2..];
+        assert!(first_rejection(rules, &alert("SELL", &score)).is_none());
+        let (name, _) = first_rejection(rules, &alert("BUY", &score)).unwrap();
+        assert_eq!(name, "no near-certainties");
+
+        let low = AlertScore { score: 10, tier: Tier::Low, factors: vec![] };
+        let (name, _) = first_rejection(rules, &alert("SELL", &low)).unwrap();
+        assert_eq!(name, "medium tier and up");
+    }
+
+    #[test]
+    fn odds_are_those_of_the_side_taken() {
+        let ctx = MarketContext {
+            yes_price: 0.95,
+            no_price: 0.05,
+            spread: 0.01,
+            volume_24h: 0.0,
+            open_interest: 0.0,
+            price_change_24h: 0.0,
+            liquidity: 0.0,
+            tags: vec![],
+        };
+        // A contrarian buy of the 5% side is checked against 5%, not 95%
+        assert_eq!(bought_side_price("BUY", Some("No"), 0.05, Some(&ctx)), Some(0.05));
+        assert_eq!(bought_side_price("BUY", Some("Yes"), 0.95, Some(&ctx)), Some(0.95));
+        assert_eq!(bought_side_price("SELL", Some("Yes"), 0.95, Some(&ctx)), None);
+        // Kalshi: side is YES/NO, trade price is always the YES price
+        assert_eq!(bought_side_price("NO", Some("BTC above 100k"), 0.95, Some(&ctx)), Some(0.05));
+        assert!((bought_side_price("NO", None, 0.9, None).unwrap() - 0.1).abs() < 1e-9);
+        // Outcomes other than Yes/No fall back to the price paid
+        assert_eq!(bought_side_price("BUY", Some("Lakers"), 0.4, Some(&ctx)), Some(0.4));
+    }
+}
@@ -0,0 +1,274 @@
+//! Alert scoring: how significant an alert is, from the whale's track record,
+//! the trade itself and the market around it.
+//!
+//! Same factors and default points as the MCP server's `scoreAlert`
+//! (`integration/src/scoring/scorer.ts`), scaled by the configured weights.
+
+use serde::{Deserialize, Serialize};
+
+use super::AlertData;
+use crate::config::ScoringWeights;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    High,
+    Medium,
+    Low,
+}
+
+impl Tier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Tier::High => "high",
+            Tier::Medium => "medium",
+            Tier::Low => "low",
+        }
+    }
+}
+
+impl std::fmt::Display for Tier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertScore {
+    pub score: u32,
+    pub tier: Tier,
+    /// Why the alert scored what it did, one line per factor
+    pub factors: Vec<String>,
+}
+
+/// Running total of weighted points and the factors behind them
+struct Tally {
+    points: f64,
+    factors: Vec<String>,
+}
+
+impl Tally {
+    fn add(&mut self, weight: f64, points: f64, factor: String) {
+        if weight > 0.0 {
+            self.points += weight * points;
+            self.factors.push(factor);
+        }
+    }
+}
+
+pub fn score_alert(alert: &AlertData, weights: &ScoringWeights) -> AlertScore {
+    let mut tally = Tally {
+        points: 0.0,
+        factors: Vec::new(),
+    };
+
+    // Whale profile
+    if let Some(wp) = alert.whale_profile {
+        if let Some(rank) = wp.leaderboard_rank {
+            let points = match rank {
+                0..=10 => Some((30.0, "Top 10")),
+                11..=50 => Some((25.0, "Top 50")),
+                51..=100 => Some((20.0, "Top 100")),
+                101..=500 => Some((10.0, "Top 500")),
+                _ => None,
+            };
+            if let Some((points, label)) = points {
+                tally.add(weights.leaderboard_rank, points, format!("{} leaderboard trader (#{})", label, rank));
+            }
+        }
+
+        if let Some(win_rate) = wp.win_rate {
+            let points = if win_rate >= 0.8 {
+                Some((20.0, "Elite"))
+            } else if win_rate >= 0.7 {
+                Some((15.0, "Strong"))
+            } else if win_rate >= 0.6 {
+                Some((10.0, "Above average"))
+            } else {
+                None
+            };
+            if let Some((points, label)) = points {
+                tally.add(weights.win_rate, points, format!("{} win rate ({:.0}%)", label, win_rate * 100.0));
+            }
+        }
+
+        if let Some(portfolio) = wp.portfolio_value {
+            if portfolio >= 1_000_000.0 {
+                tally.add(weights.portfolio, 10.0, format!("Large portfolio (${})", format_value(portfolio)));
+            } else if portfolio >= 500_000.0 {
+                tally.add(weights.portfolio, 5.0, format!("Mid-size portfolio (${})", format_value(portfolio)));
+            }
+        }
+    }
+
+    // Wallet activity
+    if let Some(activity) = alert.wallet_activity {
+        if activity.is_heavy_actor {
+            tally.add(
+                weights.wallet_activity,
+                15.0,
+                format!("Heavy actor ({} txns/24h)", activity.transactions_last_day),
+            );
+        } else if activity.is_repeat_actor {
+            tally.add(
+                weights.wallet_activity,
+                10.0,
+                format!("Repeat actor ({} txns/1h)", activity.transactions_last_hour),
+            );
+        }
+    }
+
+    // Trade size
+    let value = alert.value;
+    let (points, factor) = if value >= 250_000.0 {
+        (20.0, format!("Massive trade (${})", format_value(value)))
+    } else if value >= 100_000.0 {
+        (15.0, format!("Large trade (${})", format_value(value)))
+    } else if value >= 50_000.0 {
+        (10.0, format!("Significant trade (${})", format_value(value)))
+    } else {
+        (5.0, format!("Trade size: ${}", format_value(value)))
+    };
+    tally.add(weights.trade_size, points, factor);
+
+    // Order book imbalance
+    if let Some(ob) = alert.order_book {
+        let total_depth = ob.bid_depth_10pct + ob.ask_depth_10pct;
+        if total_depth > 0.0 {
+            let bid_share = ob.bid_depth_10pct / total_depth;
+            if bid_share >= 0.65 {
+                tally.add(weights.order_book, 10.0, format!("Strong bid pressure ({:.0}% bid)", bid_share * 100.0));
+            } else if bid_share <= 0.35 {
+                tally.add(
+                    weights.order_book,
+                    10.0,
+                    format!("Strong ask pressure ({:.0}% ask)", (1.0 - bid_share) * 100.0),
+                );
+            }
+        }
+    }
+
+    // Contrarian: buying the side the market thinks is unlikely
+    if let Some(ctx) = alert.market_context {
+        let outcome = alert.outcome.map(str::to_lowercase);
+        let buying = alert.side.eq_ignore_ascii_case("BUY");
+        let yes_pct = ctx.yes_price * 100.0;
+        if buying && outcome.as_deref() == Some("no") && ctx.yes_price > 0.6 {
+            tally.add(weights.contrarian, 15.0, format!("Contrarian: buying NO when YES is at {:.0}%", yes_pct));
+        } else if buying && outcome.as_deref() == Some("yes") && ctx.yes_price < 0.4 {
+            tally.add(weights.contrarian, 15.0, format!("Contrarian: buying YES when YES is at {:.0}%", yes_pct));
+        }
+    }
+
+    let score = tally.points.round().max(0.0) as u32;
+    let tier = if score >= weights.high_tier {
+        Tier::High
+    } else if score >= weights.medium_tier {
+        Tier::Medium
+    } else {
+        Tier::Low
+    };
+
+    AlertScore {
+        score,
+        tier,
+        factors: tally.factors,
+    }
+}
+
+fn format_value(v: f64) -> String {
+    if v >= 1_000_000.0 {
+        format!("{:.1}M", v / 1_000_000.0)
+    } else if v >= 1_000.0 {
+        format!("{:.0}k", v / 1_000.0)
+    } else {
+        format!("{:.0}", v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::MarketContext;
+    use crate::whale_profile::WhaleProfile;
+
+    fn alert<'a>(profile: Option<&'a WhaleProfile>, ctx: Option<&'a MarketContext>) -> AlertData<'a> {
+        AlertData {
+            platform: "Polymarket",
+            trade_id: None,
+            order_id: None,
+            market_title: None,
+            outcome: Some("No"),
+            side: "BUY",
+            value: 120_000.0,
+            price: 0.3,
+            size: 400_000.0,
+            fill_count: 1,
+            timestamp: "2026-10-01T12:00:00Z",
+            wallet_id: None,
+            wallet_activity: None,
+            market_context: ctx,
+            whale_profile: profile,
+            order_book: None,
+            top_holders: None,
+            accumulation: None,
+            score: None,
+            filter_decisions: &[],
+            threshold: None,
+            band: None,
+            liquidity: None,
+            relative_triggers: &[],
+            price_impact: None,
+            anomalies: &[],
+            fresh_wallet: None,
+            wallet_cluster: None,
+        }
+    }
+
+    #[test]
+    fn matches_mcp_scorer_and_applies_weights() {
+        let profile = WhaleProfile {
+            wallet_id: "0xw".into(),
+            portfolio_value: Some(2_000_000.0),
+            positions_count: None,
+            leaderboard_rank: Some(42),
+            leaderboard_profit: None,
+            win_rate: Some(0.72),
+            markets_traded: None,
+            funded_by: None,
+            first_seen_at: None,
+            lifetime_trades: None,
+            funded_at: None,
+        };
+        let ctx = MarketContext {
+            yes_price: 0.7,
+            no_price: 0.3,
+            spread: 0.01,
+            volume_24h: 0.0,
+            open_interest: 0.0,
+            price_change_24h: 0.0,
+            liquidity: 0.0,
+            tags: vec![],
+        };
+
+        // 25 rank + 15 win rate + 10 portfolio + 15 size + 15 contrarian
+        let scored = score_alert(&alert(Some(&profile), Some(&ctx)), &ScoringWeights::default());
+        assert_eq!(scored.score, 80);
+        assert_eq!(scored.tier, Tier::High);
+        assert_eq!(scored.factors[0], "Top 50 leaderboard trader (#42)");
+        assert!(scored.factors.contains(&"Contrarian: buying NO when YES is at 70%".to_string()));
+
+        let weights = ScoringWeights {
+            leaderboard_rank: 0.0,
+            contrarian: 0.5,
+            ..ScoringWeights::default()
+        };
+        let scored = score_alert(&alert(Some(&profile), Some(&ctx)), &weights);
+        assert_eq!(scored.score, 48);
+        assert_eq!(scored.tier, Tier::Medium);
+        assert_eq!(scored.factors.len(), 4);
+
+        let scored = score_alert(&alert(None, None), &ScoringWeights::default());
+        assert_eq!((scored.score, scored.tier), (15, Tier::Low));
+    }
+}
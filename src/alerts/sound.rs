@@ -18,6 +18,11 @@ pub fn play_triple_beep() {
 }
 
 fn play_sound_internal(_sound_file: &str) {
+    // A replay can fire hours of alerts in seconds
+    if crate::capture::replayer().is_some() {
+        return;
+    }
+
     #[cfg(target_os = "macos")]
     {
         std::process::Command::new("afplay")
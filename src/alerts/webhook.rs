@@ -17,16 +17,20 @@ pub fn escape_special_chars(s: &str) -> String {
         .join(" ")
 }
 
-pub async fn send_webhook_alert(webhook_url: &str, alert: &AlertData<'_>) {
+pub async fn send_webhook_alert(webhook_url: &str, alert: &AlertData<'_>) -> bool {
     let payload = super::build_alert_payload(alert, true);
+    send_webhook_payload(webhook_url, &payload).await
+}
 
+/// POST an already-built alert payload. Returns true on a 2xx response.
+pub async fn send_webhook_payload(webhook_url: &str, payload: &serde_json::Value) -> bool {
     let client = reqwest::Client::builder()
         .danger_accept_invalid_certs(true)
         .timeout(std::time::Duration::from_secs(5))
         .build()
         .unwrap();
 
-    match client.post(webhook_url).json(&payload).send().await {
+    match client.post(webhook_url).json(payload).send().await {
         Ok(response) => {
             if !response.status().is_success() {
                 eprintln!(
@@ -34,10 +38,13 @@ pub async fn send_webhook_alert(webhook_url: &str, alert: &AlertData<'_>) {
                     "[WEBHOOK ERROR]".red(),
                     response.status()
                 );
+                return false;
             }
+            true
         }
         Err(e) => {
             eprintln!("{} Failed to send webhook: {}", "[WEBHOOK ERROR]".red(), e);
+            false
         }
     }
 }
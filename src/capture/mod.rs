@@ -0,0 +1,179 @@
+//! Record-and-replay of raw API traffic.
+//!
+//! `wwatcher watch --record <dir>` writes every HTTP response and WebSocket
+//! frame to `<dir>/events.jsonl`. `wwatcher replay <dir>` feeds them back
+//! through the same pipeline on a virtual clock, with no network.
+//!
+//! Code that needs the time of day should use [`now`] rather than
+//! `chrono::Utc::now()`, and tokio's `Instant` rather than std's, so that a
+//! replay sees the same clock the recording did.
+
+pub mod record;
+pub mod replay;
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const EVENTS_FILE: &str = "events.jsonl";
+pub const CAPTURE_VERSION: u32 = 1;
+
+/// Settings a recording was made with, so a replay can use the same ones
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// When the recording started (RFC3339); the replay clock starts here
+    pub started_at: String,
+    pub threshold: u64,
+    pub interval: u64,
+    /// Effective config, with credentials and the webhook URL removed
+    pub config: Config,
+}
+
+/// One captured event, a line of `events.jsonl`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureEvent {
+    /// Milliseconds since the recording started
+    pub t_ms: u64,
+    /// Wall-clock time of the event, for people reading the capture
+    pub at: String,
+    #[serde(flatten)]
+    pub record: Record,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Record {
+    /// An HTTP request and how it ended
+    Http(HttpExchange),
+    /// A WebSocket listener was started for `source`
+    WsOpened { source: String },
+    /// A WebSocket connection (or reconnection) was established
+    WsConnected { source: String },
+    /// A text frame received from the socket
+    WsText { source: String, text: String },
+}
+
+/// Outcome of one HTTP request: a body on success, otherwise the status
+/// (0 when no response arrived) and an error message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpExchange {
+    pub url: String,
+    /// Body of a POST, which tells apart requests to one URL (JSON-RPC calls)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A WebSocket event handed back to a listener during replay
+#[derive(Debug, Clone)]
+pub enum WsFrame {
+    Connected,
+    Text(String),
+}
+
+/// Maps the process clock onto wall-clock time. While recording or
+/// replaying, all time of day is derived from tokio's clock so the two agree.
+struct Clock {
+    started: tokio::time::Instant,
+    started_at: DateTime<Utc>,
+}
+
+impl Clock {
+    fn at(&self, instant: tokio::time::Instant) -> DateTime<Utc> {
+        let elapsed = instant.saturating_duration_since(self.started);
+        self.started_at + chrono::Duration::from_std(elapsed).unwrap_or_default()
+    }
+
+    fn offset_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+}
+
+enum Session {
+    Record(record::Recorder),
+    Replay(replay::Replayer),
+}
+
+static SESSION: OnceLock<Session> = OnceLock::new();
+
+pub fn recorder() -> Option<&'static record::Recorder> {
+    match SESSION.get() {
+        Some(Session::Record(r)) => Some(r),
+        _ => None,
+    }
+}
+
+pub fn replayer() -> Option<&'static replay::Replayer> {
+    match SESSION.get() {
+        Some(Session::Replay(r)) => Some(r),
+        _ => None,
+    }
+}
+
+fn clock() -> Option<&'static Clock> {
+    match SESSION.get()? {
+        Session::Record(r) => Some(&r.clock),
+        Session::Replay(r) => Some(&r.clock),
+    }
+}
+
+/// Current time of day: the capture clock while recording or replaying, otherwise the system clock
+pub fn now() -> DateTime<Utc> {
+    match clock() {
+        Some(clock) => clock.at(tokio::time::Instant::now()),
+        None => Utc::now(),
+    }
+}
+
+/// Sleep until `offset` after the start of the recording being replayed
+pub async fn sleep_until_offset(offset: Duration) {
+    if let Some(clock) = clock() {
+        tokio::time::sleep_until(clock.started + offset).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_round_trip_as_flat_json_lines() {
+        let event = CaptureEvent {
+            t_ms: 1500,
+            at: "2026-10-01T12:00:01.500Z".into(),
+            record: Record::Http(HttpExchange {
+                url: "https://clob.polymarket.com/book?token_id=1".into(),
+                request_body: None,
+                status: 429,
+                body: None,
+                retry_after_secs: Some(3),
+                error: Some("rate limited".into()),
+            }),
+        };
+
+        let line = serde_json::to_string(&event).unwrap();
+        assert!(line.contains(r#""kind":"http""#));
+        assert!(line.contains(r#""status":429"#));
+        assert!(!line.contains("body"));
+
+        let parsed: CaptureEvent = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.t_ms, 1500);
+        assert!(matches!(parsed.record, Record::Http(ref h) if h.retry_after_secs == Some(3)));
+
+        let ws: CaptureEvent =
+            serde_json::from_str(r#"{"t_ms":0,"at":"x","kind":"ws_text","source":"kalshi","text":"{}"}"#).unwrap();
+        assert!(matches!(ws.record, Record::WsText { ref source, .. } if source == "kalshi"));
+    }
+}
@@ -0,0 +1,76 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::{CaptureEvent, Clock, HttpExchange, Manifest, Record, Session, EVENTS_FILE, MANIFEST_FILE, SESSION};
+
+/// Appends captured traffic to `events.jsonl`, one event per line
+pub struct Recorder {
+    pub(super) clock: Clock,
+    out: Mutex<BufWriter<File>>,
+}
+
+/// Start recording into `dir`. Must be called before any API traffic.
+pub fn start(dir: &Path, manifest: &Manifest) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(MANIFEST_FILE), serde_json::to_string_pretty(manifest)?)?;
+    let file = File::create(dir.join(EVENTS_FILE))?;
+
+    let started_at = chrono::DateTime::parse_from_rfc3339(&manifest.started_at)?.with_timezone(&chrono::Utc);
+    let recorder = Recorder {
+        clock: Clock {
+            started: tokio::time::Instant::now(),
+            started_at,
+        },
+        out: Mutex::new(BufWriter::new(file)),
+    };
+
+    SESSION
+        .set(Session::Record(recorder))
+        .map_err(|_| "a capture session is already running")?;
+    Ok(())
+}
+
+impl Recorder {
+    fn write(&self, record: Record) {
+        let t_ms = self.clock.offset_ms();
+        let event = CaptureEvent {
+            t_ms,
+            at: super::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            record,
+        };
+
+        let Ok(line) = serde_json::to_string(&event) else {
+            return;
+        };
+        let mut out = self.out.lock().unwrap();
+        // Flush every event so a crash or Ctrl-C keeps everything up to it
+        if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+            eprintln!("Warning: Failed to write capture event");
+        }
+    }
+
+    pub fn http(&self, exchange: HttpExchange) {
+        self.write(Record::Http(exchange));
+    }
+
+    pub fn ws_opened(&self, source: &str) {
+        self.write(Record::WsOpened {
+            source: source.to_string(),
+        });
+    }
+
+    pub fn ws_connected(&self, source: &str) {
+        self.write(Record::WsConnected {
+            source: source.to_string(),
+        });
+    }
+
+    pub fn ws_text(&self, source: &str, text: &str) {
+        self.write(Record::WsText {
+            source: source.to_string(),
+            text: text.to_string(),
+        });
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::{CaptureEvent, Clock, HttpExchange, Manifest, Record, Session, WsFrame, EVENTS_FILE, MANIFEST_FILE, SESSION};
+
+/// Serves recorded traffic back in the order it was captured
+pub struct Replayer {
+    pub(super) clock: Clock,
+    http: Mutex<HttpResponses>,
+    ws: HashMap<String, Vec<(Duration, WsFrame)>>,
+    /// Offset of the last captured event
+    end: Duration,
+}
+
+/// A request as responses are looked up by: its URL and any body
+type RequestKey = (String, Option<String>);
+
+/// Recorded HTTP responses per request
+#[derive(Default)]
+struct HttpResponses {
+    /// Unserved responses, oldest first
+    queued: HashMap<RequestKey, VecDeque<HttpExchange>>,
+    /// Last response served, reused once its queue runs out
+    last: HashMap<RequestKey, HttpExchange>,
+}
+
+impl HttpResponses {
+    fn push(&mut self, exchange: HttpExchange) {
+        let key = (exchange.url.clone(), exchange.request_body.clone());
+        self.queued.entry(key).or_default().push_back(exchange);
+    }
+
+    fn next(&mut self, url: &str, body: Option<&str>) -> Option<HttpExchange> {
+        let key = (url.to_string(), body.map(str::to_string));
+        match self.queued.get_mut(&key).and_then(VecDeque::pop_front) {
+            Some(exchange) => {
+                self.last.insert(key, exchange.clone());
+                Some(exchange)
+            }
+            None => self.last.get(&key).cloned(),
+        }
+    }
+}
+
+/// Read a capture directory's manifest
+pub fn load_manifest(dir: &Path) -> Result<Manifest, Box<dyn std::error::Error>> {
+    let path = dir.join(MANIFEST_FILE);
+    let json = fs::read_to_string(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let manifest: Manifest = serde_json::from_str(&json)?;
+    if manifest.version != super::CAPTURE_VERSION {
+        return Err(format!(
+            "Unsupported capture version {} (expected {})",
+            manifest.version,
+            super::CAPTURE_VERSION
+        )
+        .into());
+    }
+    Ok(manifest)
+}
+
+/// Start replaying the capture in `dir`, with the virtual clock at the
+/// manifest's start time. Must be called on a paused tokio clock.
+pub fn start(dir: &Path, manifest: &Manifest) -> Result<&'static Replayer, Box<dyn std::error::Error>> {
+    let started_at = chrono::DateTime::parse_from_rfc3339(&manifest.started_at)?.with_timezone(&chrono::Utc);
+    let text = fs::read_to_string(dir.join(EVENTS_FILE))?;
+
+    let mut http = HttpResponses::default();
+    let mut ws: HashMap<String, Vec<(Duration, WsFrame)>> = HashMap::new();
+    let mut end = Duration::ZERO;
+
+    for (line_no, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: CaptureEvent =
+            serde_json::from_str(line).map_err(|e| format!("{} line {}: {}", EVENTS_FILE, line_no + 1, e))?;
+        let at = Duration::from_millis(event.t_ms);
+        end = end.max(at);
+
+        match event.record {
+            Record::Http(exchange) => http.push(exchange),
+            Record::WsOpened { source } => {
+                ws.entry(source).or_default();
+            }
+            Record::WsConnected { source } => ws.entry(source).or_default().push((at, WsFrame::Connected)),
+            Record::WsText { source, text } => ws.entry(source).or_default().push((at, WsFrame::Text(text))),
+        }
+    }
+
+    let replayer = Replayer {
+        clock: Clock {
+            started: tokio::time::Instant::now(),
+            started_at,
+        },
+        http: Mutex::new(http),
+        ws,
+        end,
+    };
+
+    SESSION
+        .set(Session::Replay(replayer))
+        .map_err(|_| "a capture session is already running")?;
+    Ok(super::replayer().expect("replay session just started"))
+}
+
+impl Replayer {
+    /// Next recorded response for a request to `url` with `body`. Once they
+    /// run out the last one is served again; `None` if the request was never
+    /// made while recording.
+    pub fn http(&self, url: &str, body: Option<&str>) -> Option<HttpExchange> {
+        self.http.lock().unwrap().next(url, body)
+    }
+
+    /// Recorded socket events for `source`, or `None` if it had no listener
+    pub fn ws_frames(&self, source: &str) -> Option<Vec<(Duration, WsFrame)>> {
+        self.ws.get(source).cloned()
+    }
+
+    /// Instant of the last captured event on the virtual clock
+    pub fn end(&self) -> tokio::time::Instant {
+        self.clock.started + self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(body: &str, response: &str) -> HttpExchange {
+        HttpExchange {
+            url: "https://polygon.example".into(),
+            request_body: Some(body.into()),
+            status: 200,
+            body: Some(response.into()),
+            retry_after_secs: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn posts_to_one_url_are_served_by_request_body() {
+        let mut http = HttpResponses::default();
+        http.push(exchange("wallet a", "funded by x"));
+        http.push(exchange("wallet b", "funded by y"));
+
+        let url = "https://polygon.example";
+        // Asked for in another order than recorded, each still gets its own response
+        assert_eq!(http.next(url, Some("wallet b")).unwrap().body.as_deref(), Some("funded by y"));
+        assert_eq!(http.next(url, Some("wallet a")).unwrap().body.as_deref(), Some("funded by x"));
+        assert_eq!(http.next(url, Some("wallet a")).unwrap().body.as_deref(), Some("funded by x"));
+        assert!(http.next(url, Some("wallet c")).is_none());
+        assert!(http.next(url, None).is_none());
+    }
+}
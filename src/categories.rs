@@ -1,5 +1,5 @@
-/// Market category system with keyword matching for Polymarket
-/// and series ticker mapping for Kalshi.
+//! Market category system with keyword matching for Polymarket
+//! and series ticker mapping for Kalshi.
 
 use std::collections::HashMap;
 
@@ -0,0 +1,753 @@
+//! Wallet clusters: Polymarket proxy wallets that look like one trader.
+//!
+//! Two wallets are linked when their trade history (the `alerts` table, plus
+//! `wallet_memory` rows no stored alert matches) shows at least two of:
+//! - lockstep timing: trades on the same outcome and side within `LOCKSTEP_SECS`
+//! - the same market sequence: the markets they traded, in the same order
+//! - mirrored sizes: near-identical trade values on the same outcome and side
+//!
+//! or when both got their first USDC from the same address (with a Polygon
+//! RPC endpoint set). Linked wallets form clusters; a cluster's ID comes from
+//! its lowest wallet address, so it changes only when a lower one joins.
+//!
+//! A new alert relinks its wallet, off the alert sink in `pipeline::link`, and
+//! reassigns only the clusters whose links changed; `relink_all` rebuilds
+//! everything.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use colored::*;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+
+/// Trades on the same outcome and side this close together are in lockstep
+const LOCKSTEP_SECS: i64 = 120;
+/// Lockstep trade pairs, or markets with mirrored sizes, needed for the signal
+const MIN_SHARED: usize = 2;
+/// Values within this share of each other are mirrored
+const MIRROR_SHARE: f64 = 0.05;
+/// Markets a shared sequence needs, and its share of the shorter history
+const MIN_SEQUENCE: usize = 3;
+const SEQUENCE_SHARE: f64 = 0.8;
+/// An address that funded more tracked wallets than this is an exchange or
+/// bridge, not an owner
+const MAX_FUNDED: i64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkReason {
+    Lockstep,
+    Sequence,
+    MirroredSizes,
+    SharedFunder,
+}
+
+impl LinkReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LinkReason::Lockstep => "lockstep",
+            LinkReason::Sequence => "sequence",
+            LinkReason::MirroredSizes => "mirrored_sizes",
+            LinkReason::SharedFunder => "shared_funder",
+        }
+    }
+}
+
+/// What an alert shows about its wallet's cluster
+#[derive(Debug, Clone, Serialize)]
+pub struct WalletCluster {
+    pub id: String,
+    pub wallets: u32,
+    /// Value of every stored alert of the cluster's wallets, in USD
+    pub combined_value: f64,
+}
+
+impl WalletCluster {
+    pub fn summary(&self) -> String {
+        format!(
+            "part of cluster {} ({} wallets, ${:.0} combined)",
+            self.id, self.wallets, self.combined_value
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClusterMember {
+    pub wallet_id: String,
+    pub alerts: u32,
+    pub value: f64,
+    pub funded_by: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WalletLink {
+    pub wallet_a: String,
+    pub wallet_b: String,
+    pub reasons: Vec<LinkReason>,
+}
+
+struct WalletTrade {
+    /// Market title and outcome
+    market: String,
+    action: String,
+    ts: i64,
+    value: f64,
+}
+
+fn wallet_trades(conn: &Connection, wallet_id: &str) -> Vec<WalletTrade> {
+    let mut trades: Vec<WalletTrade> = conn
+        .prepare_cached(
+            "SELECT COALESCE(market_title, '') || '|' || COALESCE(outcome, ''), action, timestamp, value
+             FROM alerts WHERE wallet_id = ?1",
+        )
+        .and_then(|mut stmt| {
+            let rows = stmt.query_map(params![wallet_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, f64>(3)?))
+            })?;
+            Ok(rows
+                .flatten()
+                .filter_map(|(market, action, timestamp, value)| {
+                    let ts = chrono::DateTime::parse_from_rfc3339(&timestamp).ok()?.timestamp();
+                    Some(WalletTrade { market, action, ts, value })
+                })
+                .collect())
+        })
+        .unwrap_or_default();
+    trades.extend(memory_trades(conn, wallet_id));
+    trades.sort_by_key(|t| t.ts);
+    trades
+}
+
+/// Wallet memory trades the alert history doesn't also hold
+fn memory_trades(conn: &Connection, wallet_id: &str) -> Vec<WalletTrade> {
+    conn.prepare_cached(
+        "SELECT COALESCE(m.market_title, '') || '|' || COALESCE(m.outcome, ''), m.action, m.seen_at, m.value
+         FROM wallet_memory m
+         WHERE m.wallet_hash = ?1 AND NOT EXISTS (
+             SELECT 1 FROM alerts a
+             WHERE a.wallet_id = m.wallet_id AND a.market_title IS m.market_title AND a.outcome IS m.outcome
+               AND a.action = m.action AND a.value = m.value
+         )",
+    )
+    .and_then(|mut stmt| {
+        let rows = stmt.query_map(params![db::wallet_hash(wallet_id)], |row| {
+            Ok(WalletTrade {
+                market: row.get(0)?,
+                action: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                ts: row.get(2)?,
+                value: row.get(3)?,
+            })
+        })?;
+        Ok(rows.flatten().collect())
+    })
+    .unwrap_or_default()
+}
+
+/// Trade pairs on the same outcome and side within `LOCKSTEP_SECS`, each trade used once
+fn lockstep_pairs(a: &[WalletTrade], b: &[WalletTrade]) -> usize {
+    let mut used = vec![false; b.len()];
+    let mut pairs = 0;
+    for ta in a {
+        let matched = b.iter().enumerate().find(|(i, tb)| {
+            !used[*i] && tb.market == ta.market && tb.action == ta.action && (tb.ts - ta.ts).abs() <= LOCKSTEP_SECS
+        });
+        if let Some((i, _)) = matched {
+            used[i] = true;
+            pairs += 1;
+        }
+    }
+    pairs
+}
+
+/// Markets on which both wallets made a trade of near-identical value
+fn mirrored_markets(a: &[WalletTrade], b: &[WalletTrade]) -> usize {
+    let mut markets: Vec<&str> = a
+        .iter()
+        .filter(|ta| {
+            b.iter().any(|tb| {
+                tb.market == ta.market
+                    && tb.action == ta.action
+                    && (tb.value - ta.value).abs() <= MIRROR_SHARE * tb.value.max(ta.value)
+            })
+        })
+        .map(|t| t.market.as_str())
+        .collect();
+    markets.sort_unstable();
+    markets.dedup();
+    markets.len()
+}
+
+/// Markets in the order first traded
+fn market_sequence(trades: &[WalletTrade]) -> Vec<&str> {
+    let mut sequence: Vec<&str> = Vec::new();
+    for trade in trades {
+        if !sequence.contains(&trade.market.as_str()) {
+            sequence.push(&trade.market);
+        }
+    }
+    sequence
+}
+
+/// Longest common subsequence length
+fn common_sequence(a: &[&str], b: &[&str]) -> usize {
+    let mut row = vec![0usize; b.len() + 1];
+    for x in a {
+        let mut diagonal = 0;
+        for (j, y) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if x == y { diagonal + 1 } else { above.max(row[j]) };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Behavioral reasons to link two wallets
+fn compare(a: &[WalletTrade], b: &[WalletTrade]) -> Vec<LinkReason> {
+    let mut reasons = Vec::new();
+    if lockstep_pairs(a, b) >= MIN_SHARED {
+        reasons.push(LinkReason::Lockstep);
+    }
+    let (seq_a, seq_b) = (market_sequence(a), market_sequence(b));
+    let shared = common_sequence(&seq_a, &seq_b);
+    if shared >= MIN_SEQUENCE && shared as f64 >= SEQUENCE_SHARE * seq_a.len().min(seq_b.len()) as f64 {
+        reasons.push(LinkReason::Sequence);
+    }
+    if mirrored_markets(a, b) >= MIN_SHARED {
+        reasons.push(LinkReason::MirroredSizes);
+    }
+    reasons
+}
+
+fn funder_of(conn: &Connection, wallet_id: &str) -> Option<String> {
+    conn.query_row("SELECT funder FROM wallet_funding WHERE wallet_id = ?1", params![wallet_id], |row| row.get(0))
+        .ok()
+}
+
+/// Remember where a wallet's first USDC came from
+pub fn record_funder(conn: &Connection, wallet_id: &str, funder: &str) {
+    if let Err(e) = conn.execute(
+        "INSERT OR REPLACE INTO wallet_funding (wallet_id, funder) VALUES (?1, ?2)",
+        params![wallet_id, funder],
+    ) {
+        eprintln!("Warning: Failed to record wallet funding: {}", e);
+    }
+}
+
+/// Other wallets funded by the same address, unless it funds too many to mean anything
+fn funded_alongside(conn: &Connection, wallet_id: &str) -> Vec<String> {
+    let Some(funder) = funder_of(conn, wallet_id) else {
+        return Vec::new();
+    };
+    let funded: i64 = conn
+        .query_row("SELECT COUNT(*) FROM wallet_funding WHERE funder = ?1", params![funder], |row| row.get(0))
+        .unwrap_or(0);
+    if funded > MAX_FUNDED {
+        return Vec::new();
+    }
+    conn.prepare("SELECT wallet_id FROM wallet_funding WHERE funder = ?1 AND wallet_id != ?2")
+        .and_then(|mut stmt| {
+            let rows = stmt.query_map(params![funder, wallet_id], |row| row.get(0))?;
+            Ok(rows.flatten().collect())
+        })
+        .unwrap_or_default()
+}
+
+/// Wallets with an alert or wallet memory on any outcome the wallet (`?1`,
+/// hashed `?2`) has traded
+const SAME_MARKETS_SQL: &str = "
+    WITH traded AS (
+        SELECT market_title, outcome FROM alerts WHERE wallet_id = ?1
+        UNION SELECT market_title, outcome FROM wallet_memory WHERE wallet_hash = ?2
+    )
+    SELECT a.wallet_id FROM traded w
+    JOIN alerts a ON a.market_title = w.market_title AND a.outcome = w.outcome
+    WHERE a.wallet_id IS NOT NULL AND a.wallet_id != ?1
+    UNION
+    SELECT m.wallet_id FROM traded w
+    JOIN wallet_memory m ON m.market_title = w.market_title AND m.outcome = w.outcome
+    WHERE m.wallet_id != ?1";
+
+fn wallets_on_same_markets(conn: &Connection, wallet_id: &str) -> Vec<String> {
+    conn.prepare_cached(SAME_MARKETS_SQL)
+        .and_then(|mut stmt| {
+            let rows = stmt.query_map(params![wallet_id, db::wallet_hash(wallet_id)], |row| row.get(0))?;
+            Ok(rows.flatten().collect())
+        })
+        .unwrap_or_default()
+}
+
+/// Re-evaluate a wallet's links against every wallet it could be linked to.
+/// Returns the wallets whose link to it was added, changed or removed.
+pub fn link_wallet(conn: &Connection, wallet_id: &str) -> Vec<String> {
+    let trades = wallet_trades(conn, wallet_id);
+    let funded = funded_alongside(conn, wallet_id);
+
+    let mut candidates = wallets_on_same_markets(conn, wallet_id);
+    candidates.extend(funded.iter().cloned());
+    candidates.extend(linked_wallets(conn, wallet_id));
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    let mut changed = Vec::new();
+    for other in candidates {
+        let mut reasons = compare(&trades, &wallet_trades(conn, &other));
+        if reasons.len() < 2 {
+            reasons.clear();
+        }
+        if funded.contains(&other) {
+            reasons.push(LinkReason::SharedFunder);
+        }
+        if store_link(conn, wallet_id, &other, &reasons) {
+            changed.push(other);
+        }
+    }
+    changed
+}
+
+fn linked_wallets(conn: &Connection, wallet_id: &str) -> Vec<String> {
+    conn.prepare_cached(
+        "SELECT wallet_b FROM wallet_links WHERE wallet_a = ?1
+         UNION SELECT wallet_a FROM wallet_links WHERE wallet_b = ?1",
+    )
+    .and_then(|mut stmt| {
+        let rows = stmt.query_map(params![wallet_id], |row| row.get(0))?;
+        Ok(rows.flatten().collect())
+    })
+    .unwrap_or_default()
+}
+
+/// Store (or with no reasons, remove) the link between two wallets.
+/// Returns true if it changed.
+fn store_link(conn: &Connection, a: &str, b: &str, reasons: &[LinkReason]) -> bool {
+    let (a, b) = if a < b { (a, b) } else { (b, a) };
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT reasons FROM wallet_links WHERE wallet_a = ?1 AND wallet_b = ?2",
+            params![a, b],
+            |row| row.get(0),
+        )
+        .ok();
+    let reasons_json = serde_json::to_string(reasons).unwrap_or_default();
+    let result = match (reasons.is_empty(), stored.as_deref()) {
+        (true, None) => return false,
+        (false, Some(s)) if s == reasons_json => return false,
+        (true, Some(_)) => conn.execute(
+            "DELETE FROM wallet_links WHERE wallet_a = ?1 AND wallet_b = ?2",
+            params![a, b],
+        ),
+        (false, _) => conn.execute(
+            "INSERT OR REPLACE INTO wallet_links (wallet_a, wallet_b, reasons) VALUES (?1, ?2, ?3)",
+            params![a, b, reasons_json],
+        ),
+    };
+    match result {
+        Ok(_) => true,
+        Err(e) => {
+            eprintln!("Warning: Failed to store wallet link: {}", e);
+            false
+        }
+    }
+}
+
+/// Wallets in alert history whose funding source hasn't been looked up
+pub fn unfunded_wallets(conn: &Connection) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let wallets = conn
+        .prepare(
+            "SELECT DISTINCT wallet_id FROM alerts
+             WHERE wallet_id IS NOT NULL AND wallet_id NOT IN (SELECT wallet_id FROM wallet_funding)",
+        )?
+        .query_map([], |row| row.get(0))?
+        .flatten()
+        .collect();
+    Ok(wallets)
+}
+
+/// Link every wallet in alert history and wallet memory from scratch, then rebuild clusters
+pub fn relink_all(conn: &Connection) -> Result<usize, Box<dyn std::error::Error>> {
+    let wallets: Vec<String> = conn
+        .prepare(
+            "SELECT wallet_id FROM alerts WHERE wallet_id IS NOT NULL
+             UNION SELECT wallet_id FROM wallet_memory",
+        )?
+        .query_map([], |row| row.get(0))?
+        .flatten()
+        .collect();
+    conn.execute("DELETE FROM wallet_links", [])?;
+    for wallet in &wallets {
+        link_wallet(conn, wallet);
+    }
+    rebuild_clusters(conn)?;
+    Ok(wallets.len())
+}
+
+fn cluster_id_for(root: &str) -> String {
+    format!("C-{}", &db::wallet_hash(&root.to_lowercase())[..8])
+}
+
+fn cluster_members(conn: &Connection, wallet_id: &str) -> Vec<String> {
+    conn.prepare_cached(
+        "SELECT wallet_id FROM wallet_clusters
+         WHERE cluster_id = (SELECT cluster_id FROM wallet_clusters WHERE wallet_id = ?1)",
+    )
+    .and_then(|mut stmt| {
+        let rows = stmt.query_map(params![wallet_id], |row| row.get(0))?;
+        Ok(rows.flatten().collect())
+    })
+    .unwrap_or_default()
+}
+
+/// Every wallet reachable from `wallet_id` over stored links, itself included
+fn linked_component(conn: &Connection, wallet_id: &str) -> BTreeSet<String> {
+    let mut component = BTreeSet::from([wallet_id.to_string()]);
+    let mut pending = vec![wallet_id.to_string()];
+    while let Some(wallet) = pending.pop() {
+        for other in linked_wallets(conn, &wallet) {
+            if component.insert(other.clone()) {
+                pending.push(other);
+            }
+        }
+    }
+    component
+}
+
+/// Reassign the clusters of `wallets` after their links changed: each one's
+/// old cluster, which a removed link may have split, and whatever it is
+/// linked to now, which an added link may have merged. Other clusters are
+/// left alone.
+fn update_clusters(conn: &Connection, wallets: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut affected: BTreeSet<String> = BTreeSet::new();
+    for wallet in wallets {
+        affected.insert(wallet.clone());
+        affected.extend(cluster_members(conn, wallet));
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    let mut placed: BTreeSet<String> = BTreeSet::new();
+    for wallet in &affected {
+        if placed.contains(wallet) {
+            continue;
+        }
+        let component = linked_component(&tx, wallet);
+        // The lowest address is the root, as in `rebuild_clusters`
+        let id = component.first().map(|root| cluster_id_for(root)).unwrap_or_default();
+        for member in &component {
+            if component.len() > 1 {
+                tx.execute(
+                    "INSERT OR REPLACE INTO wallet_clusters (wallet_id, cluster_id) VALUES (?1, ?2)",
+                    params![member, id],
+                )?;
+            } else {
+                tx.execute("DELETE FROM wallet_clusters WHERE wallet_id = ?1", params![member])?;
+            }
+        }
+        placed.extend(component);
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn find(parent: &mut HashMap<String, String>, wallet: &str) -> String {
+    let mut root = wallet.to_string();
+    while let Some(up) = parent.get(&root).filter(|up| **up != root) {
+        root = up.clone();
+    }
+    parent.insert(wallet.to_string(), root.clone());
+    root
+}
+
+/// Recompute cluster IDs from the stored links
+pub fn rebuild_clusters(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let links: Vec<(String, String)> = conn
+        .prepare("SELECT wallet_a, wallet_b FROM wallet_links")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .flatten()
+        .collect();
+
+    let mut parent: HashMap<String, String> = HashMap::new();
+    for (a, b) in &links {
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        if ra != rb {
+            let (low, high) = if ra < rb { (ra, rb) } else { (rb, ra) };
+            parent.insert(high, low);
+        }
+    }
+
+    let mut clusters: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let wallets: Vec<String> = parent.keys().cloned().collect();
+    for wallet in wallets {
+        let root = find(&mut parent, &wallet);
+        clusters.entry(root).or_default().push(wallet);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM wallet_clusters", [])?;
+    for (root, members) in &clusters {
+        let id = cluster_id_for(root);
+        for wallet in members {
+            tx.execute(
+                "INSERT INTO wallet_clusters (wallet_id, cluster_id) VALUES (?1, ?2)",
+                params![wallet, id],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn cluster_summary(conn: &Connection, cluster_id: &str) -> Option<WalletCluster> {
+    conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM((SELECT SUM(value) FROM alerts a WHERE a.wallet_id = c.wallet_id)), 0)
+         FROM wallet_clusters c WHERE c.cluster_id = ?1",
+        params![cluster_id],
+        |row| {
+            Ok(WalletCluster {
+                id: cluster_id.to_string(),
+                wallets: row.get(0)?,
+                combined_value: row.get(1)?,
+            })
+        },
+    )
+    .ok()
+    .filter(|c| c.wallets > 0)
+}
+
+/// The cluster a wallet is in, if it is linked to any other
+pub fn cluster_of(conn: &Connection, wallet_id: &str) -> Option<WalletCluster> {
+    let id: String = conn
+        .query_row("SELECT cluster_id FROM wallet_clusters WHERE wallet_id = ?1", params![wallet_id], |row| row.get(0))
+        .ok()?;
+    cluster_summary(conn, &id)
+}
+
+/// Update a wallet's links after a new alert
+pub fn observe_alert(conn: &Connection, wallet_id: &str, funded_by: Option<&str>) {
+    if let Some(funder) = funded_by {
+        record_funder(conn, wallet_id, funder);
+    }
+    let mut changed = link_wallet(conn, wallet_id);
+    if !changed.is_empty() {
+        changed.push(wallet_id.to_string());
+        if let Err(e) = update_clusters(conn, &changed) {
+            eprintln!("Warning: Failed to update wallet clusters: {}", e);
+        }
+    }
+}
+
+/// Every cluster, largest combined value first
+pub fn list_clusters(conn: &Connection) -> Result<Vec<WalletCluster>, Box<dyn std::error::Error>> {
+    let ids: Vec<String> = conn
+        .prepare("SELECT DISTINCT cluster_id FROM wallet_clusters")?
+        .query_map([], |row| row.get(0))?
+        .flatten()
+        .collect();
+    let mut clusters: Vec<WalletCluster> = ids.iter().filter_map(|id| cluster_summary(conn, id)).collect();
+    clusters.sort_by(|a, b| b.combined_value.total_cmp(&a.combined_value));
+    Ok(clusters)
+}
+
+/// Members of a cluster and the links between them
+pub fn cluster_detail(
+    conn: &Connection,
+    cluster_id: &str,
+) -> Result<(Vec<ClusterMember>, Vec<WalletLink>), Box<dyn std::error::Error>> {
+    let members: Vec<ClusterMember> = conn
+        .prepare(
+            "SELECT c.wallet_id, COUNT(a.id), COALESCE(SUM(a.value), 0), f.funder
+             FROM wallet_clusters c
+             LEFT JOIN alerts a ON a.wallet_id = c.wallet_id
+             LEFT JOIN wallet_funding f ON f.wallet_id = c.wallet_id
+             WHERE c.cluster_id = ?1
+             GROUP BY c.wallet_id ORDER BY 3 DESC",
+        )?
+        .query_map(params![cluster_id], |row| {
+            Ok(ClusterMember {
+                wallet_id: row.get(0)?,
+                alerts: row.get(1)?,
+                value: row.get(2)?,
+                funded_by: row.get(3)?,
+            })
+        })?
+        .flatten()
+        .collect();
+
+    let links: Vec<WalletLink> = conn
+        .prepare(
+            "SELECT l.wallet_a, l.wallet_b, l.reasons FROM wallet_links l
+             JOIN wallet_clusters c ON c.wallet_id = l.wallet_a
+             WHERE c.cluster_id = ?1 ORDER BY l.wallet_a, l.wallet_b",
+        )?
+        .query_map(params![cluster_id], |row| {
+            let reasons: String = row.get(2)?;
+            Ok(WalletLink {
+                wallet_a: row.get(0)?,
+                wallet_b: row.get(1)?,
+                reasons: serde_json::from_str(&reasons).unwrap_or_default(),
+            })
+        })?
+        .flatten()
+        .collect();
+
+    Ok((members, links))
+}
+
+/// Cluster ID for a cluster ID or member wallet
+pub fn resolve_cluster_id(conn: &Connection, query: &str) -> Option<String> {
+    conn.query_row(
+        "SELECT cluster_id FROM wallet_clusters WHERE cluster_id = ?1 OR LOWER(wallet_id) = LOWER(?1) LIMIT 1",
+        params![query],
+        |row| row.get(0),
+    )
+    .ok()
+}
+
+pub fn print_wallet_cluster(cluster: &WalletCluster) {
+    println!("{} {}", "[WALLET CLUSTER]".bright_magenta().bold(), cluster.summary());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trades(entries: &[(&str, i64, f64)]) -> Vec<WalletTrade> {
+        entries
+            .iter()
+            .map(|&(market, ts, value)| WalletTrade { market: market.into(), action: "BUY".into(), ts, value })
+            .collect()
+    }
+
+    #[test]
+    fn links_need_two_behavioral_signals() {
+        let a = trades(&[("m1", 0, 10_000.0), ("m2", 600, 20_000.0), ("m3", 1200, 15_000.0)]);
+        // Same markets in the same order, a minute behind, with matching sizes
+        let b = trades(&[("m1", 60, 10_200.0), ("m2", 660, 19_900.0), ("m3", 1260, 40_000.0)]);
+        assert_eq!(compare(&a, &b), vec![LinkReason::Lockstep, LinkReason::Sequence, LinkReason::MirroredSizes]);
+
+        // The same markets hours apart and at other sizes is only a shared sequence
+        let c = trades(&[("m1", 9000, 50_000.0), ("m2", 9600, 5_000.0), ("m3", 9900, 70_000.0)]);
+        assert_eq!(compare(&a, &c), vec![LinkReason::Sequence]);
+        assert_eq!(common_sequence(&["m1", "m2", "m3"], &["m2", "m1", "m3"]), 2);
+    }
+
+    fn alert(conn: &Connection, wallet: &str, market: &str, minute: u32, value: f64) {
+        let order_id = format!("{}|{}|{}", wallet, market, minute);
+        let timestamp = format!("2026-10-01T12:{:02}:00Z", minute);
+        db::insert_alert(
+            conn, "Polymarket", Some(&order_id), Some(&order_id), "WHALE_ENTRY", "BUY", value, 0.5, value * 2.0, 1,
+            Some(market), None, Some("Yes"), Some(wallet), &timestamp, None, None, None, None, None,
+        );
+    }
+
+    fn cluster_ids(conn: &Connection) -> Vec<(String, String)> {
+        conn.prepare("SELECT wallet_id, cluster_id FROM wallet_clusters ORDER BY wallet_id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn candidates_come_from_shared_outcomes_through_the_index() {
+        let conn = db::open_in_memory();
+        alert(&conn, "0xa", "m1", 0, 10_000.0);
+        alert(&conn, "0xb", "m1", 5, 50_000.0);
+        alert(&conn, "0xc", "m2", 5, 10_000.0);
+        assert_eq!(wallets_on_same_markets(&conn, "0xa"), vec!["0xb".to_string()]);
+
+        let plan: Vec<String> = conn
+            .prepare(&format!("EXPLAIN QUERY PLAN {}", SAME_MARKETS_SQL))
+            .unwrap()
+            .query_map(params!["0xa", db::wallet_hash("0xa")], |row| row.get(3))
+            .unwrap()
+            .flatten()
+            .collect();
+        assert!(plan.iter().any(|step| step.contains("idx_alerts_market_outcome_wallet")), "{:?}", plan);
+    }
+
+    #[test]
+    fn wallet_memory_trades_link_wallets() {
+        let conn = db::open_in_memory();
+        let mut tracker = crate::types::WalletTracker::new();
+        // 0xa's trades are only in wallet memory; the one 0xb also has as an alert counts once
+        for (minute, market) in [(0, "m1"), (10, "m2"), (20, "m3")] {
+            alert(&conn, "0xb", market, minute + 1, 10_100.0);
+            let seen_at = 1_790_856_000 + minute as i64 * 60;
+            tracker.record_to_db(&conn, "0xa", Some(market), None, Some("Yes"), "BUY", 10_000.0, 0.5, "Polymarket", seen_at);
+            tracker.record_to_db(&conn, "0xb", Some(market), None, Some("Yes"), "BUY", 10_100.0, 0.5, "Polymarket", seen_at + 60);
+        }
+        assert_eq!(wallet_trades(&conn, "0xb").len(), 3);
+        assert_eq!(wallets_on_same_markets(&conn, "0xb"), vec!["0xa".to_string()]);
+
+        observe_alert(&conn, "0xb", None);
+        assert_eq!(cluster_of(&conn, "0xa").unwrap().wallets, 2);
+    }
+
+    #[test]
+    fn alerts_merge_clusters_without_touching_others() {
+        let conn = db::open_in_memory();
+        // An unrelated cluster, linked by a shared funder
+        record_funder(&conn, "0xx", "0xfunder");
+        record_funder(&conn, "0xy", "0xfunder");
+        alert(&conn, "0xx", "m9", 0, 1_000.0);
+        observe_alert(&conn, "0xy", None);
+        assert!(cluster_of(&conn, "0xy").is_some());
+        let unrelated = cluster_ids(&conn);
+
+        // 0xb trades in lockstep with 0xc at mirrored sizes
+        for (minute, market) in [(0, "m1"), (10, "m2"), (20, "m3")] {
+            alert(&conn, "0xc", market, minute, 10_000.0);
+            alert(&conn, "0xb", market, minute + 1, 10_100.0);
+        }
+        observe_alert(&conn, "0xc", None);
+        let cluster = cluster_of(&conn, "0xb").unwrap();
+        assert_eq!(cluster.wallets, 2);
+        assert_eq!(cluster.id, cluster_id_for("0xb"));
+
+        // A lower address joining renames the cluster after it
+        record_funder(&conn, "0xb", "0xshared");
+        alert(&conn, "0xa", "m7", 30, 5_000.0);
+        observe_alert(&conn, "0xa", Some("0xshared"));
+        let cluster = cluster_of(&conn, "0xa").unwrap();
+        assert_eq!(cluster.wallets, 3);
+        assert_eq!(cluster.id, cluster_id_for("0xa"));
+        assert_eq!(cluster_of(&conn, "0xc").unwrap().id, cluster.id);
+
+        let ids = cluster_ids(&conn);
+        assert!(unrelated.iter().all(|pair| ids.contains(pair)));
+        rebuild_clusters(&conn).unwrap();
+        assert_eq!(cluster_ids(&conn), ids);
+    }
+
+    #[test]
+    fn a_removed_link_splits_its_cluster() {
+        let conn = db::open_in_memory();
+        for (a, b) in [("0xa", "0xb"), ("0xb", "0xc"), ("0xc", "0xd")] {
+            store_link(&conn, a, b, &[LinkReason::SharedFunder]);
+        }
+        rebuild_clusters(&conn).unwrap();
+        assert_eq!(cluster_of(&conn, "0xd").unwrap().wallets, 4);
+
+        store_link(&conn, "0xb", "0xc", &[]);
+        update_clusters(&conn, &["0xb".to_string(), "0xc".to_string()]).unwrap();
+        let ids = cluster_ids(&conn);
+        assert_eq!(
+            ids,
+            vec![
+                ("0xa".to_string(), cluster_id_for("0xa")),
+                ("0xb".to_string(), cluster_id_for("0xa")),
+                ("0xc".to_string(), cluster_id_for("0xc")),
+                ("0xd".to_string(), cluster_id_for("0xc")),
+            ]
+        );
+
+        // A wallet left with no links leaves its cluster
+        store_link(&conn, "0xc", "0xd", &[]);
+        update_clusters(&conn, &["0xc".to_string(), "0xd".to_string()]).unwrap();
+        assert!(cluster_of(&conn, "0xd").is_none());
+        rebuild_clusters(&conn).unwrap();
+        assert_eq!(cluster_ids(&conn).len(), 2);
+    }
+}
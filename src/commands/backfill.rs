@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use colored::*;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::display::format_number;
+use crate::alerts::{history, AlertData};
+use crate::capture;
+use crate::categories::CategoryRegistry;
+use crate::db;
+use crate::pipeline::aggregate::{self, FillAggregator};
+use crate::pipeline::enrich::{check_odds_filter, passes_category_filter};
+use crate::pipeline::WatchSettings;
+use crate::platforms::kalshi_auth::KalshiSigner;
+use crate::platforms::{kalshi, polymarket, NormalizedTrade, Platform};
+use crate::types::WalletTracker;
+
+/// The Data API rejects offsets past this, so Polymarket history ends there
+const POLYMARKET_MAX_OFFSET: u32 = 10_000;
+
+/// How far a platform's backfill got, saved to `metadata` after every page
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Progress {
+    since: i64,
+    threshold: u64,
+    /// Polymarket: Data API offset of the next page a resume reads
+    #[serde(default)]
+    offset: u32,
+    /// Kalshi: trades after this time (Unix seconds) are done, up to fills
+    /// held back for the next page
+    #[serde(default)]
+    before: Option<i64>,
+    /// Oldest trade time reached
+    #[serde(default)]
+    oldest: Option<i64>,
+    scanned: u64,
+    stored: u64,
+    complete: bool,
+}
+
+impl Progress {
+    fn key(platform: Platform) -> String {
+        format!("backfill_{}", platform.name().to_lowercase())
+    }
+
+    /// The saved progress for this platform if it was for the same run,
+    /// otherwise a fresh start
+    fn load(conn: &Connection, platform: Platform, since: i64, threshold: u64) -> Self {
+        let saved = db::get_metadata(conn, &Self::key(platform))
+            .and_then(|json| serde_json::from_str::<Progress>(&json).ok());
+
+        match saved {
+            Some(p) if p.since == since && p.threshold == threshold => p,
+            _ => Progress {
+                since,
+                threshold,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn save(&self, conn: &Connection, platform: Platform) {
+        if let Ok(json) = serde_json::to_string(self) {
+            db::set_metadata(conn, &Self::key(platform), &json);
+        }
+    }
+
+    fn reached(&mut self, ts: i64) {
+        self.scanned += 1;
+        self.oldest = Some(self.oldest.map_or(ts, |o| o.min(ts)));
+    }
+
+    /// Overwrite the progress line
+    fn report(&self, platform: Platform) {
+        print!(
+            "\r  {:<11} {} trades scanned, {} stored, back to {}   ",
+            platform.name(),
+            self.scanned,
+            self.stored.to_string().bright_white(),
+            format_day(self.oldest.unwrap_or(self.since))
+        );
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// Filters and storage shared by both platforms
+struct Backfill<'a> {
+    conn: &'a Connection,
+    settings: WatchSettings,
+    wallet_tracker: WalletTracker,
+}
+
+impl Backfill<'_> {
+    /// Apply the watch filters to a whale-sized order and store it. Returns
+    /// false if it was filtered out or is already in the database.
+    fn store(&mut self, trade: &NormalizedTrade, ts: i64) -> bool {
+        if !passes_category_filter(&self.settings, trade) {
+            return false;
+        }
+        // Current odds say nothing about a past trade; its own price does
+        if let Some(ref cfg) = self.settings.config {
+            if check_odds_filter(cfg, trade, None).is_err() {
+                return false;
+            }
+        }
+
+        let platform = trade.platform.name();
+        let value = trade.value();
+        let order_id = trade.order_id();
+        let alert = AlertData {
+            platform,
+            trade_id: Some(&trade.trade_id),
+            order_id: Some(&order_id),
+            market_title: trade.market_title.as_deref(),
+            outcome: trade.outcome.as_deref(),
+            side: &trade.side,
+            value,
+            price: trade.price,
+            size: trade.size,
+            fill_count: trade.fills,
+            timestamp: &trade.timestamp,
+            wallet_id: trade.wallet_id.as_deref(),
+            wallet_activity: None,
+            market_context: None,
+            whale_profile: None,
+            order_book: None,
+            top_holders: None,
+            accumulation: None,
+            score: None,
+            filter_decisions: &[],
+            threshold: Some(self.settings.threshold),
+            band: self.settings.band(value, self.settings.threshold),
+            liquidity: None,
+            relative_triggers: &[],
+            price_impact: None,
+            anomalies: &[],
+            fresh_wallet: None,
+            wallet_cluster: None,
+        };
+        let score = self.settings.score(&alert);
+        let alert = AlertData { score: Some(&score), ..alert };
+        if !history::log_alert(&alert, self.conn) {
+            return false;
+        }
+
+        if let Some(ref wallet_id) = trade.wallet_id {
+            self.wallet_tracker.record_to_db(
+                self.conn,
+                wallet_id,
+                trade.market_title.as_deref(),
+                Some(&trade.asset_id),
+                trade.outcome.as_deref(),
+                &trade.side,
+                value,
+                trade.price,
+                platform,
+                ts,
+            );
+        }
+        true
+    }
+}
+
+/// Page through each platform's trade history back to `since` (YYYY-MM-DD,
+/// UTC), storing qualifying trades as alerts and wallet memory. No webhooks
+/// are sent. Progress is saved after every page, so running the same command
+/// again after an interruption picks up where it stopped.
+pub async fn backfill(
+    since: &str,
+    platform: &str,
+    threshold: u64,
+    conn: &Connection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let since_ts = chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid --since '{}', expected YYYY-MM-DD", since))?
+        .and_hms_opt(0, 0, 0)
+        .map(|dt| dt.and_utc().timestamp())
+        .ok_or("Invalid --since date")?;
+    if since_ts >= capture::now().timestamp() {
+        return Err(format!("--since {} is in the future", since).into());
+    }
+
+    let platforms = match platform.to_lowercase().as_str() {
+        "all" => vec![Platform::Polymarket, Platform::Kalshi],
+        "polymarket" => vec![Platform::Polymarket],
+        "kalshi" => vec![Platform::Kalshi],
+        other => {
+            return Err(format!("Unknown platform '{}' (expected polymarket, kalshi or all)", other).into());
+        }
+    };
+
+    let config = crate::config::load_config().ok();
+    let selected_categories: Vec<String> = config
+        .as_ref()
+        .map(|c| c.categories.clone())
+        .unwrap_or_else(|| vec!["all".into()]);
+
+    println!("{}", "WHALE WATCHER BACKFILL".bright_cyan().bold());
+    println!("Since:      {}", since.bright_white());
+    println!(
+        "Threshold:  {}",
+        format!("${}", format_number(threshold)).bright_green()
+    );
+    if selected_categories.iter().any(|s| s == "all") {
+        println!("Categories: {}", "All markets".bright_green());
+    } else {
+        println!("Categories: {}", selected_categories.join(", ").bright_green());
+    }
+    println!("Webhooks:   {}", "Off".dimmed());
+    println!();
+
+    let kalshi_signer = match config.as_ref().map(KalshiSigner::from_config) {
+        Some(Ok(signer)) => signer,
+        Some(Err(e)) => {
+            eprintln!("{} {}", "[WARN] Kalshi credentials unusable, falling back to public access:".yellow(), e);
+            None
+        }
+        None => None,
+    };
+
+    let mut run = Backfill {
+        conn,
+        settings: WatchSettings {
+            config,
+            threshold,
+            category_registry: CategoryRegistry::new(),
+            selected_categories,
+            explain: false,
+        },
+        wallet_tracker: WalletTracker::new(),
+    };
+
+    for platform in platforms {
+        let mut progress = Progress::load(conn, platform, since_ts, threshold);
+        if progress.complete {
+            println!(
+                "  {:<11} already backfilled to {} ({} stored), skipping",
+                platform.name(),
+                since,
+                progress.stored
+            );
+            continue;
+        }
+        if progress.scanned > 0 {
+            println!(
+                "  {:<11} resuming from {}",
+                platform.name(),
+                format_day(progress.oldest.unwrap_or(since_ts))
+            );
+        }
+
+        let result = match platform {
+            Platform::Polymarket => backfill_polymarket(&mut run, &mut progress).await,
+            Platform::Kalshi => backfill_kalshi(&mut run, &mut progress, kalshi_signer.as_ref()).await,
+        };
+        println!();
+
+        if let Err(e) = result {
+            return Err(format!(
+                "{} backfill stopped: {}. Progress is saved; run the same command again to resume.",
+                platform.name(),
+                e
+            )
+            .into());
+        }
+    }
+
+    println!();
+    println!(
+        "{} {} alerts stored",
+        "Backfill complete:".bright_cyan().bold(),
+        db::alert_count(conn).to_string().bright_white()
+    );
+    Ok(())
+}
+
+/// The Data API has no time filter, so page back by offset until trades
+/// are older than `since`. New trades shift offsets while we page, which
+/// only means some rows are seen twice.
+async fn backfill_polymarket(run: &mut Backfill<'_>, progress: &mut Progress) -> Result<(), Box<dyn std::error::Error>> {
+    let mut offset = progress.offset;
+    // Fills held back from earlier pages, and the offset of the page they
+    // start on, where a resume reads again
+    let mut carried: Vec<NormalizedTrade> = Vec::new();
+    let mut carried_from = None;
+
+    while !progress.complete {
+        if offset >= POLYMARKET_MAX_OFFSET {
+            println!();
+            eprintln!(
+                "{} Polymarket's Data API pages back only {} trades, history stops at {}",
+                "[WARN]".yellow(),
+                POLYMARKET_MAX_OFFSET,
+                format_day(progress.oldest.unwrap_or(progress.since))
+            );
+            progress.complete = true;
+            store_orders(run, progress, std::mem::take(&mut carried));
+            progress.save(run.conn, Platform::Polymarket);
+            break;
+        }
+
+        let min_value = aggregate::min_fill_value(progress.threshold);
+        let page = polymarket::fetch_trades_page(min_value, offset).await?;
+        if page.is_empty() {
+            progress.complete = true;
+        }
+
+        let mut fills = std::mem::take(&mut carried);
+        let mut page_fills = Vec::new();
+        for trade in page.into_iter().map(NormalizedTrade::from) {
+            let Some(ts) = trade.unix_timestamp() else {
+                continue;
+            };
+            // Newest first: everything after this is older still
+            if ts < progress.since {
+                progress.complete = true;
+                break;
+            }
+            progress.reached(ts);
+            page_fills.push(trade);
+        }
+
+        if !progress.complete {
+            carried = aggregate::split_trailing_fills(&mut page_fills);
+        }
+        fills.extend(page_fills);
+        store_orders(run, progress, fills);
+
+        if carried.is_empty() {
+            carried_from = None;
+        } else if carried_from.is_none() {
+            carried_from = Some(offset);
+        }
+        offset += polymarket::TRADES_PAGE_SIZE;
+        progress.offset = carried_from.unwrap_or(offset);
+        progress.save(run.conn, Platform::Polymarket);
+        progress.report(Platform::Polymarket);
+    }
+
+    Ok(())
+}
+
+/// Page back through `/markets/trades` from now (or the resume point) to
+/// `since`. The threshold is applied client-side since Kalshi can't filter by size.
+async fn backfill_kalshi(
+    run: &mut Backfill<'_>,
+    progress: &mut Progress,
+    signer: Option<&KalshiSigner>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Titles and categories by ticker, fetched only for whale-sized trades
+    let mut markets: HashMap<String, Option<(String, Option<String>)>> = HashMap::new();
+    // Cursors are only good for one chain of requests; a resumed run restarts
+    // from `before` instead
+    let mut cursor: Option<String> = None;
+    // Fills held back from earlier pages
+    let mut carried: Vec<NormalizedTrade> = Vec::new();
+
+    while !progress.complete {
+        let (page, next) = kalshi::fetch_trades_page(progress.since, progress.before, cursor.as_deref(), signer).await?;
+
+        let mut oldest_in_page = None;
+        let mut fills = std::mem::take(&mut carried);
+        let mut page_fills = Vec::new();
+        for trade in page.into_iter().map(NormalizedTrade::from) {
+            let Some(ts) = trade.unix_timestamp() else {
+                continue;
+            };
+            oldest_in_page = Some(oldest_in_page.map_or(ts, |o: i64| o.min(ts)));
+            progress.reached(ts);
+            page_fills.push(trade);
+        }
+        if next.is_some() {
+            carried = aggregate::split_trailing_fills(&mut page_fills);
+        }
+        fills.extend(page_fills);
+
+        for mut order in merge_fills(fills) {
+            let Some(ts) = order.unix_timestamp() else {
+                continue;
+            };
+            if order.value() < progress.threshold as f64 {
+                continue;
+            }
+
+            if !markets.contains_key(&order.market_id) {
+                let info = kalshi::fetch_market_info_full(&order.market_id, signer)
+                    .await
+                    .map(|info| (info.title, info.category));
+                markets.insert(order.market_id.clone(), info);
+            }
+            if let Some(Some((title, category))) = markets.get(&order.market_id) {
+                order.market_title = Some(title.clone());
+                order.category = category.clone();
+            }
+
+            if run.store(&order, ts) {
+                progress.stored += 1;
+            }
+        }
+
+        // A resume re-reads the held-back fills (and the oldest second, which
+        // may straddle the page boundary); the duplicates are skipped
+        let resume_at = carried.iter().filter_map(NormalizedTrade::unix_timestamp).max().or(oldest_in_page);
+        if let Some(before) = resume_at {
+            progress.before = Some(before);
+        }
+        match next {
+            Some(c) => cursor = Some(c),
+            None => progress.complete = true,
+        }
+        progress.save(run.conn, Platform::Kalshi);
+        progress.report(Platform::Kalshi);
+    }
+
+    Ok(())
+}
+
+/// Merge Polymarket fills into orders and store those over the threshold
+fn store_orders(run: &mut Backfill<'_>, progress: &mut Progress, fills: Vec<NormalizedTrade>) {
+    for order in merge_fills(fills) {
+        let Some(ts) = order.unix_timestamp() else {
+            continue;
+        };
+        if order.value() >= progress.threshold as f64 && run.store(&order, ts) {
+            progress.stored += 1;
+        }
+    }
+}
+
+/// Merge fills (newest first) into orders, as `watch` does. The fills of the
+/// oldest order on a page are held back with `aggregate::split_trailing_fills`
+/// and merged with the next page, so an order split across pages is stored whole.
+fn merge_fills(page: Vec<NormalizedTrade>) -> Vec<NormalizedTrade> {
+    let mut fills = FillAggregator::default();
+    let now = tokio::time::Instant::now();
+    for fill in page.into_iter().rev() {
+        fills.push(fill, now);
+    }
+    fills.flush_all()
+}
+
+fn format_day(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
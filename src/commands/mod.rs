@@ -1,4 +1,8 @@
+pub mod backfill;
+pub mod performance;
+pub mod replay;
 pub mod setup;
 pub mod status;
 pub mod test;
+pub mod wallet;
 pub mod watch;
@@ -0,0 +1,75 @@
+use colored::*;
+use rusqlite::Connection;
+
+use crate::outcomes::{self, HorizonStats, CHECKPOINTS, RESOLUTION};
+
+/// Wallets listed in the text report; `--json` has them all
+const TOP_WALLETS: usize = 10;
+
+/// Report alert performance by platform, category, tier, size band and wallet
+pub fn show_performance(platform_filter: &str, as_json: bool, conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let report = outcomes::performance(conn, platform_filter)?;
+
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    if report.get("platform").is_none_or(|groups| groups.is_empty()) {
+        println!("No alert outcomes recorded yet.");
+        println!(
+            "Run {} to start following alerted markets.",
+            "wwatcher watch".bright_cyan()
+        );
+        return Ok(());
+    }
+
+    let labels: Vec<&str> = CHECKPOINTS.iter().map(|c| c.label).chain([RESOLUTION]).collect();
+    println!("{}", "ALERT PERFORMANCE".bright_cyan().bold());
+    println!("{}", "Hit rate and average move in the whale's direction, in cents (samples)".dimmed());
+    if platform_filter != "all" {
+        println!("Platform filter: {}", platform_filter);
+    }
+
+    for dimension in outcomes::DIMENSIONS {
+        let Some(groups) = report.get(dimension) else {
+            continue;
+        };
+        println!();
+        let mut header = format!("{:<24} {:>6}", dimension.to_uppercase(), "alerts");
+        for label in &labels {
+            header.push_str(&format!(" {:>22}", label));
+        }
+        println!("{}", header.bright_yellow());
+
+        let shown = if dimension == "wallet" { TOP_WALLETS } else { groups.len() };
+        for group in groups.iter().take(shown) {
+            let mut line = format!("{:<24} {:>6}", truncate(&group.key, 24), group.alerts);
+            for label in &labels {
+                line.push_str(&format!(" {:>22}", group.horizons.get(label).map(format_stats).unwrap_or_default()));
+            }
+            println!("{}", line);
+        }
+        if groups.len() > shown {
+            println!("{}", format!("... {} more (see --json)", groups.len() - shown).dimmed());
+        }
+    }
+
+    Ok(())
+}
+
+fn format_stats(stats: &HorizonStats) -> String {
+    match (stats.hit_rate, stats.avg_move) {
+        (Some(hit_rate), Some(avg_move)) => {
+            format!("{:.0}% {:+.1}¢ ({})", hit_rate * 100.0, avg_move * 100.0, stats.samples)
+        }
+        _ => "-".into(),
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        format!("{}…", text.chars().take(max - 1).collect::<String>())
+    }
+}
@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use colored::*;
+
+use crate::alerts::display::format_number;
+use crate::capture::replay;
+use crate::categories::CategoryRegistry;
+use crate::config;
+use crate::db;
+use crate::pipeline::ingest::IngestOptions;
+use crate::pipeline::{self, WatchSettings};
+use crate::platforms::kalshi::KalshiSource;
+use crate::platforms::polymarket::PolymarketSource;
+use crate::platforms::MarketSource;
+
+/// Database a replay writes to, inside the capture directory
+const REPLAY_DB: &str = "replay.db";
+
+/// Replay a `watch --record` capture through the watch pipeline
+pub fn replay_capture(dir: PathBuf, explain: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // The virtual clock needs a paused current-thread runtime, and a runtime
+    // can't be started from inside main's, so the replay gets its own thread
+    let handle = std::thread::spawn(move || -> Result<(), String> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .start_paused(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        runtime.block_on(run(&dir, explain)).map_err(|e| e.to_string())
+    });
+
+    match handle.join() {
+        Ok(result) => result.map_err(Into::into),
+        Err(_) => Err("replay panicked".into()),
+    }
+}
+
+async fn run(dir: &Path, explain: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = replay::load_manifest(dir)?;
+    config::override_endpoints(manifest.config.endpoints.clone())?;
+    let replayer = replay::start(dir, &manifest)?;
+
+    let mut cfg = manifest.config;
+    // Alerts are kept so later trades dedupe against them, however old the capture is
+    cfg.history_retention_days = 0;
+
+    println!("{}", "WHALE WATCHER REPLAY".bright_cyan().bold());
+    println!("Capture:   {}", dir.display().to_string().bright_white());
+    println!("Recorded:  {}", manifest.started_at);
+    println!(
+        "Duration:  {} seconds",
+        replayer.end().duration_since(tokio::time::Instant::now()).as_secs()
+    );
+    println!(
+        "Threshold: {}",
+        format!("${}", format_number(manifest.threshold)).bright_green()
+    );
+    println!();
+
+    // A fresh database each run, so replays are repeatable
+    let db_path = dir.join(REPLAY_DB);
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", db_path.display(), suffix));
+    }
+    let conn = db::open_db_at(&db_path)?;
+
+    let mut sources: Vec<Arc<dyn MarketSource>> = Vec::new();
+    if cfg.platforms.iter().any(|p| p == "all" || p == "polymarket") {
+        sources.push(Arc::new(PolymarketSource));
+    }
+    if cfg.platforms.iter().any(|p| p == "all" || p == "kalshi") {
+        sources.push(Arc::new(KalshiSource::new(None)));
+    }
+
+    let (accumulation_threshold, accumulation_window_minutes) =
+        (cfg.accumulation_threshold, cfg.accumulation_window_minutes);
+    let threshold_policy = cfg.threshold_policy.clone();
+    let settings = WatchSettings {
+        threshold: manifest.threshold,
+        category_registry: CategoryRegistry::new(),
+        selected_categories: cfg.categories.clone(),
+        config: Some(cfg),
+        explain,
+    };
+
+    pipeline::run(
+        sources,
+        settings,
+        IngestOptions {
+            threshold: manifest.threshold,
+            threshold_policy,
+            interval: Duration::from_secs(manifest.interval),
+            resume_lookback_minutes: 0,
+            accumulation_threshold,
+            accumulation_window_minutes,
+            // Just past the last event, so everything captured is delivered
+            stop_at: Some(replayer.end() + Duration::from_millis(1)),
+        },
+        None,
+        conn,
+        &|| db::open_db_at(&db_path),
+    )
+    .await?;
+
+    let alerts = db::alert_count(&db::open_db_at(&db_path)?);
+    println!();
+    println!(
+        "{} {} alerts stored in {}",
+        "Replay finished:".bright_cyan().bold(),
+        alerts.to_string().bright_white(),
+        db_path.display()
+    );
+    Ok(())
+}
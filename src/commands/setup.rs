@@ -71,10 +71,9 @@ pub async fn setup_config() -> Result<(), Box<dyn std::error::Error>> {
     println!("  [3] Kalshi only");
     println!();
 
-    let current_platforms = if existing.platforms.iter().any(|p| p == "all") {
-        "1"
-    } else if existing.platforms.contains(&"polymarket".into())
-        && existing.platforms.contains(&"kalshi".into())
+    let current_platforms = if existing.platforms.iter().any(|p| p == "all")
+        || (existing.platforms.contains(&"polymarket".into())
+            && existing.platforms.contains(&"kalshi".into()))
     {
         "1"
     } else if existing.platforms.contains(&"polymarket".into()) {
@@ -217,8 +216,7 @@ pub async fn setup_config() -> Result<(), Box<dyn std::error::Error>> {
         existing.threshold
     } else {
         threshold_input
-            .replace('$', "")
-            .replace(',', "")
+            .replace(['$', ','], "")
             .parse()
             .unwrap_or(existing.threshold)
     };
@@ -227,6 +225,45 @@ pub async fn setup_config() -> Result<(), Box<dyn std::error::Error>> {
         format!("Threshold set to ${}", threshold).bright_green()
     );
 
+    println!();
+    println!("Optional overrides for some platforms, categories or markets,");
+    println!("as key=amount pairs. The most specific one applies.");
+    println!(
+        "  e.g. {}",
+        "kalshi=10000, weather=5000, politics:us_elections=250000".bright_cyan()
+    );
+    let mut threshold_policy = existing.threshold_policy.clone();
+    let current = threshold_policy.describe_overrides();
+    print!(
+        "Overrides (current: {}, Enter to keep, 'clear' to remove): ",
+        if current.is_empty() { "none".dimmed() } else { current.bright_green() }
+    );
+    let overrides_input = read_line();
+    if overrides_input.eq_ignore_ascii_case("clear") {
+        threshold_policy.set_overrides("");
+    } else if !overrides_input.is_empty() {
+        for entry in threshold_policy.set_overrides(&overrides_input) {
+            println!("{} {}", "Skipped invalid override:".yellow(), entry);
+        }
+    }
+
+    println!();
+    println!("Optional size bands name alerts over a value, e.g. {}.", "mega-whale=250000".bright_cyan());
+    println!("Banded alerts get their own header and webhook alert_type.");
+    let current = threshold_policy.describe_bands();
+    print!(
+        "Bands (current: {}, Enter to keep, 'clear' to remove): ",
+        if current.is_empty() { "none".dimmed() } else { current.bright_green() }
+    );
+    let bands_input = read_line();
+    if bands_input.eq_ignore_ascii_case("clear") {
+        threshold_policy.bands.clear();
+    } else if !bands_input.is_empty() {
+        for entry in threshold_policy.set_bands(&bands_input) {
+            println!("{} {}", "Skipped invalid band:".yellow(), entry);
+        }
+    }
+
     println!();
     println!("How many days of alert history to keep? (0 = forever)");
     print!(
@@ -274,9 +311,17 @@ pub async fn setup_config() -> Result<(), Box<dyn std::error::Error>> {
     let kalshi_key_id = read_line();
 
     let (kalshi_api_key_id, kalshi_private_key) = if !kalshi_key_id.is_empty() {
-        print!("Kalshi Private Key: ");
+        print!("Kalshi private key (path to .pem file): ");
         let pk = read_line();
-        println!("{}", "Kalshi API configured".bright_green());
+        let probe = crate::config::Config {
+            kalshi_api_key_id: Some(kalshi_key_id.clone()),
+            kalshi_private_key: Some(pk.clone()),
+            ..Default::default()
+        };
+        match crate::platforms::kalshi_auth::KalshiSigner::from_config(&probe) {
+            Ok(_) => println!("{}", "Kalshi API configured".bright_green()),
+            Err(e) => println!("{} {}", "Warning:".yellow(), e),
+        }
         (Some(kalshi_key_id), Some(pk))
     } else {
         // Keep existing
@@ -322,10 +367,21 @@ pub async fn setup_config() -> Result<(), Box<dyn std::error::Error>> {
         webhook_url,
         categories: categories.clone(),
         threshold,
+        threshold_policy,
         platforms: platforms.clone(),
         history_retention_days,
         max_odds: existing.max_odds,
         min_spread: existing.min_spread,
+        resume_lookback_minutes: existing.resume_lookback_minutes,
+        accumulation_threshold: existing.accumulation_threshold,
+        accumulation_window_minutes: existing.accumulation_window_minutes,
+        coordinated_flow_wallets: existing.coordinated_flow_wallets,
+        coordinated_flow_window_minutes: existing.coordinated_flow_window_minutes,
+        price_impact_window_secs: existing.price_impact_window_secs,
+        filters: existing.filters.clone(),
+        scoring: existing.scoring.clone(),
+        fresh_wallet: existing.fresh_wallet.clone(),
+        endpoints: existing.endpoints.clone(),
     };
 
     crate::config::save_config(&config)?;
@@ -371,6 +427,14 @@ pub async fn setup_config() -> Result<(), Box<dyn std::error::Error>> {
         "  Threshold:     {}",
         format!("${}", threshold).bright_green()
     );
+    let overrides = config.threshold_policy.describe_overrides();
+    if !overrides.is_empty() {
+        println!("  Overrides:     {}", overrides.bright_green());
+    }
+    let bands = config.threshold_policy.describe_bands();
+    if !bands.is_empty() {
+        println!("  Bands:         {}", bands.bright_green());
+    }
 
     // Retention
     let ret_display = if history_retention_days == 0 {
@@ -2,6 +2,7 @@ use colored::*;
 use rusqlite::Connection;
 
 use crate::db;
+use crate::platforms::kalshi_auth::KalshiSigner;
 
 pub async fn show_status(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
     println!("{}", "WHALE WATCHER STATUS".bright_cyan().bold());
@@ -12,10 +13,10 @@ pub async fn show_status(conn: &Connection) -> Result<(), Box<dyn std::error::Er
             println!("Configuration:");
             println!(
                 "  Kalshi API: {}",
-                if cfg.kalshi_api_key_id.is_some() {
-                    "Configured".green()
-                } else {
-                    "Not configured (using public data)".yellow()
+                match KalshiSigner::from_config(&cfg) {
+                    Ok(Some(_)) => "Configured (signed requests + WebSocket)".green(),
+                    Ok(None) => "Not configured (using public data)".yellow(),
+                    Err(e) => format!("Invalid credentials: {}", e).red(),
                 }
             );
             println!(
@@ -24,8 +25,8 @@ pub async fn show_status(conn: &Connection) -> Result<(), Box<dyn std::error::Er
             );
             println!(
                 "  Webhook: {}",
-                if cfg.webhook_url.is_some() {
-                    format!("Configured ({})", cfg.webhook_url.as_ref().unwrap()).green()
+                if let Some(ref url) = cfg.webhook_url {
+                    format!("Configured ({})", url).green()
                 } else {
                     "Not configured".yellow()
                 }
@@ -37,6 +38,21 @@ pub async fn show_status(conn: &Connection) -> Result<(), Box<dyn std::error::Er
             };
             println!("  Categories:    {}", cat_display.green());
             println!("  Threshold:     {}", format!("${}", cfg.threshold).green());
+            let overrides = cfg.threshold_policy.describe_overrides();
+            if !overrides.is_empty() {
+                println!("  Overrides:     {}", overrides.green());
+            }
+            let relative = &cfg.threshold_policy.relative;
+            if relative.is_enabled() {
+                println!(
+                    "  Relative:      {}",
+                    format!("{} (trades from ${})", relative.describe(), relative.min_value).green()
+                );
+            }
+            let bands = cfg.threshold_policy.describe_bands();
+            if !bands.is_empty() {
+                println!("  Bands:         {}", bands.green());
+            }
             println!(
                 "  Retention:     {}",
                 if cfg.history_retention_days == 0 {
@@ -46,12 +62,75 @@ pub async fn show_status(conn: &Connection) -> Result<(), Box<dyn std::error::Er
                 }
                 .green()
             );
+            println!(
+                "  Resume:        {}",
+                if cfg.resume_lookback_minutes == 0 {
+                    "Disabled".to_string()
+                } else {
+                    format!("Catch up to {} min after restart", cfg.resume_lookback_minutes)
+                }
+                .green()
+            );
+            println!(
+                "  Accumulation:  {}",
+                if cfg.accumulation_threshold == 0 {
+                    "Disabled".to_string()
+                } else {
+                    format!("${} per wallet and outcome within {} min", cfg.accumulation_threshold, cfg.accumulation_window_minutes)
+                }
+                .green()
+            );
+            println!(
+                "  Coordination:  {}",
+                if cfg.coordinated_flow_wallets < 2 {
+                    "Disabled".to_string()
+                } else {
+                    format!(
+                        "{} wallets on one outcome within {} min",
+                        cfg.coordinated_flow_wallets, cfg.coordinated_flow_window_minutes
+                    )
+                }
+                .green()
+            );
+            println!(
+                "  Fresh wallet:  {}",
+                if cfg.fresh_wallet.is_enabled() {
+                    format!(
+                        "${}+ buys at {:.0}% odds or less, by wallets under {}h old with {} trades or fewer",
+                        cfg.fresh_wallet.min_value,
+                        cfg.fresh_wallet.max_odds * 100.0,
+                        cfg.fresh_wallet.max_age_hours,
+                        cfg.fresh_wallet.max_trades
+                    )
+                } else {
+                    "Disabled".to_string()
+                }
+                .green()
+            );
         }
         Err(_) => {
             println!("No configuration found. Run 'wwatcher setup' to configure.");
         }
     }
 
+    println!();
+    println!("Endpoints:");
+    let defaults = crate::config::Endpoints::default();
+    for ((label, var, url), (_, _, default_url)) in crate::config::endpoints().entries().into_iter().zip(defaults.entries()) {
+        let source = if std::env::var(var).is_ok_and(|v| !v.trim().is_empty()) {
+            format!("(from {})", var).yellow()
+        } else if url != default_url {
+            "(from config)".yellow()
+        } else {
+            "".normal()
+        };
+        println!("  {:<17} {} {}", format!("{}:", label), url.green(), source);
+    }
+    match crate::config::endpoints().polygon_rpc {
+        Some(ref url) => println!("  {:<17} {} {}", "Polygon RPC:", url.green(), "(funding links)".dimmed()),
+        None => println!("  {:<17} {}", "Polygon RPC:", "Not set (no funding links)".dimmed()),
+    }
+
     println!();
     println!("Database:");
     let alert_count = db::alert_count(conn);
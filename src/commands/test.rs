@@ -1,7 +1,7 @@
 use colored::*;
 
 use crate::alerts::sound;
-use crate::alerts::{AlertData};
+use crate::alerts::{scoring, AlertData};
 use crate::alerts::webhook;
 use crate::types;
 
@@ -68,12 +68,15 @@ pub async fn test_webhook() -> Result<(), Box<dyn std::error::Error>> {
     let timestamp = chrono::Utc::now().to_rfc3339();
     let buy_alert = AlertData {
         platform: "Polymarket",
+        trade_id: None,
+        order_id: None,
         market_title: Some("Will Bitcoin reach $100k by end of 2026?"),
         outcome: Some("Yes"),
         side: "BUY",
         value: 50000.0,
         price: 0.65,
         size: 76923.08,
+        fill_count: 3,
         timestamp: &timestamp,
         wallet_id: Some("0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb"),
         wallet_activity: Some(&test_activity),
@@ -81,7 +84,20 @@ pub async fn test_webhook() -> Result<(), Box<dyn std::error::Error>> {
         whale_profile: None,
         order_book: None,
         top_holders: None,
+        accumulation: None,
+        score: None,
+        filter_decisions: &[],
+        threshold: None,
+        band: None,
+        liquidity: None,
+        relative_triggers: &[],
+        price_impact: None,
+        anomalies: &[],
+        fresh_wallet: None,
+        wallet_cluster: None,
     };
+    let buy_score = scoring::score_alert(&buy_alert, &config.scoring);
+    let buy_alert = AlertData { score: Some(&buy_score), ..buy_alert };
     webhook::send_webhook_alert(&webhook_url, &buy_alert).await;
 
     println!("Test BUY alert sent!");
@@ -92,12 +108,15 @@ pub async fn test_webhook() -> Result<(), Box<dyn std::error::Error>> {
     let timestamp2 = chrono::Utc::now().to_rfc3339();
     let sell_alert = AlertData {
         platform: "Kalshi",
+        trade_id: None,
+        order_id: None,
         market_title: Some("Bitcoin price on Jan 16, 2026?"),
         outcome: Some("Bitcoin (BTC) price < $96999.99 at expiry"),
         side: "SELL",
         value: 35000.0,
         price: 0.54,
         size: 64814.81,
+        fill_count: 1,
         timestamp: &timestamp2,
         wallet_id: None,
         wallet_activity: None,
@@ -105,7 +124,20 @@ pub async fn test_webhook() -> Result<(), Box<dyn std::error::Error>> {
         whale_profile: None,
         order_book: None,
         top_holders: None,
+        accumulation: None,
+        score: None,
+        filter_decisions: &[],
+        threshold: None,
+        band: None,
+        liquidity: None,
+        relative_triggers: &[],
+        price_impact: None,
+        anomalies: &[],
+        fresh_wallet: None,
+        wallet_cluster: None,
     };
+    let sell_score = scoring::score_alert(&sell_alert, &config.scoring);
+    let sell_alert = AlertData { score: Some(&sell_score), ..sell_alert };
     webhook::send_webhook_alert(&webhook_url, &sell_alert).await;
 
     println!("Test SELL alert sent!");
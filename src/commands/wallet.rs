@@ -0,0 +1,105 @@
+use colored::*;
+use rusqlite::Connection;
+
+use crate::clusters;
+use crate::config;
+use crate::polygon;
+use crate::whale_profile;
+
+/// List wallet clusters, or show one cluster given its ID or a member wallet
+pub async fn show_wallets(
+    query: Option<&str>,
+    as_json: bool,
+    rebuild: bool,
+    conn: &Connection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if rebuild {
+        rebuild_clusters(conn).await?;
+    }
+
+    let Some(query) = query else {
+        let clusters = clusters::list_clusters(conn)?;
+        if as_json {
+            println!("{}", serde_json::to_string_pretty(&clusters)?);
+            return Ok(());
+        }
+        if clusters.is_empty() {
+            println!("No wallet clusters found.");
+            return Ok(());
+        }
+        println!("{}", "WALLET CLUSTERS".bright_cyan().bold());
+        println!();
+        for cluster in &clusters {
+            println!(
+                "{} | {} wallets | ${:.2}",
+                cluster.id.bright_yellow(),
+                cluster.wallets,
+                cluster.combined_value
+            );
+        }
+        println!();
+        println!("{}", "Run `wwatcher wallet <CLUSTER_ID or WALLET>` to list a cluster's members.".dimmed());
+        return Ok(());
+    };
+
+    let Some(cluster_id) = clusters::resolve_cluster_id(conn, query) else {
+        if as_json {
+            println!("null");
+        } else {
+            println!("{} is not in any wallet cluster.", query);
+        }
+        return Ok(());
+    };
+    let (members, links) = clusters::cluster_detail(conn, &cluster_id)?;
+
+    if as_json {
+        let detail = serde_json::json!({
+            "id": cluster_id,
+            "members": members,
+            "links": links,
+        });
+        println!("{}", serde_json::to_string_pretty(&detail)?);
+        return Ok(());
+    }
+
+    let combined: f64 = members.iter().map(|m| m.value).sum();
+    println!(
+        "{}",
+        format!("CLUSTER {} | {} wallets | ${:.2}", cluster_id, members.len(), combined).bright_cyan().bold()
+    );
+    println!();
+    for member in &members {
+        println!("  - {} ${:.2} ({} alerts)", member.wallet_id, member.value, member.alerts);
+        if let Some(ref funder) = member.funded_by {
+            println!("    Funded by: {}", funder.dimmed());
+        }
+    }
+    println!();
+    println!("Links:");
+    for link in &links {
+        let reasons: Vec<&str> = link.reasons.iter().map(|r| r.as_str()).collect();
+        println!("  {} <-> {} ({})", link.wallet_a, link.wallet_b, reasons.join(", ").yellow());
+    }
+
+    Ok(())
+}
+
+/// Look up missing funding sources (with a Polygon RPC endpoint), then relink every wallet
+async fn rebuild_clusters(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    if config::endpoints().polygon_rpc.is_some() {
+        let wallets = clusters::unfunded_wallets(conn)?;
+        println!("Looking up funding for {} wallets...", wallets.len());
+        for wallet in &wallets {
+            let Some(first_tx) = whale_profile::fetch_history(wallet).await.and_then(|h| h.first_tx) else {
+                continue;
+            };
+            if let Some(funding) = polygon::fetch_funding(wallet, &first_tx).await {
+                clusters::record_funder(conn, wallet, &funding.funder);
+            }
+        }
+    }
+    let relinked = clusters::relink_all(conn)?;
+    println!("{} Relinked {} wallets", "✓".bright_green(), relinked);
+    println!();
+    Ok(())
+}
@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+use crate::alerts::rules::FilterRule;
+use crate::threshold::ThresholdPolicy;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
     pub kalshi_api_key_id: Option<String>,
     pub kalshi_private_key: Option<String>,
@@ -14,13 +18,17 @@ pub struct Config {
     /// Default whale alert threshold in USD
     #[serde(default = "default_threshold")]
     pub threshold: u64,
+    /// Threshold overrides by platform, category and market, and named size bands
+    #[serde(default)]
+    pub threshold_policy: ThresholdPolicy,
     /// Which platforms to monitor: ["polymarket", "kalshi"] or ["all"]
     #[serde(default = "default_platforms")]
     pub platforms: Vec<String>,
     /// Days to retain alerts in the database (0 = keep forever)
     #[serde(default = "default_retention_days")]
     pub history_retention_days: u32,
-    /// Maximum odds to alert on (0.0-1.0). Skip if YES or NO price exceeds this.
+    /// Maximum odds to alert on (0.0-1.0). Skip a buy if the side being bought
+    /// trades above this; exits are never skipped on odds.
     /// Default 0.95 filters out near-certainties with no edge.
     #[serde(default = "default_max_odds")]
     pub max_odds: f64,
@@ -28,6 +36,240 @@ pub struct Config {
     /// Default 0.0 (disabled).
     #[serde(default = "default_min_spread")]
     pub min_spread: f64,
+    /// How far back (minutes) to catch up on trades missed while the watcher
+    /// was stopped. 0 disables resume and starts from the latest trades.
+    #[serde(default = "default_resume_lookback_minutes")]
+    pub resume_lookback_minutes: u64,
+    /// Alert when one wallet's flow into a single market outcome reaches this
+    /// many USD within `accumulation_window_minutes`, even if no single trade
+    /// crossed `threshold`. 0 disables accumulation alerts.
+    #[serde(default = "default_accumulation_threshold")]
+    pub accumulation_threshold: u64,
+    /// Rolling window for `accumulation_threshold`
+    #[serde(default = "default_accumulation_window_minutes")]
+    pub accumulation_window_minutes: u64,
+    /// Alert when this many distinct wallets alert on the same market outcome
+    /// and side within `coordinated_flow_window_minutes`. Under 2 disables
+    /// coordinated flow alerts.
+    #[serde(default = "default_coordinated_flow_wallets")]
+    pub coordinated_flow_wallets: usize,
+    /// Sliding window for `coordinated_flow_wallets`
+    #[serde(default = "default_coordinated_flow_window_minutes")]
+    pub coordinated_flow_window_minutes: u64,
+    /// How long after an alert to keep snapshotting the order book for its
+    /// price impact. 0 reports only the impact at alert time.
+    #[serde(default = "default_price_impact_window_secs")]
+    pub price_impact_window_secs: u64,
+    /// Named filter rules every alert must pass before it is shown, stored or sent
+    #[serde(default)]
+    pub filters: Vec<FilterRule>,
+    /// Weights and tier cutoffs for alert scoring
+    #[serde(default)]
+    pub scoring: ScoringWeights,
+    /// When a young wallet's bet is sent as a `FRESH_WALLET_ENTRY` alert
+    #[serde(default)]
+    pub fresh_wallet: FreshWalletPolicy,
+    /// API base URLs. Override to use the Kalshi demo environment, a caching proxy or a mock server.
+    #[serde(default)]
+    pub endpoints: Endpoints,
+}
+
+/// Multipliers on each scoring factor's points (1.0 = standard, 0 = ignore
+/// the factor), and the scores at which alerts become medium and high tier
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ScoringWeights {
+    pub leaderboard_rank: f64,
+    pub win_rate: f64,
+    pub portfolio: f64,
+    pub wallet_activity: f64,
+    pub trade_size: f64,
+    pub order_book: f64,
+    pub contrarian: f64,
+    pub high_tier: u32,
+    pub medium_tier: u32,
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        Self {
+            leaderboard_rank: 1.0,
+            win_rate: 1.0,
+            portfolio: 1.0,
+            wallet_activity: 1.0,
+            trade_size: 1.0,
+            order_book: 1.0,
+            contrarian: 1.0,
+            high_tier: 60,
+            medium_tier: 35,
+        }
+    }
+}
+
+/// A buy is sent as `FRESH_WALLET_ENTRY` when the wallet was first active (or
+/// funded, if earlier) at most `max_age_hours` before it, has at most
+/// `max_trades` trades, the buy is worth `min_value` or more, and the side
+/// bought is priced at `max_odds` or below. `max_age_hours` 0 disables the
+/// check.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct FreshWalletPolicy {
+    pub max_age_hours: u64,
+    pub max_trades: u32,
+    pub min_value: f64,
+    pub max_odds: f64,
+}
+
+impl Default for FreshWalletPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_hours: 72,
+            max_trades: 10,
+            min_value: 10_000.0,
+            max_odds: 0.35,
+        }
+    }
+}
+
+impl FreshWalletPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.max_age_hours > 0
+    }
+}
+
+/// Base URL of every API the watcher talks to. Each can also be overridden
+/// with an environment variable (see `ENDPOINT_ENV_VARS`), which wins over the config file.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Endpoints {
+    /// Kalshi REST base, including the `/trade-api/v2` prefix
+    pub kalshi_api: String,
+    pub kalshi_ws: String,
+    pub polymarket_data_api: String,
+    pub polymarket_gamma_api: String,
+    pub polymarket_clob_api: String,
+    pub polymarket_ws: String,
+    /// JSON-RPC endpoint of a Polygon node, used to link wallets by funding
+    /// source. Unset disables funding lookups.
+    pub polygon_rpc: Option<String>,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            kalshi_api: "https://api.elections.kalshi.com/trade-api/v2".into(),
+            kalshi_ws: "wss://api.elections.kalshi.com/trade-api/ws/v2".into(),
+            polymarket_data_api: "https://data-api.polymarket.com".into(),
+            polymarket_gamma_api: "https://gamma-api.polymarket.com".into(),
+            polymarket_clob_api: "https://clob.polymarket.com".into(),
+            polymarket_ws: "wss://ws-live-data.polymarket.com".into(),
+            polygon_rpc: None,
+        }
+    }
+}
+
+/// Environment variable that sets `polygon_rpc`
+pub const POLYGON_RPC_ENV_VAR: &str = "WWATCHER_POLYGON_RPC_URL";
+
+/// Environment variable that overrides each endpoint
+pub const ENDPOINT_ENV_VARS: [&str; 6] = [
+    "WWATCHER_KALSHI_API_URL",
+    "WWATCHER_KALSHI_WS_URL",
+    "WWATCHER_POLYMARKET_DATA_API_URL",
+    "WWATCHER_POLYMARKET_GAMMA_API_URL",
+    "WWATCHER_POLYMARKET_CLOB_API_URL",
+    "WWATCHER_POLYMARKET_WS_URL",
+];
+
+impl Endpoints {
+    /// (label, env var, URL) for each endpoint, in `ENDPOINT_ENV_VARS` order
+    pub fn entries(&self) -> [(&'static str, &'static str, &str); 6] {
+        [
+            ("Kalshi API", ENDPOINT_ENV_VARS[0], &self.kalshi_api),
+            ("Kalshi WS", ENDPOINT_ENV_VARS[1], &self.kalshi_ws),
+            ("Polymarket Data", ENDPOINT_ENV_VARS[2], &self.polymarket_data_api),
+            ("Polymarket Gamma", ENDPOINT_ENV_VARS[3], &self.polymarket_gamma_api),
+            ("Polymarket CLOB", ENDPOINT_ENV_VARS[4], &self.polymarket_clob_api),
+            ("Polymarket WS", ENDPOINT_ENV_VARS[5], &self.polymarket_ws),
+        ]
+    }
+
+    /// Apply environment overrides and strip trailing slashes
+    pub fn with_env_overrides(mut self) -> Self {
+        let fields = [
+            &mut self.kalshi_api,
+            &mut self.kalshi_ws,
+            &mut self.polymarket_data_api,
+            &mut self.polymarket_gamma_api,
+            &mut self.polymarket_clob_api,
+            &mut self.polymarket_ws,
+        ];
+
+        for (field, var) in fields.into_iter().zip(ENDPOINT_ENV_VARS) {
+            if let Ok(value) = std::env::var(var) {
+                if !value.trim().is_empty() {
+                    *field = value.trim().to_string();
+                }
+            }
+            let trimmed = field.trim_end_matches('/').len();
+            field.truncate(trimmed);
+        }
+        if let Ok(value) = std::env::var(POLYGON_RPC_ENV_VAR) {
+            if !value.trim().is_empty() {
+                self.polygon_rpc = Some(value.trim().to_string());
+            }
+        }
+
+        self
+    }
+}
+
+static ENDPOINTS: OnceLock<Endpoints> = OnceLock::new();
+
+/// Endpoints in effect for this process: config file, then environment overrides.
+/// Resolved once on first use.
+pub fn endpoints() -> &'static Endpoints {
+    ENDPOINTS.get_or_init(|| {
+        load_config()
+            .map(|c| c.endpoints)
+            .unwrap_or_default()
+            .with_env_overrides()
+    })
+}
+
+/// Use these endpoints instead of the configured ones (a replay uses the
+/// recording's). Fails if endpoints were already resolved.
+pub fn override_endpoints(endpoints: Endpoints) -> Result<(), Box<dyn std::error::Error>> {
+    ENDPOINTS
+        .set(endpoints)
+        .map_err(|_| "endpoints were already in use".into())
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            kalshi_api_key_id: None,
+            kalshi_private_key: None,
+            webhook_url: None,
+            categories: default_categories(),
+            threshold: default_threshold(),
+            threshold_policy: ThresholdPolicy::default(),
+            platforms: default_platforms(),
+            history_retention_days: default_retention_days(),
+            max_odds: default_max_odds(),
+            min_spread: default_min_spread(),
+            resume_lookback_minutes: default_resume_lookback_minutes(),
+            accumulation_threshold: default_accumulation_threshold(),
+            accumulation_window_minutes: default_accumulation_window_minutes(),
+            coordinated_flow_wallets: default_coordinated_flow_wallets(),
+            coordinated_flow_window_minutes: default_coordinated_flow_window_minutes(),
+            price_impact_window_secs: default_price_impact_window_secs(),
+            filters: Vec::new(),
+            scoring: ScoringWeights::default(),
+            fresh_wallet: FreshWalletPolicy::default(),
+            endpoints: Endpoints::default(),
+        }
+    }
 }
 
 fn default_categories() -> Vec<String> {
@@ -54,6 +296,30 @@ fn default_min_spread() -> f64 {
     0.0
 }
 
+fn default_resume_lookback_minutes() -> u64 {
+    60
+}
+
+fn default_accumulation_threshold() -> u64 {
+    50000
+}
+
+fn default_accumulation_window_minutes() -> u64 {
+    60
+}
+
+fn default_coordinated_flow_wallets() -> usize {
+    3
+}
+
+fn default_coordinated_flow_window_minutes() -> u64 {
+    10
+}
+
+fn default_price_impact_window_secs() -> u64 {
+    120
+}
+
 fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
     let config_dir = dirs::config_dir().ok_or("Could not determine config directory")?;
 
@@ -2,6 +2,9 @@ use rusqlite::{Connection, params};
 use sha2::{Sha256, Digest};
 use std::path::PathBuf;
 
+use crate::alerts::anomaly::{BaselineSums, Baselines, BASELINE_DAYS};
+use crate::alerts::CoordinatedFlow;
+
 pub fn wallet_hash(wallet_id: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(wallet_id.as_bytes());
@@ -16,8 +19,12 @@ pub fn db_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
 }
 
 pub fn open_db() -> Result<Connection, Box<dyn std::error::Error>> {
-    let path = db_path()?;
-    let conn = Connection::open(&path)?;
+    open_db_at(&db_path()?)
+}
+
+/// Open (creating if needed) a database at a specific path
+pub fn open_db_at(path: &std::path::Path) -> Result<Connection, Box<dyn std::error::Error>> {
+    let conn = Connection::open(path)?;
 
     // Performance pragmas
     conn.execute_batch(
@@ -30,6 +37,14 @@ pub fn open_db() -> Result<Connection, Box<dyn std::error::Error>> {
     Ok(conn)
 }
 
+/// Fresh in-memory database with the current schema, for tests
+#[cfg(test)]
+pub fn open_in_memory() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    init_schema(&conn).unwrap();
+    conn
+}
+
 fn init_schema(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
     conn.execute_batch(
         "CREATE TABLE IF NOT EXISTS alerts (
@@ -50,6 +65,12 @@ fn init_schema(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
             timestamp TEXT NOT NULL,
             market_context TEXT,
             wallet_activity TEXT,
+            trade_id TEXT,
+            order_id TEXT,
+            fill_count INTEGER NOT NULL DEFAULT 1,
+            scoring TEXT,
+            price_impact TEXT,
+            anomalies TEXT,
             created_at INTEGER DEFAULT (strftime('%s', 'now'))
         );
 
@@ -76,6 +97,98 @@ fn init_schema(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
         CREATE INDEX IF NOT EXISTS idx_wallet_memory_hash ON wallet_memory(wallet_hash);
         CREATE INDEX IF NOT EXISTS idx_wallet_memory_seen ON wallet_memory(seen_at);
 
+        CREATE TABLE IF NOT EXISTS ingestion_gaps (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            platform TEXT NOT NULL,
+            cursor_timestamp INTEGER NOT NULL,
+            oldest_fetched INTEGER,
+            pages INTEGER NOT NULL,
+            detected_at INTEGER DEFAULT (strftime('%s', 'now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS market_baselines (
+            platform TEXT NOT NULL,
+            market_id TEXT NOT NULL,
+            hour INTEGER NOT NULL,
+            category TEXT,
+            trades INTEGER NOT NULL,
+            volume REAL NOT NULL,
+            log_sum REAL NOT NULL,
+            log_sq_sum REAL NOT NULL,
+            PRIMARY KEY (platform, market_id, hour)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_market_baselines_category ON market_baselines(platform, category, hour);
+
+        CREATE TABLE IF NOT EXISTS coordinated_flows (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            flow_key TEXT NOT NULL UNIQUE,
+            platform TEXT NOT NULL,
+            market_id TEXT NOT NULL,
+            market_title TEXT,
+            outcome TEXT,
+            action TEXT NOT NULL,
+            wallet_count INTEGER NOT NULL,
+            combined_value REAL NOT NULL,
+            first_trade_at TEXT NOT NULL,
+            last_trade_at TEXT NOT NULL,
+            spread_secs INTEGER NOT NULL,
+            window_minutes INTEGER NOT NULL,
+            wallets TEXT NOT NULL,
+            created_at INTEGER DEFAULT (strftime('%s', 'now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_coordinated_flows_created ON coordinated_flows(created_at);
+
+        CREATE INDEX IF NOT EXISTS idx_alerts_wallet_id ON alerts(wallet_id);
+
+        CREATE TABLE IF NOT EXISTS wallet_funding (
+            wallet_id TEXT PRIMARY KEY,
+            funder TEXT NOT NULL,
+            checked_at INTEGER DEFAULT (strftime('%s', 'now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_wallet_funding_funder ON wallet_funding(funder);
+
+        CREATE TABLE IF NOT EXISTS wallet_links (
+            wallet_a TEXT NOT NULL,
+            wallet_b TEXT NOT NULL,
+            reasons TEXT NOT NULL,
+            linked_at INTEGER DEFAULT (strftime('%s', 'now')),
+            PRIMARY KEY (wallet_a, wallet_b)
+        );
+
+        CREATE TABLE IF NOT EXISTS wallet_clusters (
+            wallet_id TEXT PRIMARY KEY,
+            cluster_id TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_wallet_clusters_cluster ON wallet_clusters(cluster_id);
+
+        CREATE TABLE IF NOT EXISTS alert_outcomes (
+            alert_id INTEGER PRIMARY KEY,
+            platform TEXT NOT NULL,
+            market_id TEXT NOT NULL,
+            asset_id TEXT NOT NULL,
+            outcome TEXT,
+            side TEXT NOT NULL,
+            direction REAL NOT NULL,
+            category TEXT,
+            band TEXT,
+            tier TEXT,
+            wallet_id TEXT,
+            alerted_at INTEGER NOT NULL,
+            price_at_alert REAL NOT NULL,
+            price_15m REAL,
+            price_1h REAL,
+            price_24h REAL,
+            resolved_price REAL,
+            resolved_at INTEGER,
+            next_check_at INTEGER
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_alert_outcomes_next_check ON alert_outcomes(platform, next_check_at);
+
         CREATE TABLE IF NOT EXISTS metadata (
             key TEXT PRIMARY KEY,
             value TEXT
@@ -85,18 +198,78 @@ fn init_schema(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
         INSERT OR IGNORE INTO metadata (key, value) VALUES ('created_at', strftime('%s', 'now'));"
     )?;
 
+    migrate_schema(conn)?;
     Ok(())
 }
 
-/// Insert an alert into the alerts table
+/// Bring databases created by older versions up to the current schema
+fn migrate_schema(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let version: u32 = get_metadata(conn, "schema_version").and_then(|v| v.parse().ok()).unwrap_or(1);
+
+    // v2: trade and order identity, fill count, scoring, price impact and
+    // anomalies (JSON) on alerts, one stored alert per (platform, order_id).
+    // Polymarket orders in one transaction share a trade ID, so it can't be the key;
+    // legacy rows have no order ID and never conflict. Trade times are stored in
+    // one format (see `normalize_timestamp`) so alerts sort by them. The other
+    // v2 tables are created above.
+    let columns = [
+        ("trade_id", "TEXT"),
+        ("order_id", "TEXT"),
+        ("fill_count", "INTEGER NOT NULL DEFAULT 1"),
+        ("scoring", "TEXT"),
+        ("price_impact", "TEXT"),
+        ("anomalies", "TEXT"),
+    ];
+    for (column, definition) in columns {
+        let exists = conn
+            .prepare("SELECT 1 FROM pragma_table_info('alerts') WHERE name = ?1")?
+            .exists([column])?;
+        if !exists {
+            conn.execute_batch(&format!("ALTER TABLE alerts ADD COLUMN {} {};", column, definition))?;
+        }
+    }
+    if version < 2 {
+        conn.execute_batch(
+            "UPDATE alerts SET timestamp = strftime('%Y-%m-%dT%H:%M:%fZ', timestamp)
+             WHERE strftime('%Y-%m-%dT%H:%M:%fZ', timestamp) IS NOT NULL;"
+        )?;
+    }
+    conn.execute_batch(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_alerts_platform_order ON alerts(platform, order_id);
+         CREATE INDEX IF NOT EXISTS idx_alerts_trade_time ON alerts(timestamp);
+         CREATE INDEX IF NOT EXISTS idx_alerts_market_outcome_wallet ON alerts(market_title, outcome, wallet_id);
+         UPDATE metadata SET value = '2' WHERE key = 'schema_version' AND CAST(value AS INTEGER) < 2;"
+    )?;
+
+    Ok(())
+}
+
+/// A trade time as stored: RFC3339 UTC with milliseconds, e.g.
+/// `2026-10-01T12:00:00.000Z`. Platforms and feeds send different forms
+/// (`+00:00` or `Z`, with or without fractions), which don't sort as text.
+pub fn normalize_timestamp(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&chrono::Utc).to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+/// Insert an alert into the alerts table.
+///
+/// Returns true if the alert is new. An alert whose (platform, order_id) is
+/// already stored is ignored and returns false, and so does one the database
+/// failed to store: webhooks are only sent for stored alerts, exactly once.
+#[allow(clippy::too_many_arguments)]
 pub fn insert_alert(
     conn: &Connection,
     platform: &str,
+    trade_id: Option<&str>,
+    order_id: Option<&str>,
     alert_type: &str,
     action: &str,
     value: f64,
     price: f64,
     size: f64,
+    fill_count: u32,
     market_title: Option<&str>,
     market_id: Option<&str>,
     outcome: Option<&str>,
@@ -104,14 +277,18 @@ pub fn insert_alert(
     timestamp: &str,
     market_context_json: Option<&str>,
     wallet_activity_json: Option<&str>,
-) {
+    scoring_json: Option<&str>,
+    price_impact_json: Option<&str>,
+    anomalies_json: Option<&str>,
+) -> bool {
     let w_hash = wallet_id.map(wallet_hash);
+    let timestamp = normalize_timestamp(timestamp);
 
     let result = conn.execute(
-        "INSERT INTO alerts (platform, alert_type, action, value, price, size,
+        "INSERT OR IGNORE INTO alerts (platform, alert_type, action, value, price, size,
          market_title, market_id, outcome, wallet_hash, wallet_id, timestamp,
-         market_context, wallet_activity)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+         market_context, wallet_activity, trade_id, fill_count, scoring, price_impact, anomalies, order_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
         params![
             platform,
             alert_type,
@@ -127,11 +304,252 @@ pub fn insert_alert(
             timestamp,
             market_context_json,
             wallet_activity_json,
+            trade_id,
+            fill_count,
+            scoring_json,
+            price_impact_json,
+            anomalies_json,
+            order_id,
+        ],
+    );
+
+    match result {
+        Ok(inserted) => inserted > 0,
+        Err(e) => {
+            eprintln!("Warning: Failed to log alert to database: {}", e);
+            false
+        }
+    }
+}
+
+/// Replace a stored alert's price impact once it has been measured
+pub fn update_price_impact(conn: &Connection, platform: &str, order_id: &str, price_impact_json: &str) {
+    if let Err(e) = conn.execute(
+        "UPDATE alerts SET price_impact = ?3 WHERE platform = ?1 AND order_id = ?2",
+        params![platform, order_id, price_impact_json],
+    ) {
+        eprintln!("Warning: Failed to store price impact: {}", e);
+    }
+}
+
+/// Replace a stored alert's type after its fresh wallet flag was re-checked
+pub fn update_alert_type(conn: &Connection, platform: &str, order_id: &str, alert_type: &str) {
+    if let Err(e) = conn.execute(
+        "UPDATE alerts SET alert_type = ?3 WHERE platform = ?1 AND order_id = ?2",
+        params![platform, order_id, alert_type],
+    ) {
+        eprintln!("Warning: Failed to update alert type: {}", e);
+    }
+}
+
+/// Add a trade to its market's baseline bucket for the hour of `ts`
+pub fn record_baseline_trade(
+    conn: &Connection,
+    platform: &str,
+    market_id: &str,
+    category: Option<&str>,
+    ts: i64,
+    value: f64,
+) {
+    if value <= 0.0 {
+        return;
+    }
+    let ln = value.ln();
+    let result = conn
+        .prepare_cached(
+            "INSERT INTO market_baselines (platform, market_id, hour, category, trades, volume, log_sum, log_sq_sum)
+             VALUES (?1, ?2, ?3, ?4, 1, ?5, ?6, ?7)
+             ON CONFLICT (platform, market_id, hour) DO UPDATE SET
+                category = COALESCE(excluded.category, category),
+                trades = trades + 1,
+                volume = volume + excluded.volume,
+                log_sum = log_sum + excluded.log_sum,
+                log_sq_sum = log_sq_sum + excluded.log_sq_sum",
+        )
+        .and_then(|mut stmt| stmt.execute(params![platform, market_id, ts.div_euclid(3600), category, value, ln, ln * ln]));
+    if let Err(e) = result {
+        eprintln!("Warning: Failed to record market baseline: {}", e);
+    }
+}
+
+/// Fill in the category of a market's buckets recorded before it was known
+pub fn set_baseline_category(conn: &Connection, platform: &str, market_id: &str, category: &str) {
+    if let Err(e) = conn.execute(
+        "UPDATE market_baselines SET category = ?3 WHERE platform = ?1 AND market_id = ?2 AND category IS NULL",
+        params![platform, market_id, category],
+    ) {
+        eprintln!("Warning: Failed to update market baseline: {}", e);
+    }
+}
+
+/// Sums over the baseline window ending at the hour of `ts`, for the buckets
+/// `scope` selects (`column = ?2`)
+fn baseline_sums(conn: &Connection, platform: &str, scope: &str, key: &str, ts: i64) -> rusqlite::Result<BaselineSums> {
+    let hour = ts.div_euclid(3600);
+    let since = hour - BASELINE_DAYS * 24;
+    let mut sums = BaselineSums::default();
+
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT COALESCE(SUM(trades), 0), COALESCE(SUM(log_sum), 0), COALESCE(SUM(log_sq_sum), 0), MIN(hour)
+         FROM market_baselines WHERE platform = ?1 AND {} = ?2 AND hour >= ?3 AND hour <= ?4",
+        scope
+    ))?;
+    let first_hour = stmt.query_row(params![platform, key, since, hour], |row| {
+        sums.trades = row.get::<_, i64>(0)? as u64;
+        sums.log_sum = row.get(1)?;
+        sums.log_sq_sum = row.get(2)?;
+        row.get::<_, Option<i64>>(3)
+    })?;
+    let Some(first_hour) = first_hour else {
+        return Ok(sums);
+    };
+    sums.hours = (hour - first_hour) as u64;
+
+    // Hourly flow, summed across markets for a category
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT hour, SUM(volume) FROM market_baselines
+         WHERE platform = ?1 AND {} = ?2 AND hour >= ?3 AND hour <= ?4 GROUP BY hour",
+        scope
+    ))?;
+    let flows = stmt.query_map(params![platform, key, since, hour], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?)))?;
+    for (bucket, flow) in flows.flatten() {
+        if bucket == hour {
+            sums.current_flow = flow;
+        } else {
+            sums.flow_sum += flow;
+            sums.flow_sq_sum += flow * flow;
+        }
+    }
+    Ok(sums)
+}
+
+/// Baselines for a trade at `ts` in a market and, if known, its category
+pub fn load_baselines(conn: &Connection, platform: &str, market_id: &str, category: Option<&str>, ts: i64) -> Baselines {
+    let load = |scope: &str, key: &str| {
+        baseline_sums(conn, platform, scope, key, ts).unwrap_or_else(|e| {
+            eprintln!("Warning: Failed to load market baseline: {}", e);
+            BaselineSums::default()
+        })
+    };
+    Baselines {
+        market: load("market_id", market_id),
+        category: category.map(|c| (c.to_string(), load("category", c))),
+    }
+}
+
+/// Store a coordinated flow, replacing the earlier version of the same flow
+pub fn upsert_coordinated_flow(conn: &Connection, flow_key: &str, flow: &CoordinatedFlow) {
+    let wallets = serde_json::to_string(&flow.wallets).unwrap_or_else(|_| "[]".into());
+    if let Err(e) = conn.execute(
+        "INSERT INTO coordinated_flows (flow_key, platform, market_id, market_title, outcome, action,
+         wallet_count, combined_value, first_trade_at, last_trade_at, spread_secs, window_minutes, wallets)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+         ON CONFLICT (flow_key) DO UPDATE SET
+            market_title = COALESCE(excluded.market_title, market_title),
+            wallet_count = excluded.wallet_count,
+            combined_value = excluded.combined_value,
+            first_trade_at = excluded.first_trade_at,
+            last_trade_at = excluded.last_trade_at,
+            spread_secs = excluded.spread_secs,
+            wallets = excluded.wallets",
+        params![
+            flow_key,
+            flow.platform,
+            flow.market_id,
+            flow.market_title,
+            flow.outcome,
+            flow.action,
+            flow.wallets.len() as i64,
+            flow.combined_value,
+            flow.first_trade_at,
+            flow.last_trade_at,
+            flow.spread_secs,
+            flow.window_minutes as i64,
+            wallets,
         ],
+    ) {
+        eprintln!("Warning: Failed to store coordinated flow: {}", e);
+    }
+}
+
+/// Most recent coordinated flows, newest first
+pub fn query_coordinated_flows(
+    conn: &Connection,
+    limit: usize,
+    platform_filter: &str,
+) -> Result<Vec<CoordinatedFlow>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT platform, market_id, market_title, outcome, action, combined_value,
+                first_trade_at, last_trade_at, spread_secs, window_minutes, wallets
+         FROM coordinated_flows WHERE ?1 = 'all' OR LOWER(platform) = LOWER(?1)
+         ORDER BY last_trade_at DESC LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(params![platform_filter, limit as i64], |row| {
+        let wallets: String = row.get(10)?;
+        Ok(CoordinatedFlow {
+            platform: row.get(0)?,
+            market_id: row.get(1)?,
+            market_title: row.get(2)?,
+            outcome: row.get(3)?,
+            action: row.get(4)?,
+            combined_value: row.get(5)?,
+            first_trade_at: row.get(6)?,
+            last_trade_at: row.get(7)?,
+            spread_secs: row.get(8)?,
+            wallets: serde_json::from_str(&wallets).unwrap_or_default(),
+            window_minutes: row.get::<_, i64>(9)? as u64,
+        })
+    })?;
+    Ok(rows.flatten().collect())
+}
+
+/// Whether an alert for this platform order is already stored
+pub fn alert_exists(conn: &Connection, platform: &str, order_id: &str) -> bool {
+    conn.prepare_cached("SELECT 1 FROM alerts WHERE platform = ?1 AND order_id = ?2")
+        .and_then(|mut stmt| stmt.exists(params![platform, order_id]))
+        .unwrap_or(false)
+}
+
+/// Read a value from the `metadata` key/value table
+pub fn get_metadata(conn: &Connection, key: &str) -> Option<String> {
+    conn.query_row(
+        "SELECT value FROM metadata WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    )
+    .ok()
+    .flatten()
+}
+
+/// Insert or replace a value in the `metadata` key/value table
+pub fn set_metadata(conn: &Connection, key: &str, value: &str) {
+    let result = conn.execute(
+        "INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)",
+        params![key, value],
     );
 
     if let Err(e) = result {
-        eprintln!("Warning: Failed to log alert to database: {}", e);
+        eprintln!("Warning: Failed to write metadata '{}': {}", key, e);
+    }
+}
+
+/// Record that polling couldn't reach the last processed trade,
+/// so trades between `cursor_timestamp` and `oldest_fetched` may have been missed
+pub fn insert_gap_event(
+    conn: &Connection,
+    platform: &str,
+    cursor_timestamp: i64,
+    oldest_fetched: Option<i64>,
+    pages: u32,
+) {
+    let result = conn.execute(
+        "INSERT INTO ingestion_gaps (platform, cursor_timestamp, oldest_fetched, pages)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![platform, cursor_timestamp, oldest_fetched, pages],
+    );
+
+    if let Err(e) = result {
+        eprintln!("Warning: Failed to record gap event: {}", e);
     }
 }
 
@@ -147,17 +565,17 @@ pub fn query_alerts(
         (
             "SELECT platform, alert_type, action, value, price, size,
                     market_title, outcome, wallet_id, timestamp,
-                    wallet_activity, market_context
-             FROM alerts ORDER BY created_at DESC LIMIT ?1".to_string(),
+                    wallet_activity, market_context, fill_count, scoring, price_impact, anomalies
+             FROM alerts ORDER BY timestamp DESC LIMIT ?1".to_string(),
             vec![Box::new(limit as i64)],
         )
     } else {
         (
             "SELECT platform, alert_type, action, value, price, size,
                     market_title, outcome, wallet_id, timestamp,
-                    wallet_activity, market_context
+                    wallet_activity, market_context, fill_count, scoring, price_impact, anomalies
              FROM alerts WHERE LOWER(platform) = LOWER(?1)
-             ORDER BY created_at DESC LIMIT ?2".to_string(),
+             ORDER BY timestamp DESC LIMIT ?2".to_string(),
             vec![
                 Box::new(platform_filter.to_string()),
                 Box::new(limit as i64),
@@ -180,6 +598,10 @@ pub fn query_alerts(
         let timestamp: String = row.get(9)?;
         let wallet_activity_json: Option<String> = row.get(10)?;
         let market_context_json: Option<String> = row.get(11)?;
+        let fill_count: u32 = row.get(12)?;
+        let scoring_json: Option<String> = row.get(13)?;
+        let price_impact_json: Option<String> = row.get(14)?;
+        let anomalies_json: Option<String> = row.get(15)?;
 
         let mut alert = serde_json::json!({
             "platform": platform,
@@ -188,6 +610,7 @@ pub fn query_alerts(
             "value": value,
             "price": price,
             "size": size,
+            "fill_count": fill_count,
             "timestamp": timestamp,
             "market_title": market_title,
             "outcome": outcome,
@@ -209,20 +632,42 @@ pub fn query_alerts(
             }
         }
 
+        if let Some(sc_json) = scoring_json {
+            if let Ok(sc) = serde_json::from_str::<serde_json::Value>(&sc_json) {
+                alert["scoring"] = sc;
+            }
+        }
+
+        if let Some(pi_json) = price_impact_json {
+            if let Ok(pi) = serde_json::from_str::<serde_json::Value>(&pi_json) {
+                alert["price_impact"] = pi;
+            }
+        }
+
+        if let Some(an_json) = anomalies_json {
+            if let Ok(an) = serde_json::from_str::<serde_json::Value>(&an_json) {
+                alert["anomalies"] = an;
+            }
+        }
+
         Ok(alert)
     })?;
 
-    for row in rows {
-        if let Ok(alert) = row {
-            alerts.push(alert);
-        }
+    for alert in rows.flatten() {
+        alerts.push(alert);
     }
 
     Ok(alerts)
 }
 
-/// Prune old alerts based on retention days
+/// Prune old alerts and coordinated flows based on retention days. Alert
+/// outcomes are kept: the tracker follows them for longer, and performance
+/// reports cover them.
 pub fn prune_old_alerts(conn: &Connection, retention_days: u32) {
+    // 0 means keep forever
+    if retention_days == 0 {
+        return;
+    }
     let seconds = retention_days as i64 * 86400;
     let result = conn.execute(
         "DELETE FROM alerts WHERE created_at < (strftime('%s', 'now') - ?1)",
@@ -231,19 +676,37 @@ pub fn prune_old_alerts(conn: &Connection, retention_days: u32) {
     if let Err(e) = result {
         eprintln!("Warning: Failed to prune old alerts: {}", e);
     }
+    let result = conn.execute(
+        "DELETE FROM coordinated_flows WHERE created_at < (strftime('%s', 'now') - ?1)",
+        params![seconds],
+    );
+    if let Err(e) = result {
+        eprintln!("Warning: Failed to prune coordinated flows: {}", e);
+    }
 }
 
 /// Prune expired wallet memory (12h window)
 pub fn prune_wallet_memory(conn: &Connection) {
     let result = conn.execute(
-        "DELETE FROM wallet_memory WHERE seen_at < (strftime('%s', 'now') - 43200)",
-        [],
+        "DELETE FROM wallet_memory WHERE seen_at < ?1 - 43200",
+        params![crate::capture::now().timestamp()],
     );
     if let Err(e) = result {
         eprintln!("Warning: Failed to prune wallet memory: {}", e);
     }
 }
 
+/// Prune baseline buckets older than the baseline window
+pub fn prune_baselines(conn: &Connection) {
+    let result = conn.execute(
+        "DELETE FROM market_baselines WHERE hour < ?1",
+        params![crate::capture::now().timestamp().div_euclid(3600) - BASELINE_DAYS * 24],
+    );
+    if let Err(e) = result {
+        eprintln!("Warning: Failed to prune market baselines: {}", e);
+    }
+}
+
 /// Migrate existing JSONL history to SQLite
 pub fn migrate_jsonl_if_exists(conn: &Connection) {
     let config_dir = match dirs::config_dir() {
@@ -280,11 +743,14 @@ pub fn migrate_jsonl_if_exists(conn: &Connection) {
             insert_alert(
                 conn,
                 platform,
+                None,
+                None,
                 alert_type,
                 action,
                 value,
                 price,
                 size,
+                1,
                 market_title,
                 None,
                 outcome,
@@ -292,6 +758,9 @@ pub fn migrate_jsonl_if_exists(conn: &Connection) {
                 timestamp,
                 None,
                 wa_json.as_deref(),
+                None,
+                None,
+                None,
             );
             count += 1;
         }
@@ -311,3 +780,93 @@ pub fn alert_count(conn: &Connection) -> i64 {
     conn.query_row("SELECT COUNT(*) FROM alerts", [], |row| row.get(0))
         .unwrap_or(0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(conn: &Connection, platform: &str, trade_id: Option<&str>) -> bool {
+        insert_order(conn, platform, trade_id, trade_id)
+    }
+
+    fn insert_order(conn: &Connection, platform: &str, trade_id: Option<&str>, order_id: Option<&str>) -> bool {
+        insert_alert(
+            conn, platform, trade_id, order_id, "WHALE_ENTRY", "BUY", 50000.0, 0.5, 100000.0, 1,
+            None, None, None, None, "2026-01-01T00:00:00Z", None, None, None, None, None,
+        )
+    }
+
+    #[test]
+    fn alerts_are_stored_once_per_platform_trade() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+
+        assert!(insert(&conn, "Polymarket", Some("0xabc")));
+        assert!(!insert(&conn, "Polymarket", Some("0xabc")));
+        assert!(insert(&conn, "Kalshi", Some("0xabc")));
+        assert!(alert_exists(&conn, "Polymarket", "0xabc"));
+
+        // Legacy rows without a trade ID never conflict
+        assert!(insert(&conn, "Polymarket", None));
+        assert!(insert(&conn, "Polymarket", None));
+        assert_eq!(alert_count(&conn), 4);
+
+        // Two orders in one Polymarket transaction share its hash as trade ID
+        assert!(insert_order(&conn, "Polymarket", Some("0xtx"), Some("0xtx|0xa|77|BUY")));
+        assert!(insert_order(&conn, "Polymarket", Some("0xtx"), Some("0xtx|0xb|77|SELL")));
+        assert!(!insert_order(&conn, "Polymarket", Some("0xtx"), Some("0xtx|0xb|77|SELL")));
+    }
+
+    #[test]
+    fn migration_adds_trade_id_to_existing_alerts_table() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE alerts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                platform TEXT NOT NULL,
+                alert_type TEXT NOT NULL,
+                action TEXT NOT NULL,
+                category TEXT,
+                subcategory TEXT,
+                value REAL NOT NULL,
+                price REAL NOT NULL,
+                size REAL NOT NULL,
+                market_title TEXT,
+                market_id TEXT,
+                outcome TEXT,
+                wallet_hash TEXT,
+                wallet_id TEXT,
+                timestamp TEXT NOT NULL,
+                market_context TEXT,
+                wallet_activity TEXT,
+                created_at INTEGER DEFAULT (strftime('%s', 'now'))
+            );
+            INSERT INTO alerts (platform, alert_type, action, value, price, size, timestamp) VALUES
+                ('Kalshi', 'WHALE_ENTRY', 'BUY', 1, 0.5, 2, '2026-10-01T12:00:00Z'),
+                ('Kalshi', 'WHALE_ENTRY', 'BUY', 1, 0.5, 2, '2026-10-01T12:00:00.900123Z'),
+                ('Polymarket', 'WHALE_ENTRY', 'BUY', 1, 0.5, 2, '2026-10-01T14:00:00.950+02:00');",
+        )
+        .unwrap();
+
+        init_schema(&conn).unwrap();
+        assert!(insert(&conn, "Kalshi", Some("t1")));
+        assert!(!insert(&conn, "Kalshi", Some("t1")));
+        assert_eq!(get_metadata(&conn, "schema_version").as_deref(), Some("2"));
+
+        // Older rows' mixed time formats are rewritten, so history sorts by trade time
+        let order: Vec<String> = query_alerts(&conn, 10, "all")
+            .unwrap()
+            .iter()
+            .map(|alert| alert["timestamp"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            order,
+            [
+                "2026-10-01T12:00:00.950Z",
+                "2026-10-01T12:00:00.900Z",
+                "2026-10-01T12:00:00.000Z",
+                "2026-01-01T00:00:00.000Z",
+            ]
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! Shared HTTP client for every market API call.
+//!
+//! One pooled `reqwest::Client` for the whole process, a token bucket per API
+//! host sized to that host's published rate limit, `Retry-After`-aware
+//! handling of 429s, and jittered retries for transient failures.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use reqwest::header::RETRY_AFTER;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use crate::capture::{self, HttpExchange};
+use crate::config::{self, Endpoints};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_RETRIES: u32 = 3;
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+/// Longer `Retry-After` values are returned to the caller instead of waited out
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// (sustained requests per second, burst size) for each API
+fn host_limits(endpoints: &Endpoints) -> [(&str, f64, f64); 4] {
+    [
+        // Kalshi Basic tier: 20 reads/s
+        (&endpoints.kalshi_api, 20.0, 20.0),
+        // Data API: /positions and /closed-positions allow 150 req/10s, the strictest endpoint we call
+        (&endpoints.polymarket_data_api, 15.0, 15.0),
+        // Gamma /markets: 300 req/10s
+        (&endpoints.polymarket_gamma_api, 30.0, 30.0),
+        // CLOB /book: 1500 req/10s
+        (&endpoints.polymarket_clob_api, 150.0, 150.0),
+    ]
+}
+
+#[derive(Error, Debug)]
+pub enum HttpError {
+    #[error("rate limited{}", retry_hint(.0))]
+    RateLimited(Option<Duration>),
+    #[error("unauthorized (status {0})")]
+    Unauthorized(StatusCode),
+    #[error("not found")]
+    NotFound,
+    #[error("API returned status: {0}")]
+    Status(StatusCode),
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("failed to decode response: {0}")]
+    Decode(String),
+    /// A replayed request that failed while recording, or was never recorded
+    #[error("unavailable in replay: {0}")]
+    Unavailable(String),
+}
+
+/// ", retry after Ns" when the server said how long to wait
+pub fn retry_hint(retry_after: &Option<Duration>) -> String {
+    retry_after
+        .map(|d| format!(", retry after {}s", d.as_secs()))
+        .unwrap_or_default()
+}
+
+/// Token bucket for one host. A 429 pauses the whole host, not just the
+/// request that got it.
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, capacity: f64) -> Self {
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            last_refill: Instant::now(),
+            paused_until: None,
+        }
+    }
+
+    /// Take a token, or return how long to wait before trying again
+    fn try_take(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        if let Some(until) = self.paused_until {
+            if now < until {
+                return Err(until - now);
+            }
+            self.paused_until = None;
+        }
+
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+
+    fn pause(&mut self, delay: Duration) {
+        let until = Instant::now() + delay;
+        self.paused_until = Some(self.paused_until.map_or(until, |u| u.max(until)));
+    }
+}
+
+pub struct HttpClient {
+    client: reqwest::Client,
+    buckets: HashMap<String, Mutex<TokenBucket>>,
+}
+
+/// The process-wide client
+pub fn shared() -> &'static HttpClient {
+    static CLIENT: OnceLock<HttpClient> = OnceLock::new();
+    CLIENT.get_or_init(HttpClient::new)
+}
+
+impl HttpClient {
+    fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("failed to build HTTP client");
+
+        // Keyed by host of the configured endpoint. If several APIs share a host
+        // (a proxy or mock server), the first one listed applies.
+        let mut buckets = HashMap::new();
+        for (url, rate, burst) in host_limits(config::endpoints()) {
+            if let Some(host) = reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)) {
+                buckets
+                    .entry(host)
+                    .or_insert_with(|| Mutex::new(TokenBucket::new(rate, burst)));
+            }
+        }
+
+        Self { client, buckets }
+    }
+
+    /// Wait until the host's rate limit allows another request
+    async fn acquire(&self, host: &str) {
+        let Some(bucket) = self.buckets.get(host) else {
+            return;
+        };
+
+        loop {
+            let wait = match bucket.lock().unwrap().try_take() {
+                Ok(()) => return,
+                Err(wait) => wait,
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn pause_host(&self, host: &str, delay: Duration) {
+        if let Some(bucket) = self.buckets.get(host) {
+            bucket.lock().unwrap().pause(delay);
+        }
+    }
+
+    /// Send the request made by `build`, retrying 429s and transient failures.
+    /// `build` runs once per attempt, so signed requests get a fresh signature.
+    pub async fn send<F>(&self, build: F) -> Result<Response, HttpError>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let mut attempt = 0;
+
+        loop {
+            let request = build(&self.client).build()?;
+            let host = request.url().host_str().unwrap_or_default().to_string();
+            self.acquire(&host).await;
+
+            let delay = match self.client.execute(request).await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => match response.status() {
+                    StatusCode::TOO_MANY_REQUESTS => {
+                        let retry_after = parse_retry_after(&response);
+                        let delay = retry_after.unwrap_or_else(|| backoff(attempt));
+                        self.pause_host(&host, delay);
+                        if attempt >= MAX_RETRIES || delay > MAX_RETRY_AFTER {
+                            return Err(HttpError::RateLimited(retry_after));
+                        }
+                        delay
+                    }
+                    status @ (StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => {
+                        return Err(HttpError::Unauthorized(status));
+                    }
+                    StatusCode::NOT_FOUND => return Err(HttpError::NotFound),
+                    status if status.is_server_error() && attempt < MAX_RETRIES => backoff(attempt),
+                    status => return Err(HttpError::Status(status)),
+                },
+                Err(e) if (e.is_timeout() || e.is_connect()) && attempt < MAX_RETRIES => backoff(attempt),
+                Err(e) => return Err(e.into()),
+            };
+
+            attempt += 1;
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Send and read the body as text. This is the point where responses
+    /// are captured with `--record` and served back by `replay`.
+    pub async fn get_text<F>(&self, build: F) -> Result<String, HttpError>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        if let Some(replayer) = capture::replayer() {
+            let request = build(&self.client).build()?;
+            return match replayer.http(request.url().as_str(), request_body(&request).as_deref()) {
+                Some(exchange) => from_exchange(exchange),
+                None => Err(HttpError::Unavailable(format!("no recorded response for {}", request.url()))),
+            };
+        }
+
+        let result = match self.send(&build).await {
+            Ok(response) => response.text().await.map_err(HttpError::from),
+            Err(e) => Err(e),
+        };
+
+        if let Some(recorder) = capture::recorder() {
+            if let Ok(request) = build(&self.client).build() {
+                recorder.http(to_exchange(&request, &result));
+            }
+        }
+
+        result
+    }
+
+    /// Send and deserialize a JSON body
+    pub async fn get_json<T, F>(&self, build: F) -> Result<T, HttpError>
+    where
+        T: DeserializeOwned,
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let text = self.get_text(build).await?;
+        serde_json::from_str(&text).map_err(|e| HttpError::Decode(e.to_string()))
+    }
+}
+
+/// Body a request was built with, part of its capture key
+fn request_body(request: &reqwest::Request) -> Option<String> {
+    let bytes = request.body()?.as_bytes()?;
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn to_exchange(request: &reqwest::Request, result: &Result<String, HttpError>) -> HttpExchange {
+    let (status, retry_after) = match result {
+        Ok(_) => (StatusCode::OK.as_u16(), None),
+        Err(HttpError::RateLimited(retry_after)) => (StatusCode::TOO_MANY_REQUESTS.as_u16(), *retry_after),
+        Err(HttpError::Unauthorized(status) | HttpError::Status(status)) => (status.as_u16(), None),
+        Err(HttpError::NotFound) => (StatusCode::NOT_FOUND.as_u16(), None),
+        Err(_) => (0, None),
+    };
+
+    HttpExchange {
+        url: request.url().to_string(),
+        request_body: request_body(request),
+        status,
+        body: result.as_ref().ok().cloned(),
+        retry_after_secs: retry_after.map(|d| d.as_secs()),
+        error: result.as_ref().err().map(|e| e.to_string()),
+    }
+}
+
+fn from_exchange(exchange: HttpExchange) -> Result<String, HttpError> {
+    if let Some(body) = exchange.body {
+        return Ok(body);
+    }
+
+    let error = exchange.error.unwrap_or_default();
+    match StatusCode::from_u16(exchange.status) {
+        Ok(StatusCode::TOO_MANY_REQUESTS) => Err(HttpError::RateLimited(exchange.retry_after_secs.map(Duration::from_secs))),
+        Ok(status @ (StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)) => Err(HttpError::Unauthorized(status)),
+        Ok(StatusCode::NOT_FOUND) => Err(HttpError::NotFound),
+        Ok(status) => Err(HttpError::Status(status)),
+        Err(_) => Err(HttpError::Unavailable(error)),
+    }
+}
+
+/// `Retry-After` as delay-seconds or an HTTP date
+fn parse_retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let secs = (at.timestamp() - chrono::Utc::now().timestamp()).max(0);
+    Some(Duration::from_secs(secs as u64))
+}
+
+/// Exponential backoff with jitter: a random delay between half and all of
+/// `BACKOFF_BASE * 2^attempt`, so concurrent retries don't line up
+fn backoff(attempt: u32) -> Duration {
+    let ceiling = BACKOFF_BASE * 2u32.pow(attempt);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    let jitter = f64::from(nanos % 1000) / 1000.0;
+    ceiling.mul_f64(0.5 + jitter * 0.5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_allows_burst_then_waits() {
+        let mut bucket = TokenBucket::new(10.0, 2.0);
+        assert!(bucket.try_take().is_ok());
+        assert!(bucket.try_take().is_ok());
+
+        let wait = bucket.try_take().unwrap_err();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn pause_blocks_until_it_expires() {
+        let mut bucket = TokenBucket::new(10.0, 10.0);
+        bucket.pause(Duration::from_secs(5));
+        let wait = bucket.try_take().unwrap_err();
+        assert!(wait > Duration::from_secs(4));
+    }
+
+    #[test]
+    fn backoff_grows_and_stays_within_bounds() {
+        for attempt in 0..MAX_RETRIES {
+            let ceiling = BACKOFF_BASE * 2u32.pow(attempt);
+            let delay = backoff(attempt);
+            assert!(delay >= ceiling / 2 && delay <= ceiling);
+        }
+    }
+}
@@ -1,13 +1,22 @@
 mod alerts;
+mod capture;
 mod categories;
+mod clusters;
 mod commands;
 mod config;
 mod db;
+mod http;
+mod outcomes;
+mod pipeline;
 mod platforms;
+mod polygon;
+mod threshold;
 mod types;
 mod whale_profile;
 mod ws;
 
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
@@ -29,6 +38,37 @@ enum Commands {
         /// Polling interval in seconds
         #[arg(short, long, default_value = "5")]
         interval: u64,
+
+        /// Record every raw API response and WebSocket frame to this directory
+        #[arg(long, value_name = "DIR")]
+        record: Option<PathBuf>,
+
+        /// Print which filter rejected each trade over the threshold
+        #[arg(long)]
+        explain: bool,
+    },
+    /// Replay a recording made with `watch --record`, offline and on a virtual clock
+    Replay {
+        /// Directory written by `watch --record`
+        dir: PathBuf,
+
+        /// Print which filter rejected each trade over the threshold
+        #[arg(long)]
+        explain: bool,
+    },
+    /// Load past whale trades into the database, without sending webhooks
+    Backfill {
+        /// Date to backfill from (YYYY-MM-DD, UTC)
+        #[arg(long, value_name = "DATE")]
+        since: String,
+
+        /// Platform: polymarket, kalshi, or all (default: all)
+        #[arg(short, long, default_value = "all")]
+        platform: String,
+
+        /// Minimum transaction size to store (in USD)
+        #[arg(short, long, default_value = "25000")]
+        threshold: u64,
     },
     /// View alert history
     History {
@@ -43,6 +83,33 @@ enum Commands {
         /// Output as JSON
         #[arg(long)]
         json: bool,
+
+        /// Show coordinated flows (several wallets on one outcome) instead of alerts
+        #[arg(long)]
+        coordinated: bool,
+    },
+    /// Report how alerted markets moved afterwards: hit rate and average move in the whale's direction
+    Performance {
+        /// Filter by platform: polymarket, kalshi, or all (default: all)
+        #[arg(short, long, default_value = "all")]
+        platform: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List wallet clusters, or the members of one
+    Wallet {
+        /// Cluster ID or wallet address to show
+        query: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Relink every wallet in alert history first (looking up funding with a Polygon RPC endpoint)
+        #[arg(long)]
+        rebuild: bool,
     },
     /// Configure API credentials
     Setup,
@@ -74,15 +141,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Commands::Watch {
             threshold,
             interval,
+            record,
+            explain,
         } => {
-            commands::watch::watch_whales(threshold, interval, conn).await?;
+            commands::watch::watch_whales(threshold, interval, record, explain, conn).await?;
+        }
+        Commands::Replay { dir, explain } => {
+            commands::replay::replay_capture(dir, explain)?;
+        }
+        Commands::Backfill {
+            since,
+            platform,
+            threshold,
+        } => {
+            commands::backfill::backfill(&since, &platform, threshold, &conn).await?;
         }
         Commands::History {
             limit,
             platform,
             json,
+            coordinated,
         } => {
-            alerts::history::show_alert_history(limit, &platform, json, &conn)?;
+            if coordinated {
+                alerts::history::show_coordinated_flows(limit, &platform, json, &conn)?;
+            } else {
+                alerts::history::show_alert_history(limit, &platform, json, &conn)?;
+            }
+        }
+        Commands::Performance { platform, json } => {
+            commands::performance::show_performance(&platform, json, &conn)?;
+        }
+        Commands::Wallet { query, json, rebuild } => {
+            commands::wallet::show_wallets(query.as_deref(), json, rebuild, &conn).await?;
         }
         Commands::TestSound => {
             commands::test::test_sound().await?;
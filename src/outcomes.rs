@@ -0,0 +1,344 @@
+//! Alert outcomes: where each alerted market's price went afterwards.
+//!
+//! Every stored alert gets a row in `alert_outcomes` with the price it was
+//! alerted at and the dimensions it is reported by, so the row outlives the
+//! alert when old alerts are pruned. The outcome tracker fills in the price at each checkpoint
+//! (+15m, +1h, +24h) and the settled price once the market resolves, and
+//! `wwatcher performance` turns the rows into a hit rate and an average move
+//! in the whale's direction.
+
+use std::collections::{BTreeMap, HashMap};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::pipeline::impact::direction;
+use crate::platforms::{NormalizedTrade, Platform};
+
+/// A price checkpoint after the alert
+pub struct Checkpoint {
+    pub label: &'static str,
+    column: &'static str,
+    /// Seconds after the alert
+    pub offset: i64,
+    /// Seconds after `offset` a price still counts for the checkpoint
+    pub tolerance: i64,
+}
+
+pub const CHECKPOINTS: [Checkpoint; 3] = [
+    Checkpoint { label: "15m", column: "price_15m", offset: 15 * 60, tolerance: 15 * 60 },
+    Checkpoint { label: "1h", column: "price_1h", offset: 60 * 60, tolerance: 30 * 60 },
+    Checkpoint { label: "24h", column: "price_24h", offset: 24 * 3600, tolerance: 6 * 3600 },
+];
+/// Label of the settled price in reports
+pub const RESOLUTION: &str = "resolution";
+
+/// Wait before retrying a checkpoint whose price couldn't be fetched
+pub const RETRY_SECS: i64 = 60;
+/// Time between resolution checks once the checkpoints are done
+const RESOLUTION_INTERVAL: i64 = 6 * 3600;
+/// Markets still open this long after the alert stop being checked
+const RESOLUTION_MAX: i64 = 90 * 86400;
+
+/// An alert whose outcome is still being followed
+pub struct PendingOutcome {
+    pub alert_id: i64,
+    pub platform: Platform,
+    pub market_id: String,
+    pub asset_id: String,
+    pub outcome: Option<String>,
+    pub side: String,
+    pub alerted_at: i64,
+    pub prices: [Option<f64>; 3],
+}
+
+impl PendingOutcome {
+    /// The alerted trade, as far as the sources need it to look the market up
+    pub fn trade(&self) -> NormalizedTrade {
+        NormalizedTrade {
+            platform: self.platform,
+            trade_id: self.alert_id.to_string(),
+            market_id: self.market_id.clone(),
+            asset_id: self.asset_id.clone(),
+            market_title: None,
+            category: None,
+            outcome: self.outcome.clone(),
+            side: self.side.clone(),
+            price: 0.0,
+            size: 0.0,
+            timestamp: String::new(),
+            wallet_id: None,
+            fills: 1,
+        }
+    }
+}
+
+/// What the tracker should fetch for an outcome at `now`
+#[derive(Debug, PartialEq)]
+pub enum Step {
+    /// Price the checkpoint at this index of `CHECKPOINTS`
+    Price(usize),
+    /// Every checkpoint is priced or missed: check whether the market resolved
+    Resolve,
+    /// Nothing is due yet
+    Wait,
+}
+
+/// The step due at `now`. A checkpoint is only priced within its tolerance;
+/// once that passes it is left empty as missed.
+pub fn step(alerted_at: i64, prices: &[Option<f64>; 3], now: i64) -> Step {
+    let elapsed = now - alerted_at;
+    for (index, checkpoint) in CHECKPOINTS.iter().enumerate() {
+        if prices[index].is_some() || elapsed > checkpoint.offset + checkpoint.tolerance {
+            continue;
+        }
+        return if elapsed >= checkpoint.offset { Step::Price(index) } else { Step::Wait };
+    }
+    Step::Resolve
+}
+
+/// When to look at an unresolved outcome next, or `None` to stop following it
+pub fn next_check(alerted_at: i64, prices: &[Option<f64>; 3], now: i64) -> Option<i64> {
+    match step(alerted_at, prices, now) {
+        Step::Price(index) => {
+            let checkpoint = &CHECKPOINTS[index];
+            Some((now + RETRY_SECS).min(alerted_at + checkpoint.offset + checkpoint.tolerance))
+        }
+        Step::Wait => CHECKPOINTS
+            .iter()
+            .enumerate()
+            .find(|(index, checkpoint)| {
+                prices[*index].is_none() && now - alerted_at <= checkpoint.offset + checkpoint.tolerance
+            })
+            .map(|(_, checkpoint)| alerted_at + checkpoint.offset),
+        Step::Resolve => {
+            let give_up = alerted_at + RESOLUTION_MAX;
+            (now < give_up).then(|| (now + RESOLUTION_INTERVAL).min(give_up))
+        }
+    }
+}
+
+/// Start following a just-stored alert
+pub fn record_alert(
+    conn: &Connection,
+    trade: &NormalizedTrade,
+    category: Option<&str>,
+    band: Option<&str>,
+    tier: &str,
+    alerted_at: i64,
+) {
+    let platform = trade.platform.name();
+    if let Err(e) = conn.execute(
+        "INSERT OR IGNORE INTO alert_outcomes (alert_id, platform, market_id, asset_id, outcome, side, direction,
+         category, band, tier, wallet_id, alerted_at, price_at_alert, next_check_at)
+         SELECT id, ?1, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14 FROM alerts WHERE platform = ?1 AND order_id = ?2",
+        params![
+            platform,
+            trade.order_id(),
+            trade.market_id,
+            trade.asset_id,
+            trade.outcome,
+            trade.side,
+            direction(trade),
+            category,
+            band,
+            tier,
+            trade.wallet_id,
+            alerted_at,
+            trade.price,
+            alerted_at + CHECKPOINTS[0].offset,
+        ],
+    ) {
+        eprintln!("Warning: Failed to record alert outcome: {}", e);
+    }
+}
+
+/// Outcomes on `platform` with a check due by `now`, oldest first
+pub fn load_due(conn: &Connection, platform: Platform, now: i64, limit: usize) -> rusqlite::Result<Vec<PendingOutcome>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT alert_id, market_id, asset_id, outcome, side, alerted_at, price_15m, price_1h, price_24h
+         FROM alert_outcomes WHERE platform = ?1 AND next_check_at <= ?2
+         ORDER BY next_check_at LIMIT ?3",
+    )?;
+    let rows = stmt.query_map(params![platform.name(), now, limit as i64], |row| {
+        Ok(PendingOutcome {
+            alert_id: row.get(0)?,
+            platform,
+            market_id: row.get(1)?,
+            asset_id: row.get(2)?,
+            outcome: row.get(3)?,
+            side: row.get(4)?,
+            alerted_at: row.get(5)?,
+            prices: [row.get(6)?, row.get(7)?, row.get(8)?],
+        })
+    })?;
+    Ok(rows.flatten().collect())
+}
+
+/// Store the price at a checkpoint
+pub fn store_checkpoint(conn: &Connection, alert_id: i64, index: usize, price: f64) {
+    let sql = format!("UPDATE alert_outcomes SET {} = ?2 WHERE alert_id = ?1", CHECKPOINTS[index].column);
+    if let Err(e) = conn.execute(&sql, params![alert_id, price]) {
+        eprintln!("Warning: Failed to store alert outcome: {}", e);
+    }
+}
+
+/// Store the settled price; the outcome is no longer checked
+pub fn store_resolution(conn: &Connection, alert_id: i64, price: f64, now: i64) {
+    if let Err(e) = conn.execute(
+        "UPDATE alert_outcomes SET resolved_price = ?2, resolved_at = ?3, next_check_at = NULL WHERE alert_id = ?1",
+        params![alert_id, price, now],
+    ) {
+        eprintln!("Warning: Failed to store alert resolution: {}", e);
+    }
+}
+
+/// Schedule the next check, or stop checking with `None`
+pub fn set_next_check(conn: &Connection, alert_id: i64, next_check_at: Option<i64>) {
+    if let Err(e) = conn.execute(
+        "UPDATE alert_outcomes SET next_check_at = ?2 WHERE alert_id = ?1",
+        params![alert_id, next_check_at],
+    ) {
+        eprintln!("Warning: Failed to schedule alert outcome: {}", e);
+    }
+}
+
+/// Moves at one horizon across a group of alerts
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct HorizonStats {
+    pub samples: u32,
+    /// Share of samples that moved the whale's way
+    pub hit_rate: Option<f64>,
+    /// Mean move in the whale's direction (price units, 0.01 = one cent)
+    pub avg_move: Option<f64>,
+    #[serde(skip)]
+    hits: u32,
+    #[serde(skip)]
+    move_sum: f64,
+}
+
+impl HorizonStats {
+    fn add(&mut self, moved: f64) {
+        self.samples += 1;
+        self.move_sum += moved;
+        if moved > 0.0 {
+            self.hits += 1;
+        }
+        self.hit_rate = Some(self.hits as f64 / self.samples as f64);
+        self.avg_move = Some(self.move_sum / self.samples as f64);
+    }
+}
+
+/// Alerts sharing one value of a report dimension
+#[derive(Debug, Clone, Serialize)]
+pub struct PerformanceGroup {
+    pub key: String,
+    pub alerts: u32,
+    /// Keyed by checkpoint label, then `RESOLUTION`
+    pub horizons: BTreeMap<&'static str, HorizonStats>,
+}
+
+/// Report dimensions, in display order
+pub const DIMENSIONS: [&str; 5] = ["platform", "category", "tier", "band", "wallet"];
+
+/// Performance of every followed alert, including alerts since pruned, grouped
+/// by each of `DIMENSIONS`. Groups are sorted by alert count, largest first.
+pub fn performance(
+    conn: &Connection,
+    platform_filter: &str,
+) -> Result<BTreeMap<&'static str, Vec<PerformanceGroup>>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT platform, category, tier, band, wallet_id,
+                direction, price_at_alert, price_15m, price_1h, price_24h, resolved_price
+         FROM alert_outcomes
+         WHERE ?1 = 'all' OR LOWER(platform) = LOWER(?1)",
+    )?;
+    let rows = stmt.query_map(params![platform_filter], |row| {
+        let keys: [Option<String>; 5] = [row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?];
+        let direction: f64 = row.get(5)?;
+        let at_alert: f64 = row.get(6)?;
+        let prices: [Option<f64>; 4] = [row.get(7)?, row.get(8)?, row.get(9)?, row.get(10)?];
+        Ok((keys, prices.map(|p| p.map(|p| direction * (p - at_alert)))))
+    })?;
+
+    let labels: Vec<&'static str> = CHECKPOINTS.iter().map(|c| c.label).chain([RESOLUTION]).collect();
+    let mut groups: Vec<HashMap<String, PerformanceGroup>> = vec![HashMap::new(); DIMENSIONS.len()];
+    for (keys, moves) in rows.flatten() {
+        for (dimension, key) in keys.into_iter().enumerate() {
+            let key = key.unwrap_or_else(|| "unknown".into());
+            let group = groups[dimension].entry(key.clone()).or_insert_with(|| PerformanceGroup {
+                key,
+                alerts: 0,
+                horizons: labels.iter().map(|&label| (label, HorizonStats::default())).collect(),
+            });
+            group.alerts += 1;
+            for (label, moved) in labels.iter().zip(moves) {
+                if let (Some(stats), Some(moved)) = (group.horizons.get_mut(label), moved) {
+                    stats.add(moved);
+                }
+            }
+        }
+    }
+
+    Ok(DIMENSIONS
+        .iter()
+        .zip(groups)
+        .map(|(&dimension, groups)| {
+            let mut groups: Vec<PerformanceGroup> = groups.into_values().collect();
+            groups.sort_by(|a, b| b.alerts.cmp(&a.alerts).then_with(|| a.key.cmp(&b.key)));
+            (dimension, groups)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoints_are_priced_within_their_tolerance_then_resolution_is_checked() {
+        let at = 1_000_000;
+        let none = [None; 3];
+        assert_eq!(step(at, &none, at + 60), Step::Wait);
+        assert_eq!(next_check(at, &none, at + 60), Some(at + 15 * 60));
+        assert_eq!(step(at, &none, at + 20 * 60), Step::Price(0));
+        // A failed fetch is retried, but not past the checkpoint's tolerance
+        assert_eq!(next_check(at, &none, at + 20 * 60), Some(at + 21 * 60));
+        assert_eq!(next_check(at, &none, at + 30 * 60 - 10), Some(at + 30 * 60));
+
+        // The 15m price was missed; the next is the 1h checkpoint
+        assert_eq!(step(at, &none, at + 31 * 60), Step::Wait);
+        assert_eq!(next_check(at, &none, at + 31 * 60), Some(at + 3600));
+        let priced = [Some(0.5), Some(0.55), None];
+        assert_eq!(next_check(at, &priced, at + 3600), Some(at + 24 * 3600));
+
+        // After the last checkpoint only resolution is left, until it's given up on
+        assert_eq!(step(at, &priced, at + 31 * 3600), Step::Resolve);
+        assert_eq!(next_check(at, &priced, at + 31 * 3600), Some(at + 37 * 3600));
+        assert_eq!(next_check(at, &priced, at + RESOLUTION_MAX), None);
+    }
+
+    #[test]
+    fn outcomes_outlive_pruned_alerts() {
+        let conn = crate::db::open_in_memory();
+        let trade = NormalizedTrade { wallet_id: Some("0xw".into()), ..NormalizedTrade::test("t1") };
+        crate::db::insert_alert(
+            &conn, "Polymarket", Some("t1"), Some(&trade.order_id()), "WHALE_ENTRY", "BUY", 50.0, 0.5, 100.0, 1,
+            None, Some("m"), None, Some("0xw"), &trade.timestamp, None, None, None, None, None,
+        );
+        record_alert(&conn, &trade, Some("politics"), Some("WHALE"), "high", 1_000_000);
+        store_checkpoint(&conn, 1, 0, 0.6);
+
+        // Past the 30-day retention while the tracker still follows the market
+        conn.execute("UPDATE alerts SET created_at = created_at - 31 * 86400", []).unwrap();
+        crate::db::prune_old_alerts(&conn, 30);
+        assert_eq!(crate::db::alert_count(&conn), 0);
+
+        let report = performance(&conn, "all").unwrap();
+        for (dimension, key) in [("platform", "Polymarket"), ("tier", "high"), ("band", "WHALE"), ("wallet", "0xw")] {
+            let group = &report[dimension][0];
+            assert_eq!((group.key.as_str(), group.alerts), (key, 1));
+            assert_eq!(group.horizons["15m"].samples, 1);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Accumulation detection: a wallet building a position through trades that
+//! are each under the alert threshold.
+//!
+//! Flow is tracked per wallet, market outcome and side over a rolling window
+//! of trade time. When it reaches the accumulation threshold, one
+//! `WHALE_ACCUMULATION` alert covers every trade counted, and counting starts
+//! over.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::alerts::Accumulation;
+use crate::platforms::NormalizedTrade;
+
+/// Flows not traded for a whole window are dropped this often (in trades recorded)
+const PRUNE_EVERY: u32 = 1024;
+
+struct FlowTrade {
+    trade_id: String,
+    ts: i64,
+    timestamp: String,
+    value: f64,
+    size: f64,
+    fills: u32,
+}
+
+pub struct AccumulationTracker {
+    threshold: f64,
+    window_minutes: u64,
+    /// Trades in the window per (wallet, asset, side), oldest first
+    flows: HashMap<String, VecDeque<FlowTrade>>,
+    /// Newest trade time seen (Unix seconds)
+    newest: i64,
+    since_prune: u32,
+}
+
+impl AccumulationTracker {
+    /// `None` when accumulation alerts are disabled (threshold 0)
+    pub fn new(threshold: u64, window_minutes: u64) -> Option<Self> {
+        (threshold > 0).then(|| Self {
+            threshold: threshold as f64,
+            window_minutes,
+            flows: HashMap::new(),
+            newest: 0,
+            since_prune: 0,
+        })
+    }
+
+    fn window_secs(&self) -> i64 {
+        self.window_minutes as i64 * 60
+    }
+
+    /// Count a trade under its market's threshold. When it takes its flow to
+    /// the threshold, returns the alert: one trade for the whole flow (total
+    /// size, VWAP price, the crossing trade's time) and its summary.
+    pub fn record(&mut self, trade: &NormalizedTrade) -> Option<(NormalizedTrade, Accumulation)> {
+        let wallet = trade.wallet_id.as_deref()?;
+        let ts = trade.unix_timestamp()?;
+        self.newest = self.newest.max(ts);
+        self.maybe_prune();
+
+        let earliest = ts - self.window_secs();
+        let key = format!("{}|{}|{}", wallet, trade.asset_id, trade.side);
+        let flow = self.flows.entry(key.clone()).or_default();
+
+        // Polling can hand back an order the stream already delivered
+        if flow.iter().any(|t| t.trade_id == trade.trade_id) {
+            return None;
+        }
+        flow.push_back(FlowTrade {
+            trade_id: trade.trade_id.clone(),
+            ts,
+            timestamp: trade.timestamp.clone(),
+            value: trade.value(),
+            size: trade.size,
+            fills: trade.fills,
+        });
+        flow.retain(|t| t.ts >= earliest);
+
+        let total_value: f64 = flow.iter().map(|t| t.value).sum();
+        if total_value < self.threshold {
+            return None;
+        }
+
+        let flow = self.flows.remove(&key)?;
+        let size: f64 = flow.iter().map(|t| t.size).sum();
+        let first_trade_at = flow
+            .iter()
+            .min_by_key(|t| t.ts)
+            .map(|t| t.timestamp.clone())
+            .unwrap_or_default();
+
+        let alert = NormalizedTrade {
+            trade_id: format!("accum:{}", trade.trade_id),
+            price: if size > 0.0 { total_value / size } else { trade.price },
+            size,
+            fills: flow.iter().map(|t| t.fills).sum(),
+            ..trade.clone()
+        };
+        let summary = Accumulation {
+            trades: flow.len() as u32,
+            total_value,
+            window_minutes: self.window_minutes,
+            first_trade_at,
+        };
+        Some((alert, summary))
+    }
+
+    fn maybe_prune(&mut self) {
+        self.since_prune += 1;
+        if self.since_prune < PRUNE_EVERY {
+            return;
+        }
+        self.since_prune = 0;
+        let earliest = self.newest - self.window_secs();
+        self.flows
+            .retain(|_, flow| flow.back().is_some_and(|t| t.ts >= earliest));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(id: &str, minute: u32, value: f64) -> NormalizedTrade {
+        NormalizedTrade {
+            asset_id: "yes".into(),
+            outcome: Some("Yes".into()),
+            size: value / 0.5,
+            timestamp: format!("2026-10-01T12:{:02}:00+00:00", minute),
+            wallet_id: Some("0xw".into()),
+            ..NormalizedTrade::test(id)
+        }
+    }
+
+    #[test]
+    fn fires_once_when_windowed_flow_crosses_threshold() {
+        let mut tracker = AccumulationTracker::new(50_000, 30).unwrap();
+
+        // Falls out of the 30 min window before the others arrive
+        assert!(tracker.record(&trade("old", 0, 15_000.0)).is_none());
+        assert!(tracker.record(&trade("a", 40, 15_000.0)).is_none());
+        assert!(tracker.record(&trade("a", 40, 15_000.0)).is_none());
+        assert!(tracker.record(&trade("b", 50, 15_000.0)).is_none());
+
+        let (alert, summary) = tracker.record(&trade("c", 59, 20_000.0)).unwrap();
+        assert_eq!(alert.trade_id, "accum:c");
+        assert_eq!(summary.trades, 3);
+        assert_eq!(summary.total_value, 50_000.0);
+        assert_eq!(summary.first_trade_at, "2026-10-01T12:40:00+00:00");
+        assert_eq!(alert.value(), 50_000.0);
+
+        // Counting starts over
+        assert!(tracker.record(&trade("d", 59, 15_000.0)).is_none());
+    }
+}
@@ -0,0 +1,244 @@
+//! Merges the fills of one order back into a single trade before the
+//! threshold check.
+//!
+//! A whale sweeping the book shows up as many fills: on Polymarket they share
+//! a transaction hash, on Kalshi they are taker trades on one ticker and side
+//! within a second of each other. Each fill alone may be under the threshold.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::platforms::{NormalizedTrade, Platform};
+
+/// How long an order stays open for more fills after its latest one
+const SETTLE: Duration = Duration::from_secs(1);
+/// Kalshi fills at most this far apart (trade time, seconds) belong to one order
+const KALSHI_WINDOW_SECS: i64 = 1;
+/// Fills below this share of the threshold aren't fetched from APIs that
+/// filter by size server-side
+const MIN_FILL_SHARE: f64 = 0.1;
+
+/// Smallest fill to request from a size-filtered API, so the fills of a
+/// whale-sized order aren't filtered out before they can be merged
+pub fn min_fill_value(threshold: u64) -> u64 {
+    (threshold as f64 * MIN_FILL_SHARE) as u64
+}
+
+/// Split the fills that may belong to an order continuing on the next, older
+/// page off a newest-first page: those within `KALSHI_WINDOW_SECS` of its
+/// oldest fill. They are merged together with the next page.
+pub fn split_trailing_fills(page: &mut Vec<NormalizedTrade>) -> Vec<NormalizedTrade> {
+    let Some(oldest) = page.iter().filter_map(NormalizedTrade::unix_timestamp).min() else {
+        return Vec::new();
+    };
+    let (trailing, rest): (Vec<_>, Vec<_>) = page
+        .drain(..)
+        .partition(|fill| fill.unix_timestamp().is_some_and(|ts| ts <= oldest + KALSHI_WINDOW_SECS));
+    *page = rest;
+    trailing
+}
+
+/// Fills grouped into orders, per platform key:
+/// - Polymarket: transaction hash, wallet, asset and side
+/// - Kalshi: ticker and side, within `KALSHI_WINDOW_SECS` of the first fill
+fn order_key(fill: &NormalizedTrade) -> String {
+    match fill.platform {
+        Platform::Polymarket => fill.order_id(),
+        Platform::Kalshi => format!("{}|{}", fill.market_id, fill.side),
+    }
+}
+
+struct OpenOrder {
+    key: String,
+    fills: Vec<NormalizedTrade>,
+    last_fill_at: Instant,
+}
+
+impl OpenOrder {
+    /// Whether `fill` is too far from the first fill to be part of this order
+    fn outside_window(&self, fill: &NormalizedTrade) -> bool {
+        if fill.platform != Platform::Kalshi {
+            return false;
+        }
+        match (self.fills[0].unix_timestamp(), fill.unix_timestamp()) {
+            (Some(first), Some(ts)) => (ts - first).abs() > KALSHI_WINDOW_SECS,
+            _ => false,
+        }
+    }
+
+    /// One trade for the whole order: total size, VWAP price, and the first
+    /// fill's ID and time, so the same order gets the same ID however its
+    /// fills arrived
+    fn merge(self) -> NormalizedTrade {
+        let mut fills = self.fills.into_iter();
+        let mut order = fills.next().expect("an open order has at least one fill");
+        let mut notional = order.price * order.size;
+
+        for fill in fills {
+            notional += fill.price * fill.size;
+            order.size += fill.size;
+            order.fills += fill.fills;
+        }
+        if order.size > 0.0 {
+            order.price = notional / order.size;
+        }
+        order
+    }
+}
+
+/// Collects fills until their order settles, then releases the merged order
+#[derive(Default)]
+pub struct FillAggregator {
+    /// Open orders by arrival, so they're released in the order they started
+    open: BTreeMap<u64, OpenOrder>,
+    by_key: HashMap<String, u64>,
+    next_id: u64,
+    /// Orders closed by a later fill outside their window
+    closed: Vec<NormalizedTrade>,
+}
+
+impl FillAggregator {
+    /// Add a fill received at `now`. Fills should arrive oldest first.
+    pub fn push(&mut self, fill: NormalizedTrade, now: Instant) {
+        let key = order_key(&fill);
+
+        if let Some(&id) = self.by_key.get(&key) {
+            let order = self.open.get_mut(&id).expect("indexed order is open");
+            if !order.outside_window(&fill) {
+                // The same fill can arrive twice (stream and polling overlap)
+                if !order.fills.iter().any(|f| f.is_same_fill(&fill)) {
+                    order.fills.push(fill);
+                    order.last_fill_at = now;
+                }
+                return;
+            }
+            let order = self.open.remove(&id).expect("indexed order is open");
+            self.closed.push(order.merge());
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.by_key.insert(key.clone(), id);
+        self.open.insert(
+            id,
+            OpenOrder {
+                key,
+                fills: vec![fill],
+                last_fill_at: now,
+            },
+        );
+    }
+
+    /// When the next open order settles
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.open.values().map(|o| o.last_fill_at + SETTLE).min()
+    }
+
+    /// Merged orders that are complete as of `now`, in arrival order
+    pub fn flush(&mut self, now: Instant) -> Vec<NormalizedTrade> {
+        let settled: Vec<u64> = self
+            .open
+            .iter()
+            .filter(|(_, o)| o.last_fill_at + SETTLE <= now)
+            .map(|(&id, _)| id)
+            .collect();
+        self.release(settled)
+    }
+
+    /// Every order, settled or not
+    pub fn flush_all(&mut self) -> Vec<NormalizedTrade> {
+        let ids: Vec<u64> = self.open.keys().copied().collect();
+        self.release(ids)
+    }
+
+    fn release(&mut self, ids: Vec<u64>) -> Vec<NormalizedTrade> {
+        let mut orders = std::mem::take(&mut self.closed);
+        for id in ids {
+            if let Some(order) = self.open.remove(&id) {
+                self.by_key.remove(&order.key);
+                orders.push(order.merge());
+            }
+        }
+        orders
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(platform: Platform, id: &str, timestamp: &str, price: f64, size: f64) -> NormalizedTrade {
+        NormalizedTrade {
+            platform,
+            price,
+            size,
+            timestamp: timestamp.into(),
+            wallet_id: Some("0xw".into()),
+            ..NormalizedTrade::test(id)
+        }
+    }
+
+    #[test]
+    fn merges_fills_into_one_vwap_order_once_settled() {
+        let start = Instant::now();
+        let mut agg = FillAggregator::default();
+        let t = "2026-10-01T12:00:00+00:00";
+        agg.push(fill(Platform::Polymarket, "0xtx", t, 0.50, 40_000.0), start);
+        agg.push(fill(Platform::Polymarket, "0xtx", t, 0.60, 10_000.0), start);
+        // Redelivered fill is ignored
+        agg.push(fill(Platform::Polymarket, "0xtx", t, 0.60, 10_000.0), start);
+        agg.push(fill(Platform::Polymarket, "0xother", t, 0.50, 100.0), start);
+
+        assert!(agg.flush(start).is_empty());
+        let orders = agg.flush(start + SETTLE);
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].trade_id, "0xtx");
+        assert_eq!(orders[0].fills, 2);
+        assert_eq!(orders[0].size, 50_000.0);
+        assert!((orders[0].price - 0.52).abs() < 1e-9);
+        assert!((orders[0].value() - 26_000.0).abs() < 1e-6);
+        assert_eq!(agg.next_deadline(), None);
+    }
+
+    #[test]
+    fn kalshi_fills_split_on_the_time_window() {
+        let start = Instant::now();
+        let mut agg = FillAggregator::default();
+        agg.push(fill(Platform::Kalshi, "k1", "2026-10-01T12:00:00Z", 0.40, 100.0), start);
+        agg.push(fill(Platform::Kalshi, "k2", "2026-10-01T12:00:01Z", 0.40, 100.0), start);
+        agg.push(fill(Platform::Kalshi, "k3", "2026-10-01T12:00:05Z", 0.40, 100.0), start);
+
+        // k3 closed the first order right away; it waits to settle
+        let orders = agg.flush(start);
+        assert_eq!(orders.len(), 1);
+        assert_eq!((orders[0].trade_id.as_str(), orders[0].fills), ("k1", 2));
+
+        let orders = agg.flush_all();
+        assert_eq!((orders[0].trade_id.as_str(), orders[0].fills), ("k3", 1));
+    }
+
+    #[test]
+    fn trailing_fills_are_held_back_for_the_next_page() {
+        // Newest first, as history pages arrive
+        let mut page = vec![
+            fill(Platform::Kalshi, "k4", "2026-10-01T12:00:09Z", 0.40, 100.0),
+            fill(Platform::Kalshi, "k3", "2026-10-01T12:00:01Z", 0.40, 100.0),
+            fill(Platform::Kalshi, "k2", "2026-10-01T12:00:00.500Z", 0.40, 100.0),
+        ];
+        let trailing = split_trailing_fills(&mut page);
+        let ids = |fills: &[NormalizedTrade]| fills.iter().map(|f| f.trade_id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&page), ["k4"]);
+        assert_eq!(ids(&trailing), ["k3", "k2"]);
+
+        // Merged with the next page, the order's older fill joins them
+        let mut agg = FillAggregator::default();
+        let next_page = fill(Platform::Kalshi, "k1", "2026-10-01T12:00:00Z", 0.40, 100.0);
+        for f in trailing.into_iter().chain([next_page]).rev() {
+            agg.push(f, Instant::now());
+        }
+        let orders = agg.flush_all();
+        assert_eq!((orders[0].trade_id.as_str(), orders[0].fills), ("k1", 3));
+    }
+}
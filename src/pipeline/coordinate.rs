@@ -0,0 +1,216 @@
+//! Coordinated flow: several distinct wallets alerting on the same market
+//! outcome, on the same side, within minutes of each other.
+//!
+//! Single-order alerts (not accumulation alerts, whose flow is its own
+//! signal) are tracked per (market outcome, side) over a sliding window of
+//! trade time. Once `min_wallets` distinct wallets are in the window, one
+//! `COORDINATED_FLOW` alert goes out and the flow is held open: later alerts
+//! join it (updating the stored cluster, without another alert) until the
+//! window passes with none.
+
+use std::collections::HashMap;
+
+use crate::alerts::{CoordinatedFlow, FlowWallet};
+use crate::platforms::NormalizedTrade;
+
+/// Flows not traded for a whole window are dropped this often (in alerts recorded)
+const PRUNE_EVERY: u32 = 256;
+
+struct FlowAlert {
+    trade_id: String,
+    wallet_id: String,
+    ts: i64,
+    timestamp: String,
+    value: f64,
+}
+
+#[derive(Default)]
+struct Flow {
+    /// Alerts in the flow, in the order recorded
+    alerts: Vec<FlowAlert>,
+    /// Set once the flow has alerted: the key it is stored under
+    raised: Option<String>,
+}
+
+impl Flow {
+    fn newest(&self) -> i64 {
+        self.alerts.iter().map(|a| a.ts).max().unwrap_or(i64::MIN)
+    }
+
+    fn distinct_wallets(&self) -> usize {
+        let mut wallets: Vec<&str> = self.alerts.iter().map(|a| a.wallet_id.as_str()).collect();
+        wallets.sort_unstable();
+        wallets.dedup();
+        wallets.len()
+    }
+}
+
+/// A coordinated flow to store; `is_new` when it should also alert
+pub struct Coordination {
+    /// Stable for the life of the flow, so updates replace the stored cluster
+    pub key: String,
+    pub flow: CoordinatedFlow,
+    pub is_new: bool,
+}
+
+pub struct CoordinationTracker {
+    min_wallets: usize,
+    window_minutes: u64,
+    flows: HashMap<String, Flow>,
+    newest: i64,
+    since_prune: u32,
+}
+
+impl CoordinationTracker {
+    /// `None` when coordination alerts are disabled (fewer than 2 wallets)
+    pub fn new(min_wallets: usize, window_minutes: u64) -> Option<Self> {
+        (min_wallets >= 2).then(|| Self {
+            min_wallets,
+            window_minutes,
+            flows: HashMap::new(),
+            newest: 0,
+            since_prune: 0,
+        })
+    }
+
+    fn window_secs(&self) -> i64 {
+        self.window_minutes as i64 * 60
+    }
+
+    /// Count an alerted trade. Returns the flow once it is coordinated:
+    /// new when this trade made it so, otherwise the updated flow.
+    pub fn record(&mut self, trade: &NormalizedTrade) -> Option<Coordination> {
+        let wallet = trade.wallet_id.as_deref()?;
+        let ts = trade.unix_timestamp()?;
+        self.newest = self.newest.max(ts);
+        self.maybe_prune();
+
+        let window = self.window_secs();
+        let key = format!("{}|{}|{}|{}", trade.platform.name(), trade.market_id, trade.asset_id, trade.side.to_uppercase());
+        let flow = self.flows.entry(key.clone()).or_default();
+
+        if flow.alerts.iter().any(|a| a.trade_id == trade.trade_id) {
+            return None;
+        }
+        // An open flow ends when the window passes without an alert; until it
+        // is coordinated, alerts slide out of the window
+        if flow.raised.is_some() && ts - flow.newest() > window {
+            *flow = Flow::default();
+        }
+        if flow.raised.is_none() {
+            flow.alerts.retain(|a| a.ts >= ts - window);
+        }
+        flow.alerts.push(FlowAlert {
+            trade_id: trade.trade_id.clone(),
+            wallet_id: wallet.to_string(),
+            ts,
+            timestamp: trade.timestamp.clone(),
+            value: trade.value(),
+        });
+
+        if flow.distinct_wallets() < self.min_wallets {
+            return None;
+        }
+        let is_new = flow.raised.is_none();
+        if is_new {
+            let first = flow.alerts.iter().min_by_key(|a| a.ts).map_or("", |a| a.trade_id.as_str());
+            flow.raised = Some(format!("{}|{}", key, first));
+        }
+        let stored_key = flow.raised.clone().unwrap_or_default();
+
+        Some(Coordination {
+            key: stored_key,
+            flow: summarize(trade, &flow.alerts, self.window_minutes),
+            is_new,
+        })
+    }
+
+    fn maybe_prune(&mut self) {
+        self.since_prune += 1;
+        if self.since_prune < PRUNE_EVERY {
+            return;
+        }
+        self.since_prune = 0;
+        let earliest = self.newest - self.window_secs();
+        self.flows.retain(|_, flow| flow.newest() >= earliest);
+    }
+}
+
+fn summarize(trade: &NormalizedTrade, alerts: &[FlowAlert], window_minutes: u64) -> CoordinatedFlow {
+    let mut ordered: Vec<&FlowAlert> = alerts.iter().collect();
+    ordered.sort_by_key(|a| a.ts);
+
+    let mut wallets: Vec<FlowWallet> = Vec::new();
+    for alert in &ordered {
+        match wallets.iter_mut().find(|w| w.wallet_id == alert.wallet_id) {
+            Some(wallet) => {
+                wallet.value += alert.value;
+                wallet.trades += 1;
+            }
+            None => wallets.push(FlowWallet {
+                wallet_id: alert.wallet_id.clone(),
+                value: alert.value,
+                trades: 1,
+                first_trade_at: alert.timestamp.clone(),
+            }),
+        }
+    }
+
+    let (first, last) = (ordered[0], ordered[ordered.len() - 1]);
+    CoordinatedFlow {
+        platform: trade.platform.name().to_string(),
+        market_id: trade.market_id.clone(),
+        market_title: trade.market_title.clone(),
+        outcome: trade.outcome.clone(),
+        action: trade.side.to_uppercase(),
+        combined_value: wallets.iter().map(|w| w.value).sum(),
+        wallets,
+        first_trade_at: first.timestamp.clone(),
+        last_trade_at: last.timestamp.clone(),
+        spread_secs: last.ts - first.ts,
+        window_minutes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(id: &str, wallet: &str, minute: u32) -> NormalizedTrade {
+        NormalizedTrade {
+            asset_id: "yes".into(),
+            outcome: Some("Yes".into()),
+            size: 60_000.0,
+            timestamp: format!("2026-10-01T12:{:02}:00+00:00", minute),
+            wallet_id: Some(wallet.into()),
+            ..NormalizedTrade::test(id)
+        }
+    }
+
+    #[test]
+    fn alerts_once_when_enough_wallets_join_within_the_window() {
+        let mut tracker = CoordinationTracker::new(3, 10).unwrap();
+
+        // Slides out of the window before the third wallet arrives
+        assert!(tracker.record(&trade("a", "0xa", 0)).is_none());
+        assert!(tracker.record(&trade("b", "0xb", 12)).is_none());
+        assert!(tracker.record(&trade("b2", "0xb", 13)).is_none());
+        assert!(tracker.record(&trade("c", "0xc", 15)).is_none());
+
+        let raised = tracker.record(&trade("d", "0xd", 20)).unwrap();
+        assert!(raised.is_new);
+        assert_eq!(raised.flow.wallets.len(), 3);
+        assert_eq!(raised.flow.wallets[0].trades, 2);
+        assert_eq!(raised.flow.combined_value, 120_000.0);
+        assert_eq!(raised.flow.spread_secs, 8 * 60);
+
+        // Later wallets update the same flow without alerting again
+        let grown = tracker.record(&trade("e", "0xe", 29)).unwrap();
+        assert!(!grown.is_new);
+        assert_eq!(grown.key, raised.key);
+        assert_eq!(grown.flow.wallets.len(), 4);
+
+        // A quiet window ends it
+        assert!(tracker.record(&trade("f", "0xf", 45)).is_none());
+    }
+}
@@ -0,0 +1,282 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use rusqlite::Connection;
+use tokio::sync::{mpsc, Mutex, Semaphore};
+use tokio::time::{self, Instant};
+
+use super::impact::initial_impact;
+use super::metrics::PipelineMetrics;
+use super::{EnrichedTrade, IngestedTrade, WatchSettings};
+use crate::alerts::display::format_number;
+use crate::alerts::rules::bought_side_price;
+use crate::alerts::{FilterDecision, MarketContext};
+use crate::config::Config;
+use crate::db;
+use crate::platforms::{MarketSource, NormalizedTrade};
+use crate::threshold::LiquidityRatios;
+use crate::whale_profile::WhaleProfileCache;
+
+/// Enrichment workers running at once
+pub const WORKERS: usize = 4;
+/// Total time all lookups for one alert may take. Lookups still running when
+/// it runs out are dropped and the alert goes out with what it has.
+const ALERT_BUDGET: Duration = Duration::from_secs(10);
+
+/// Start the enrichment stage: skip trades already alerted, then look up
+/// market data for each remaining trade on a bounded pool of workers.
+/// `sources` is indexed by `IngestedTrade::source`.
+pub fn spawn_enrichment(
+    mut rx: mpsc::Receiver<IngestedTrade>,
+    sources: Vec<Arc<dyn MarketSource>>,
+    settings: Arc<WatchSettings>,
+    whale_cache: Arc<Mutex<WhaleProfileCache>>,
+    conn: Connection,
+    out: mpsc::Sender<EnrichedTrade>,
+    metrics: Arc<PipelineMetrics>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let workers = Arc::new(Semaphore::new(WORKERS));
+
+        while let Some(item) = rx.recv().await {
+            // Already alerted (stream/polling overlap, restart, or another watcher on this DB)
+            if db::alert_exists(&conn, item.trade.platform.name(), &item.trade.order_id()) {
+                PipelineMetrics::incr(&metrics.duplicates_skipped);
+                continue;
+            }
+
+            // Waiting for a free worker is what pushes back on ingestion
+            let Ok(permit) = Arc::clone(&workers).acquire_owned().await else {
+                return;
+            };
+
+            let source = Arc::clone(&sources[item.source]);
+            let settings = Arc::clone(&settings);
+            let whale_cache = Arc::clone(&whale_cache);
+            let out = out.clone();
+            let metrics = Arc::clone(&metrics);
+
+            tokio::spawn(async move {
+                metrics.workers_busy.fetch_add(1, Ordering::Relaxed);
+                let enriched = enrich(source.as_ref(), &settings, &whale_cache, item, &metrics).await;
+                metrics.workers_busy.fetch_sub(1, Ordering::Relaxed);
+
+                // Keep the worker slot until the sink accepts the alert,
+                // so a slow sink slows enrichment down too
+                if let Some(enriched) = enriched {
+                    let _ = out.send(enriched).await;
+                }
+                drop(permit);
+            });
+        }
+    })
+}
+
+/// Run a lookup against the alert's deadline, counting it if it runs out of time
+async fn within<T>(
+    deadline: Instant,
+    metrics: &PipelineMetrics,
+    lookup: impl Future<Output = Option<T>>,
+) -> Option<T> {
+    match time::timeout_at(deadline, lookup).await {
+        Ok(result) => result,
+        Err(_) => {
+            PipelineMetrics::incr(&metrics.lookup_timeouts);
+            None
+        }
+    }
+}
+
+/// Filter and enrich one trade. Returns `None` if it shouldn't alert.
+async fn enrich(
+    source: &dyn MarketSource,
+    settings: &WatchSettings,
+    whale_cache: &Mutex<WhaleProfileCache>,
+    item: IngestedTrade,
+    metrics: &PipelineMetrics,
+) -> Option<EnrichedTrade> {
+    let deadline = Instant::now() + ALERT_BUDGET;
+    let mut trade = item.trade;
+
+    // Stage 1: lookups that decide whether the trade alerts at all
+    let (info, market_context) = tokio::join!(
+        within(deadline, metrics, source.fetch_market_info(&trade)),
+        within(deadline, metrics, source.fetch_market_context(&trade)),
+    );
+
+    // Fill in title + native category for venues that don't send them with the trade
+    if let Some(info) = info {
+        trade.market_title = Some(info.title);
+        trade.category = info.category;
+    }
+
+    // Accumulated flow has its own threshold. A trade under its threshold
+    // can still alert on a relative trigger, checked once the filters pass.
+    let threshold = settings.applied_threshold(&trade);
+    let mut relative = None;
+    let threshold_decision = match item.accumulation {
+        Some(_) => FilterDecision::skipped("threshold", "accumulation".into()),
+        None => {
+            let detail = format!(
+                "${} against ${} ({})",
+                format_number(trade.value() as u64),
+                format_number(threshold.value),
+                threshold.source
+            );
+            if trade.value() >= threshold.value as f64 {
+                FilterDecision::passed("threshold", Some(detail))
+            } else {
+                let Some(triggers) = settings.relative_triggers().filter(|r| trade.value() >= r.min_value as f64)
+                else {
+                    settings.explain_rejection(&trade, "threshold", &detail);
+                    PipelineMetrics::incr(&metrics.trades_filtered);
+                    return None;
+                };
+                relative = Some(triggers);
+                FilterDecision::skipped("threshold", format!("{}, checked against relative triggers", detail))
+            }
+        }
+    };
+
+    let mut filter_decisions: Vec<FilterDecision> = match check_filters(settings, &trade, market_context.as_ref()) {
+        Ok(decisions) => std::iter::once(threshold_decision).chain(decisions).collect(),
+        Err((filter, reason)) => {
+            settings.explain_rejection(&trade, filter, &reason);
+            PipelineMetrics::incr(&metrics.trades_filtered);
+            return None;
+        }
+    };
+
+    // Under the threshold: alert only if the trade is large for its market
+    let mut early_order_book = None;
+    let mut relative_triggers = Vec::new();
+    if let Some(triggers) = relative {
+        if triggers.needs_order_book() {
+            early_order_book = Some(within(deadline, metrics, source.fetch_order_book(&trade)).await);
+        }
+        let ratios = LiquidityRatios::measure(&trade, market_context.as_ref(), early_order_book.clone().flatten().as_ref());
+        relative_triggers = triggers.fired(&ratios);
+        let measured = if ratios.is_empty() { "no market size data".to_string() } else { ratios.describe() };
+        let detail = format!("{}; triggers {}", measured, triggers.describe());
+        if relative_triggers.is_empty() {
+            settings.explain_rejection(&trade, "relative", &detail);
+            PipelineMetrics::incr(&metrics.trades_filtered);
+            return None;
+        }
+        filter_decisions.insert(1, FilterDecision::passed("relative", Some(detail)));
+    }
+
+    // Stage 2: the rest of the alert, all at once
+    let (whale_profile, order_book, top_holders) = tokio::join!(
+        within(deadline, metrics, async {
+            match trade.wallet_id {
+                Some(ref wallet_id) => source.fetch_whale_profile(wallet_id, whale_cache).await,
+                None => None,
+            }
+        }),
+        within(deadline, metrics, async {
+            match early_order_book {
+                Some(book) => book,
+                None => source.fetch_order_book(&trade).await,
+            }
+        }),
+        within(deadline, metrics, source.fetch_top_holders(&trade)),
+    );
+    let liquidity = LiquidityRatios::measure(&trade, market_context.as_ref(), order_book.as_ref());
+    // A wallet's flow over the window has no single moment to measure from
+    let price_impact = match item.accumulation {
+        Some(_) => None,
+        None => Some(initial_impact(&trade, item.price_before, order_book.as_ref())),
+    };
+
+    Some(EnrichedTrade {
+        source: item.source,
+        trade,
+        market_context,
+        whale_profile,
+        order_book,
+        top_holders,
+        accumulation: item.accumulation,
+        filter_decisions,
+        threshold: threshold.value,
+        liquidity,
+        relative_triggers,
+        price_impact,
+        received_at: item.received_at,
+    })
+}
+
+/// Category, odds and spread filters. Returns how each applicable filter
+/// treated the trade, or `Err` naming the filter that rejected it and why.
+fn check_filters(
+    settings: &WatchSettings,
+    trade: &NormalizedTrade,
+    market_context: Option<&MarketContext>,
+) -> Result<Vec<FilterDecision>, (&'static str, String)> {
+    if !passes_category_filter(settings, trade) {
+        return Err(("categories", format!("not in [{}]", settings.selected_categories.join(", "))));
+    }
+
+    let mut decisions = Vec::new();
+    if let Some(cfg) = settings.config.as_ref() {
+        decisions.push(check_odds_filter(cfg, trade, market_context).map_err(|reason| ("max_odds", reason))?);
+
+        // Skip if spread too low (dead market)
+        if let (true, Some(ctx)) = (cfg.min_spread > 0.0, market_context) {
+            let detail = format!("spread {:.3}, min {:.3}", ctx.spread, cfg.min_spread);
+            if ctx.spread < cfg.min_spread {
+                return Err(("min_spread", detail));
+            }
+            decisions.push(FilterDecision::passed("min_spread", Some(detail)));
+        }
+    }
+
+    Ok(decisions)
+}
+
+/// Category filter: use the native category when available,
+/// fall back to keyword matching on the title
+pub fn passes_category_filter(settings: &WatchSettings, trade: &NormalizedTrade) -> bool {
+    let Some(ref title) = trade.market_title else {
+        return true;
+    };
+
+    let has_native_match = trade
+        .category
+        .as_ref()
+        .map(|cat| settings.category_registry.matches_native_category(cat, &settings.selected_categories))
+        .unwrap_or(false);
+
+    has_native_match
+        || settings
+            .category_registry
+            .matches_selection(title, &settings.selected_categories)
+            .is_some()
+}
+
+/// Odds filter: skip buying a near-certainty, where the side bought trades
+/// above `max_odds`. Exits are never skipped. Odds come from `market_context`
+/// when it has the side's price, otherwise from the trade's own price.
+pub fn check_odds_filter(
+    cfg: &Config,
+    trade: &NormalizedTrade,
+    market_context: Option<&MarketContext>,
+) -> Result<FilterDecision, String> {
+    let Some(odds) = bought_side_price(&trade.side, trade.outcome.as_deref(), trade.price, market_context) else {
+        return Ok(FilterDecision::skipped("max_odds", "exit".into()));
+    };
+
+    let side = if trade.side.eq_ignore_ascii_case("BUY") {
+        trade.outcome.as_deref().unwrap_or("BUY")
+    } else {
+        &trade.side
+    };
+    let detail = format!("{} at {:.0}%, max {:.0}%", side, odds * 100.0, cfg.max_odds * 100.0);
+    if odds <= cfg.max_odds {
+        Ok(FilterDecision::passed("max_odds", Some(detail)))
+    } else {
+        Err(detail)
+    }
+}
@@ -0,0 +1,255 @@
+//! Price impact: whether a whale trade moved the market, and whether the
+//! move held.
+//!
+//! A trade is only seen once it has happened, so the price before it is the
+//! last trade on the same asset that ingest saw. The alert goes out with the
+//! slippage against that price and the order book mid at enrichment. Moves
+//! are measured from the last trade, or from that mid when there was none
+//! recently, so every alert with a book is followed. The
+//! tracker then snapshots the book every `SNAPSHOT_INTERVAL` until the window
+//! ends or the mid gives back half its move, updates the stored alert and
+//! queues a `PRICE_IMPACT` webhook.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use colored::*;
+use rusqlite::Connection;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio::time::{self, Instant};
+
+use crate::alerts::{ImpactStatus, OrderBookSummary, PriceImpact};
+use crate::db;
+use crate::platforms::{MarketSource, NormalizedTrade};
+
+/// Time between follow-up order book snapshots
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(20);
+/// A last trade older than this (trade time, seconds) isn't a price "before"
+const REFERENCE_MAX_AGE_SECS: i64 = 15 * 60;
+/// Assets remembered before stale last prices are dropped
+const MAX_ASSETS: usize = 10_000;
+/// Moves under this (one cent) don't count as moving the market
+const MIN_MOVE: f64 = 0.01;
+/// Share of its move the mid must give back to count as reverted
+const RECOVERY_SHARE: f64 = 0.5;
+
+/// Last trade price per asset, as seen by one ingest task
+#[derive(Default)]
+pub struct LastPrices {
+    prices: HashMap<String, (f64, i64)>,
+}
+
+impl LastPrices {
+    /// Record an order and return the asset's price before it, if recent
+    pub fn record(&mut self, order: &NormalizedTrade) -> Option<f64> {
+        let ts = order.unix_timestamp()?;
+        if self.prices.len() >= MAX_ASSETS {
+            self.prices.retain(|_, &mut (_, seen)| ts - seen <= REFERENCE_MAX_AGE_SECS);
+        }
+        let before = self.prices.insert(order.asset_id.clone(), (order.price, ts))?;
+        (ts - before.1 <= REFERENCE_MAX_AGE_SECS).then_some(before.0)
+    }
+}
+
+/// +1 when the trade pushes the asset's price up, -1 when it pushes it down.
+/// Kalshi prices are the YES price, so NO orders push it down.
+pub fn direction(trade: &NormalizedTrade) -> f64 {
+    if trade.side.eq_ignore_ascii_case("SELL") || trade.side.eq_ignore_ascii_case("NO") {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+/// Mid of the book, if both sides are quoted
+pub fn mid_price(book: &OrderBookSummary) -> Option<f64> {
+    (book.best_bid > 0.0 && book.best_ask > 0.0 && book.best_ask < 1.0).then(|| (book.best_bid + book.best_ask) / 2.0)
+}
+
+/// What is known when the alert goes out
+pub fn initial_impact(trade: &NormalizedTrade, price_before: Option<f64>, book: Option<&OrderBookSummary>) -> PriceImpact {
+    let dir = direction(trade);
+    let mid_at_alert = book.and_then(mid_price);
+    let baseline = price_before.or(mid_at_alert);
+    let mid_move = mid_at_alert.zip(baseline).map(|(mid, base)| dir * (mid - base));
+    let status = if mid_move.is_some() { ImpactStatus::Measuring } else { ImpactStatus::Unavailable };
+
+    PriceImpact {
+        price_before,
+        slippage: price_before.map(|before| dir * (trade.price - before)),
+        baseline,
+        mid_at_alert,
+        mid_move,
+        mid_after: None,
+        final_move: None,
+        recovery_secs: None,
+        snapshots: 0,
+        status,
+    }
+}
+
+/// An alert whose price impact is still being measured
+pub struct ImpactJob {
+    /// Index of the source to snapshot the book from
+    pub source: usize,
+    pub trade: NormalizedTrade,
+    pub impact: PriceImpact,
+}
+
+/// Start the price impact tracker: measures each job over `window`, then
+/// stores the result and queues its webhook. Runs until `rx` closes and every
+/// measurement has finished.
+pub fn spawn_impact_tracker(
+    mut rx: mpsc::Receiver<ImpactJob>,
+    sources: Vec<Arc<dyn MarketSource>>,
+    window: Duration,
+    conn: Connection,
+    webhook_tx: Option<mpsc::Sender<serde_json::Value>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut running: JoinSet<(NormalizedTrade, PriceImpact)> = JoinSet::new();
+        let mut open = true;
+
+        loop {
+            tokio::select! {
+                biased;
+
+                finished = running.join_next(), if !running.is_empty() => {
+                    let Some(Ok((trade, impact))) = finished else {
+                        continue;
+                    };
+                    if let Ok(impact_json) = serde_json::to_string(&impact) {
+                        db::update_price_impact(&conn, trade.platform.name(), &trade.order_id(), &impact_json);
+                    }
+                    println!(
+                        "{} {} {} - {}",
+                        "[PRICE IMPACT]".bright_blue().bold(),
+                        trade.platform.name(),
+                        trade.market_title.as_deref().unwrap_or(&trade.market_id),
+                        impact.summary()
+                    );
+                    if let Some(ref tx) = webhook_tx {
+                        let _ = tx.send(impact_payload(&trade, &impact)).await;
+                    }
+                }
+                job = rx.recv(), if open => match job {
+                    Some(job) => {
+                        let source = Arc::clone(&sources[job.source]);
+                        running.spawn(measure(source, job, window));
+                    }
+                    None => open = false,
+                },
+            }
+
+            if !open && running.is_empty() {
+                return;
+            }
+        }
+    })
+}
+
+/// Snapshot the book until the window ends or the move reverts
+async fn measure(source: Arc<dyn MarketSource>, job: ImpactJob, window: Duration) -> (NormalizedTrade, PriceImpact) {
+    let ImpactJob { trade, mut impact, .. } = job;
+    let (Some(baseline), Some(mut peak)) = (impact.baseline, impact.mid_move) else {
+        return (trade, impact);
+    };
+    let dir = direction(&trade);
+    let started = Instant::now();
+
+    while started.elapsed() + SNAPSHOT_INTERVAL <= window {
+        time::sleep(SNAPSHOT_INTERVAL).await;
+        let Some(mid) = source.fetch_order_book(&trade).await.as_ref().and_then(mid_price) else {
+            continue;
+        };
+        let moved = dir * (mid - baseline);
+        impact.snapshots += 1;
+        impact.mid_after = Some(mid);
+        impact.final_move = Some(moved);
+        peak = peak.max(moved);
+        if peak >= MIN_MOVE && moved <= peak * (1.0 - RECOVERY_SHARE) {
+            impact.recovery_secs = Some(started.elapsed().as_secs());
+            break;
+        }
+    }
+
+    impact.status = if impact.recovery_secs.is_some() {
+        ImpactStatus::Reverted
+    } else if peak < MIN_MOVE {
+        ImpactStatus::NoMove
+    } else {
+        ImpactStatus::Held
+    };
+    (trade, impact)
+}
+
+/// Follow-up webhook for a finished measurement, keyed by the alert's order ID
+fn impact_payload(trade: &NormalizedTrade, impact: &PriceImpact) -> serde_json::Value {
+    json!({
+        "platform": trade.platform.name(),
+        "alert_type": "PRICE_IMPACT",
+        "trade_id": trade.trade_id,
+        "order_id": trade.order_id(),
+        "action": trade.side.to_uppercase(),
+        "value": trade.value(),
+        "market_title": trade.market_title,
+        "outcome": trade.outcome,
+        "timestamp": trade.timestamp,
+        "price_impact": impact,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platforms::Platform;
+
+    fn trade(side: &str, price: f64, timestamp: &str) -> NormalizedTrade {
+        NormalizedTrade {
+            platform: Platform::Kalshi,
+            market_id: "KX".into(),
+            asset_id: "KX".into(),
+            side: side.into(),
+            price,
+            size: 100_000.0,
+            timestamp: timestamp.into(),
+            ..NormalizedTrade::test("t")
+        }
+    }
+
+    #[test]
+    fn impact_is_signed_in_the_trades_direction() {
+        let mut last = LastPrices::default();
+        assert_eq!(last.record(&trade("yes", 0.40, "2026-10-01T12:00:00Z")), None);
+        let order = trade("no", 0.35, "2026-10-01T12:05:00Z");
+        let before = last.record(&order);
+        assert_eq!(before, Some(0.40));
+        // Too long after the last trade to say what the price was before
+        assert_eq!(last.record(&trade("yes", 0.5, "2026-10-01T13:00:00Z")), None);
+
+        // A NO order pushing the YES price from 40¢ to 33¢ moved it 7¢ its way
+        let book = OrderBookSummary {
+            best_bid: 0.32,
+            best_ask: 0.34,
+            bid_depth_10pct: 0.0,
+            ask_depth_10pct: 0.0,
+            bid_levels: 1,
+            ask_levels: 1,
+        };
+        let impact = initial_impact(&order, before, Some(&book));
+        assert!((impact.slippage.unwrap() - 0.05).abs() < 1e-9);
+        assert!((impact.mid_move.unwrap() - 0.07).abs() < 1e-9);
+        assert_eq!(impact.status, ImpactStatus::Measuring);
+
+        // With no recent trade, moves are followed from the mid at enrichment
+        let from_book = initial_impact(&order, None, Some(&book));
+        assert_eq!(from_book.baseline, Some(0.33));
+        assert_eq!(from_book.mid_move, Some(0.0));
+        assert_eq!(from_book.slippage, None);
+        assert_eq!(from_book.status, ImpactStatus::Measuring);
+        assert_eq!(initial_impact(&order, None, None).status, ImpactStatus::Unavailable);
+    }
+}
@@ -0,0 +1,317 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use colored::*;
+use rusqlite::Connection;
+use tokio::sync::mpsc;
+use tokio::time::{self, Instant};
+
+use super::accumulate::AccumulationTracker;
+use super::aggregate::{self, FillAggregator};
+use super::impact::LastPrices;
+use super::metrics::PipelineMetrics;
+use super::IngestedTrade;
+use crate::alerts::{anomaly, Accumulation};
+use crate::capture;
+use crate::categories::CategoryRegistry;
+use crate::db;
+use crate::platforms::{MarketSource, NormalizedTrade, Platform, TradeCursor, TradeGap};
+use crate::threshold::ThresholdPolicy;
+
+/// Polling settings for one ingest task
+#[derive(Clone)]
+pub struct IngestOptions {
+    pub threshold: u64,
+    /// Overrides of `threshold` and relative triggers; orders that could meet
+    /// one are let through
+    pub threshold_policy: ThresholdPolicy,
+    pub interval: Duration,
+    pub resume_lookback_minutes: u64,
+    /// Wallet flow that raises an accumulation alert (0 = off), and its window
+    pub accumulation_threshold: u64,
+    pub accumulation_window_minutes: u64,
+    /// Stop ingesting at this instant (the end of a replay); `None` runs forever
+    pub stop_at: Option<Instant>,
+}
+
+/// Start ingesting from one source: its real-time stream when it has one,
+/// HTTP polling when it doesn't or the stream goes silent, plus one catch-up
+/// poll from the cursor saved by the previous run.
+///
+/// Fills are merged into orders, and whale-sized orders are sent to `out`,
+/// along with accumulation alerts built from the smaller ones.
+/// `conn` is used for cursors, gap events and market baselines.
+pub fn spawn_ingest(
+    index: usize,
+    source: Arc<dyn MarketSource>,
+    conn: Connection,
+    options: IngestOptions,
+    out: mpsc::Sender<IngestedTrade>,
+    metrics: Arc<PipelineMetrics>,
+) -> tokio::task::JoinHandle<()> {
+    let platform = source.platform();
+
+    let mut stream = source.stream_trades();
+    if stream.is_some() {
+        println!("{} WS: {}", platform.name(), "Connecting...".bright_cyan());
+    } else {
+        println!("{} WS: {}", platform.name(), "Unavailable (API key required), using HTTP polling".yellow());
+    }
+
+    let mut resume_from = load_resume_cursor(&conn, platform, options.resume_lookback_minutes);
+
+    tokio::spawn(async move {
+        // Fall back to HTTP polling if the stream produces no trades in ~1 min
+        let stream_fallback_threshold = options.interval * 12;
+        let mut last_stream_trade = Instant::now();
+        let mut cursor: Option<TradeCursor> = None;
+        let mut tick = time::interval(options.interval);
+        let mut fills = FillAggregator::default();
+        let mut last_prices = LastPrices::default();
+        let categories = CategoryRegistry::new();
+        let mut accumulation =
+            AccumulationTracker::new(options.accumulation_threshold, options.accumulation_window_minutes);
+        let min_value = aggregate::min_fill_value(options.threshold_policy.lowest(options.threshold));
+        let mut stopping = false;
+
+        loop {
+            // Fixed branch order keeps replays deterministic
+            tokio::select! {
+                biased;
+
+                _ = stop_at(options.stop_at) => stopping = true,
+                streamed = next_stream_trade(&mut stream) => {
+                    let Some(trade) = streamed else {
+                        eprintln!("{} {} stream closed, using HTTP polling", "[WARN]".yellow(), platform.name());
+                        stream = None;
+                        continue;
+                    };
+                    last_stream_trade = Instant::now();
+                    fills.push(trade, last_stream_trade);
+                }
+                _ = settle(fills.next_deadline()) => {}
+                _ = tick.tick() => {
+                    for gap in source.take_stream_gaps() {
+                        report_gap(&conn, platform.name(), &gap);
+                    }
+                    let resume = resume_from.take();
+                    if resume.is_none()
+                        && stream.is_some()
+                        && last_stream_trade.elapsed() < stream_fallback_threshold
+                    {
+                        continue;
+                    }
+
+                    match source.fetch_recent_trades(min_value, resume.as_ref().or(cursor.as_ref())).await {
+                        Ok(batch) => {
+                            if let Some(ref gap) = batch.gap {
+                                report_gap(&conn, platform.name(), gap);
+                            }
+
+                            let c = cursor.get_or_insert_with(|| resume.unwrap_or_default());
+                            for trade in &batch.trades {
+                                c.advance(trade);
+                            }
+                            save_cursor(&conn, platform, c);
+
+                            // Oldest first, so alerts come out in trade order after a backlog
+                            let now = Instant::now();
+                            for trade in batch.trades.into_iter().rev() {
+                                fills.push(trade, now);
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}", "[ERROR]".red(), e);
+                        }
+                    }
+                }
+            }
+
+            // Threshold checks apply to whole orders, once their fills are merged.
+            // When stopping, orders still settling go out too, so the end of a replay isn't lost.
+            let orders = if stopping { fills.flush_all() } else { fills.flush(Instant::now()) };
+            for order in orders {
+                let price_before = last_prices.record(&order);
+                // Every order over the polling floor counts toward its market's baseline
+                if let Some(ts) = order.unix_timestamp().filter(|_| order.value() >= min_value as f64) {
+                    let category = anomaly::baseline_category(&categories, &order);
+                    db::record_baseline_trade(&conn, platform.name(), &order.market_id, category.as_deref(), ts, order.value());
+                }
+                let accumulated = match accumulation.as_mut() {
+                    Some(tracker) if accumulates(&options, platform, min_value, &order) => tracker.record(&order),
+                    _ => None,
+                };
+                // Category overrides are checked once enrichment knows the market
+                let floor = options.threshold_policy.floor(options.threshold, platform, &order.market_id);
+                if order.value() < floor as f64 {
+                    // Enrichment checks whether it's large for its market
+                    let relative = options
+                        .threshold_policy
+                        .relative_floor()
+                        .is_some_and(|f| order.value() >= f as f64);
+                    if relative && !forward(&out, &metrics, index, order, None, price_before).await {
+                        return;
+                    }
+                } else {
+                    // Let the HTTP fallback (and the next run) resume from the newest whale trade we've seen
+                    let c = cursor.get_or_insert_with(TradeCursor::default);
+                    c.advance(&order);
+                    save_cursor(&conn, platform, c);
+
+                    if !forward(&out, &metrics, index, order, None, price_before).await {
+                        return;
+                    }
+                }
+                if let Some((alert, summary)) = accumulated {
+                    if !forward(&out, &metrics, index, alert, Some(summary), None).await {
+                        return;
+                    }
+                }
+            }
+            if stopping {
+                return;
+            }
+        }
+    })
+}
+
+/// Whether an order counts toward its wallet's accumulated flow: it's over the
+/// floor polling fetches at, so the stream and the fallback see the same flow,
+/// and under its market's threshold before category overrides. A category
+/// override can lower the floor for every market, so orders forwarded on it
+/// may still be rejected by enrichment.
+fn accumulates(options: &IngestOptions, platform: Platform, min_value: u64, order: &NormalizedTrade) -> bool {
+    let threshold = options.threshold_policy.uncategorized(options.threshold, platform, &order.market_id);
+    order.value() >= min_value as f64 && order.value() < threshold as f64
+}
+
+/// Wait until `deadline`, or forever if there is none
+async fn stop_at(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Wait until the next open order settles, or forever if there is none
+async fn settle(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Next trade from the stream, or never if there is none
+async fn next_stream_trade(stream: &mut Option<mpsc::UnboundedReceiver<NormalizedTrade>>) -> Option<NormalizedTrade> {
+    match stream {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Hand a trade to the enrichment stage, waiting if its queue is full.
+/// Returns false once the pipeline has shut down.
+async fn forward(
+    out: &mpsc::Sender<IngestedTrade>,
+    metrics: &PipelineMetrics,
+    source: usize,
+    trade: NormalizedTrade,
+    accumulation: Option<Accumulation>,
+    price_before: Option<f64>,
+) -> bool {
+    PipelineMetrics::incr(&metrics.trades_ingested);
+    out.send(IngestedTrade {
+        source,
+        trade,
+        accumulation,
+        price_before,
+        received_at: Instant::now(),
+    })
+    .await
+    .is_ok()
+}
+
+fn cursor_key(platform: Platform) -> String {
+    format!("cursor_{}", platform.name().to_lowercase())
+}
+
+/// Persist a source's cursor to the `metadata` table
+fn save_cursor(conn: &Connection, platform: Platform, cursor: &TradeCursor) {
+    if let Ok(json) = serde_json::to_string(cursor) {
+        db::set_metadata(conn, &cursor_key(platform), &json);
+    }
+}
+
+/// Load the cursor saved by the previous run, clamped to the lookback window
+fn load_resume_cursor(conn: &Connection, platform: Platform, lookback_minutes: u64) -> Option<TradeCursor> {
+    if lookback_minutes == 0 {
+        return None;
+    }
+
+    let saved: TradeCursor = serde_json::from_str(&db::get_metadata(conn, &cursor_key(platform))?).ok()?;
+    let now = capture::now().timestamp();
+    let earliest = now - (lookback_minutes as i64) * 60;
+
+    if saved.timestamp < earliest {
+        println!(
+            "Resume:    {} last seen before the {} min lookback, catching up from there",
+            platform.name(),
+            lookback_minutes
+        );
+        return Some(TradeCursor {
+            timestamp: earliest,
+            trade_ids: Vec::new(),
+        });
+    }
+
+    println!(
+        "Resume:    {} from {} min ago",
+        platform.name(),
+        ((now - saved.timestamp) / 60).to_string().bright_white()
+    );
+    Some(saved)
+}
+
+/// Warn about and persist a polling gap
+fn report_gap(conn: &Connection, platform: &str, gap: &TradeGap) {
+    let since = chrono::DateTime::from_timestamp(gap.cursor_timestamp, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| gap.cursor_timestamp.to_string());
+
+    eprintln!(
+        "{} {} gap detected: couldn't page back to last trade ({}) after {} pages, some trades may be missed",
+        "[WARN]".yellow(),
+        platform,
+        since,
+        gap.pages
+    );
+
+    db::insert_gap_event(conn, platform, gap.cursor_timestamp, gap.oldest_fetched, gap.pages);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_a_category_override_lets_through_still_accumulate() {
+        let options = IngestOptions {
+            threshold: 25_000,
+            threshold_policy: serde_json::from_str(r#"{"categories": {"weather": 5000}}"#).unwrap(),
+            interval: Duration::from_secs(5),
+            resume_lookback_minutes: 0,
+            accumulation_threshold: 50_000,
+            accumulation_window_minutes: 30,
+            stop_at: None,
+        };
+        let order = |value: f64| NormalizedTrade { size: value / 0.5, ..NormalizedTrade::test("t") };
+        let floor = options.threshold_policy.floor(options.threshold, Platform::Polymarket, "m");
+        assert_eq!(floor, 5_000);
+
+        // Forwarded on the weather floor, but rejected by enrichment on any other market
+        assert!(accumulates(&options, Platform::Polymarket, 1_000, &order(20_000.0)));
+        assert!(accumulates(&options, Platform::Polymarket, 1_000, &order(3_000.0)));
+        assert!(!accumulates(&options, Platform::Polymarket, 1_000, &order(30_000.0)));
+        assert!(!accumulates(&options, Platform::Polymarket, 1_000, &order(500.0)));
+    }
+}
@@ -0,0 +1,40 @@
+//! Wallet relinking, off the alert sink.
+//!
+//! Relinking compares a wallet's trade history with every wallet that traded
+//! the same outcomes, which is too slow to hold up an alert's display and
+//! webhook. The sink shows the wallet's cluster as last stored and queues the
+//! wallet here; its relink lands before its next alert in most cases.
+
+use std::collections::BTreeMap;
+
+use rusqlite::Connection;
+use tokio::sync::mpsc;
+
+use crate::clusters;
+
+/// A wallet to relink after one of its alerts was stored
+pub struct LinkJob {
+    pub wallet_id: String,
+    /// Where its first USDC came from, if looked up
+    pub funded_by: Option<String>,
+}
+
+/// Start the cluster linker: relinks each queued wallet once for however many
+/// of its alerts queued up meanwhile. Runs until `rx` closes and every queued
+/// wallet has been relinked.
+pub fn spawn_cluster_linker(mut rx: mpsc::Receiver<LinkJob>, conn: Connection) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        while let Some(job) = rx.recv().await {
+            let mut pending = BTreeMap::from([(job.wallet_id, job.funded_by)]);
+            while let Ok(job) = rx.try_recv() {
+                let funded_by = pending.entry(job.wallet_id).or_default();
+                if job.funded_by.is_some() {
+                    *funded_by = job.funded_by;
+                }
+            }
+            for (wallet_id, funded_by) in &pending {
+                clusters::observe_alert(&conn, wallet_id, funded_by.as_deref());
+            }
+        }
+    })
+}
@@ -0,0 +1,134 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use colored::*;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// How often the full pipeline summary is printed
+const REPORT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// How often queues are checked for saturation
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Queue fill ratio that triggers a backpressure warning
+const SATURATION_WARN: f64 = 0.75;
+/// Minimum gap between two backpressure warnings
+const WARN_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Counters shared by all pipeline stages
+#[derive(Default)]
+pub struct PipelineMetrics {
+    pub trades_ingested: AtomicU64,
+    pub duplicates_skipped: AtomicU64,
+    pub trades_filtered: AtomicU64,
+    pub lookup_timeouts: AtomicU64,
+    pub alerts_emitted: AtomicU64,
+    pub webhooks_sent: AtomicU64,
+    pub webhooks_failed: AtomicU64,
+    /// Enrichment workers currently busy
+    pub workers_busy: AtomicUsize,
+    /// Slowest ingest-to-alert time since the last report, in milliseconds
+    pub max_latency_ms: AtomicU64,
+}
+
+impl PipelineMetrics {
+    pub fn incr(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_latency(&self, started: Instant) {
+        let ms = started.elapsed().as_millis() as u64;
+        self.max_latency_ms.fetch_max(ms, Ordering::Relaxed);
+    }
+}
+
+/// Reports how many slots of a bounded queue are in use
+pub struct QueueProbe {
+    name: &'static str,
+    depth: Box<dyn Fn() -> (usize, usize) + Send + Sync>,
+}
+
+impl QueueProbe {
+    pub fn new<T: Send + 'static>(name: &'static str, tx: &mpsc::Sender<T>) -> Self {
+        // A weak handle, so the probe doesn't keep the queue open after its producers finish
+        let max = tx.max_capacity();
+        let tx = tx.downgrade();
+        Self {
+            name,
+            depth: Box::new(move || (tx.upgrade().map_or(0, |tx| max - tx.capacity()), max)),
+        }
+    }
+}
+
+/// Periodically print queue depths and throughput, and warn as soon as a
+/// queue fills up (a downstream stage is falling behind)
+pub fn spawn_reporter(
+    metrics: std::sync::Arc<PipelineMetrics>,
+    queues: Vec<QueueProbe>,
+    workers: usize,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut check = tokio::time::interval(CHECK_INTERVAL);
+        let mut last_report = Instant::now();
+        let mut last_warning: Option<Instant> = None;
+
+        loop {
+            check.tick().await;
+
+            let saturated: Vec<String> = queues
+                .iter()
+                .filter_map(|q| {
+                    let (used, cap) = (q.depth)();
+                    (used as f64 >= cap as f64 * SATURATION_WARN).then(|| format!("{} {}/{}", q.name, used, cap))
+                })
+                .collect();
+
+            if !saturated.is_empty() && last_warning.is_none_or(|t| t.elapsed() >= WARN_COOLDOWN) {
+                eprintln!(
+                    "{} queue nearly full: {} (workers {}/{} busy)",
+                    "[BACKPRESSURE]".yellow(),
+                    saturated.join(", "),
+                    metrics.workers_busy.load(Ordering::Relaxed),
+                    workers
+                );
+                last_warning = Some(Instant::now());
+            }
+
+            if last_report.elapsed() >= REPORT_INTERVAL {
+                last_report = Instant::now();
+                print_report(&metrics, &queues, workers);
+            }
+        }
+    })
+}
+
+fn print_report(metrics: &PipelineMetrics, queues: &[QueueProbe], workers: usize) {
+    let queue_summary: Vec<String> = queues
+        .iter()
+        .map(|q| {
+            let (used, cap) = (q.depth)();
+            format!("{} {}/{}", q.name, used, cap)
+        })
+        .collect();
+
+    let slowest_ms = metrics.max_latency_ms.swap(0, Ordering::Relaxed);
+    let load = |c: &AtomicU64| c.load(Ordering::Relaxed);
+
+    println!(
+        "{}",
+        format!(
+            "[PIPELINE] queues: {} | workers {}/{} busy | {} ingested, {} alerts, {} filtered, {} duplicates, {} lookup timeouts, webhooks {} ok/{} failed | slowest alert {:.1}s",
+            queue_summary.join(", "),
+            metrics.workers_busy.load(Ordering::Relaxed),
+            workers,
+            load(&metrics.trades_ingested),
+            load(&metrics.alerts_emitted),
+            load(&metrics.trades_filtered),
+            load(&metrics.duplicates_skipped),
+            load(&metrics.lookup_timeouts),
+            load(&metrics.webhooks_sent),
+            load(&metrics.webhooks_failed),
+            slowest_ms as f64 / 1000.0,
+        )
+        .dimmed()
+    );
+}
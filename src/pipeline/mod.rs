@@ -0,0 +1,257 @@
+//! Staged watch pipeline.
+//!
+//! ```text
+//! ingest + fill aggregation (one task per source) ─► enrichment (bounded worker pool) ─► alert sink ─► webhook sink
+//!                                                                                          ├─► price impact tracker ─┘
+//!                                                                                          └─► cluster linker
+//!
+//! The outcome tracker runs alongside, pricing stored alerts' markets until
+//! they resolve.
+//! ```
+//!
+//! Stages are connected by bounded channels. When a stage falls behind, the
+//! one feeding it waits instead of buffering without limit, and
+//! `PipelineMetrics` reports how full each queue is.
+
+pub mod accumulate;
+pub mod aggregate;
+pub mod coordinate;
+pub mod enrich;
+pub mod impact;
+pub mod ingest;
+pub mod link;
+pub mod metrics;
+pub mod outcomes;
+pub mod sink;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rusqlite::Connection;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
+
+use crate::alerts::scoring::{self, AlertScore};
+use crate::alerts::{display, rules};
+use crate::alerts::{
+    Accumulation, AlertData, FilterDecision, MarketContext, OrderBookSummary, PriceImpact, TopHoldersSummary,
+};
+use crate::categories::CategoryRegistry;
+use crate::config::{Config, FreshWalletPolicy, ScoringWeights};
+use crate::platforms::{MarketSource, NormalizedTrade};
+use crate::threshold::{AppliedThreshold, LiquidityRatios, RelativeTriggers, SizeBand, ThresholdPolicy};
+use crate::whale_profile::{WhaleProfile, WhaleProfileCache};
+use enrich::spawn_enrichment;
+use impact::spawn_impact_tracker;
+use ingest::{spawn_ingest, IngestOptions};
+use link::spawn_cluster_linker;
+use metrics::{spawn_reporter, PipelineMetrics, QueueProbe};
+use outcomes::spawn_outcome_tracker;
+use sink::{spawn_alert_sink, spawn_webhook_sink};
+
+/// Trades waiting for an enrichment worker
+pub const TRADE_QUEUE: usize = 1024;
+/// Enriched alerts waiting to be displayed and stored
+pub const ALERT_QUEUE: usize = 256;
+/// Alerts waiting for their price impact to be measured
+pub const IMPACT_QUEUE: usize = 256;
+/// Wallets waiting to be relinked
+pub const LINK_QUEUE: usize = 256;
+/// Webhook payloads waiting to be posted
+pub const WEBHOOK_QUEUE: usize = 256;
+
+/// Watch settings shared by every stage
+pub struct WatchSettings {
+    pub config: Option<Config>,
+    /// Alert threshold where the threshold policy has no override
+    pub threshold: u64,
+    pub category_registry: CategoryRegistry,
+    pub selected_categories: Vec<String>,
+    /// Print why each filtered trade was rejected (`--explain`)
+    pub explain: bool,
+}
+
+impl WatchSettings {
+    /// Score an alert with the configured weights
+    pub fn score(&self, alert: &AlertData) -> AlertScore {
+        match self.config {
+            Some(ref cfg) => scoring::score_alert(alert, &cfg.scoring),
+            None => scoring::score_alert(alert, &ScoringWeights::default()),
+        }
+    }
+
+    /// The first configured filter rule the alert fails, and why
+    pub fn rejection(&self, alert: &AlertData) -> Option<(&str, String)> {
+        rules::first_rejection(&self.config.as_ref()?.filters, alert)
+    }
+
+    pub fn threshold_policy(&self) -> Option<&ThresholdPolicy> {
+        self.config.as_ref().map(|c| &c.threshold_policy)
+    }
+
+    /// The threshold a trade is held to, once its title and category are known
+    pub fn applied_threshold(&self, trade: &NormalizedTrade) -> AppliedThreshold {
+        match self.threshold_policy() {
+            Some(policy) => policy.resolve(self.threshold, trade, &self.category_registry),
+            None => AppliedThreshold { value: self.threshold, source: "default".into() },
+        }
+    }
+
+    /// Relative triggers, when any are set
+    pub fn relative_triggers(&self) -> Option<&RelativeTriggers> {
+        self.threshold_policy().map(|p| &p.relative).filter(|r| r.is_enabled())
+    }
+
+    /// The size band of an alert of `value` held to `threshold`
+    pub fn band(&self, value: f64, threshold: u64) -> Option<&SizeBand> {
+        self.threshold_policy()?.band(value, threshold)
+    }
+
+    /// How long to follow each alert's price impact; `None` when disabled
+    pub fn price_impact_window(&self) -> Option<Duration> {
+        let secs = self.config.as_ref().map_or(120, |c| c.price_impact_window_secs);
+        (secs > 0).then(|| Duration::from_secs(secs))
+    }
+
+    /// Distinct wallets and window (minutes) that make a coordinated flow;
+    /// `None` when disabled
+    pub fn coordinated_flow(&self) -> Option<(usize, u64)> {
+        let (wallets, minutes) = self
+            .config
+            .as_ref()
+            .map_or((3, 10), |c| (c.coordinated_flow_wallets, c.coordinated_flow_window_minutes));
+        (wallets >= 2).then_some((wallets, minutes))
+    }
+
+    /// When a young wallet's bet is flagged; `None` when disabled
+    pub fn fresh_wallet(&self) -> Option<FreshWalletPolicy> {
+        let policy = self.config.as_ref().map(|c| c.fresh_wallet.clone()).unwrap_or_default();
+        policy.is_enabled().then_some(policy)
+    }
+
+    /// Names of the configured filter rules
+    pub fn rule_names(&self) -> impl Iterator<Item = &str> {
+        self.config.iter().flat_map(|c| c.filters.iter().map(|r| r.name.as_str()))
+    }
+
+    /// Report a filtered trade when explaining
+    pub fn explain_rejection(&self, trade: &NormalizedTrade, filter: &str, reason: &str) {
+        if self.explain {
+            display::print_rejection(trade, filter, reason);
+        }
+    }
+}
+
+/// A whale-sized trade accepted by an ingest task
+pub struct IngestedTrade {
+    /// Index of the originating source
+    pub source: usize,
+    pub trade: NormalizedTrade,
+    /// Set when `trade` stands for a wallet's accumulated flow
+    pub accumulation: Option<Accumulation>,
+    /// Last trade on the same asset before this one, if seen recently
+    pub price_before: Option<f64>,
+    pub received_at: Instant,
+}
+
+/// A trade that passed filtering, with everything looked up for its alert
+pub struct EnrichedTrade {
+    /// Index of the originating source
+    pub source: usize,
+    pub trade: NormalizedTrade,
+    pub market_context: Option<MarketContext>,
+    pub whale_profile: Option<WhaleProfile>,
+    pub order_book: Option<OrderBookSummary>,
+    pub top_holders: Option<TopHoldersSummary>,
+    pub accumulation: Option<Accumulation>,
+    /// How the watch filters treated the trade
+    pub filter_decisions: Vec<FilterDecision>,
+    /// Threshold the trade was held to
+    pub threshold: u64,
+    /// Trade size against the market's
+    pub liquidity: LiquidityRatios,
+    /// Relative triggers that let the trade through under its threshold
+    pub relative_triggers: Vec<&'static str>,
+    /// Price impact as of the alert; `None` for accumulation alerts
+    pub price_impact: Option<PriceImpact>,
+    pub received_at: Instant,
+}
+
+/// Wire up and run the pipeline until every ingest task has stopped and
+/// everything in flight has been stored. `conn` goes to the alert sink;
+/// `open_db` opens the connection each other stage gets.
+pub async fn run(
+    sources: Vec<Arc<dyn MarketSource>>,
+    settings: WatchSettings,
+    ingest: IngestOptions,
+    webhook_url: Option<String>,
+    conn: Connection,
+    open_db: &dyn Fn() -> Result<Connection, Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let settings = Arc::new(settings);
+    let metrics = Arc::new(PipelineMetrics::default());
+    let whale_cache = Arc::new(Mutex::new(WhaleProfileCache::new()));
+
+    let (trade_tx, trade_rx) = mpsc::channel(TRADE_QUEUE);
+    let (alert_tx, alert_rx) = mpsc::channel(ALERT_QUEUE);
+    let mut queues = vec![QueueProbe::new("trades", &trade_tx), QueueProbe::new("alerts", &alert_tx)];
+
+    // Ingest: one task per source, each with its own DB connection for cursors
+    for (index, source) in sources.iter().enumerate() {
+        spawn_ingest(
+            index,
+            Arc::clone(source),
+            open_db()?,
+            ingest.clone(),
+            trade_tx.clone(),
+            Arc::clone(&metrics),
+        );
+    }
+    drop(trade_tx);
+
+    let impact_sources = sources.clone();
+    spawn_outcome_tracker(sources.clone(), open_db()?);
+    spawn_enrichment(
+        trade_rx,
+        sources,
+        Arc::clone(&settings),
+        Arc::clone(&whale_cache),
+        open_db()?,
+        alert_tx,
+        Arc::clone(&metrics),
+    );
+
+    let webhook_tx = webhook_url.map(|url| {
+        let (tx, rx) = mpsc::channel(WEBHOOK_QUEUE);
+        queues.push(QueueProbe::new("webhooks", &tx));
+        spawn_webhook_sink(url, rx, Arc::clone(&metrics));
+        tx
+    });
+
+    // Price impact follow-ups need the sources too, so enrichment gets a copy
+    let impact = match settings.price_impact_window() {
+        Some(window) => {
+            let (tx, rx) = mpsc::channel(IMPACT_QUEUE);
+            queues.push(QueueProbe::new("impact", &tx));
+            let tracker = spawn_impact_tracker(rx, impact_sources, window, open_db()?, webhook_tx.clone());
+            Some((tx, tracker))
+        }
+        None => None,
+    };
+    let (impact_tx, impact_tracker) = impact.unzip();
+
+    let (link_tx, link_rx) = mpsc::channel(LINK_QUEUE);
+    queues.push(QueueProbe::new("links", &link_tx));
+    let linker = spawn_cluster_linker(link_rx, open_db()?);
+
+    spawn_reporter(Arc::clone(&metrics), queues, enrich::WORKERS);
+
+    spawn_alert_sink(alert_rx, settings, whale_cache, conn, webhook_tx, impact_tx, link_tx, metrics).await?;
+    linker.await?;
+    // Let measurements still running finish and report
+    if let Some(tracker) = impact_tracker {
+        tracker.await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! Outcome tracker: prices each stored alert's market at the checkpoints in
+//! `outcomes::CHECKPOINTS` and records its settled price once it resolves.
+//!
+//! Outcomes live in the database, so ones still open when the watcher stops
+//! are picked up again on the next run.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rusqlite::Connection;
+
+use super::impact::mid_price;
+use crate::capture;
+use crate::outcomes::{self, Step};
+use crate::platforms::MarketSource;
+
+/// Time between looks for due outcomes
+const TICK: Duration = Duration::from_secs(60);
+/// Outcomes checked per source each tick
+const BATCH: usize = 50;
+
+/// Start the outcome tracker. It runs until the watcher exits.
+pub fn spawn_outcome_tracker(sources: Vec<Arc<dyn MarketSource>>, mut conn: Connection) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(TICK);
+        loop {
+            tick.tick().await;
+            for source in &sources {
+                check_due(source.as_ref(), &mut conn).await;
+            }
+        }
+    })
+}
+
+/// Price every due outcome on the source's platform and schedule its next check.
+/// Takes `&mut` only so the future stays `Send` (`Connection` isn't `Sync`).
+async fn check_due(source: &dyn MarketSource, conn: &mut Connection) {
+    let now = capture::now().timestamp();
+    let due = match outcomes::load_due(conn, source.platform(), now, BATCH) {
+        Ok(due) => due,
+        Err(e) => {
+            eprintln!("Warning: Failed to load alert outcomes: {}", e);
+            return;
+        }
+    };
+
+    for mut pending in due {
+        let trade = pending.trade();
+        match outcomes::step(pending.alerted_at, &pending.prices, now) {
+            Step::Price(index) => {
+                if let Some(mid) = source.fetch_order_book(&trade).await.as_ref().and_then(mid_price) {
+                    outcomes::store_checkpoint(conn, pending.alert_id, index, mid);
+                    pending.prices[index] = Some(mid);
+                } else if let Some(settled) = source.fetch_resolution(&trade).await {
+                    // The book is gone because the market settled
+                    outcomes::store_checkpoint(conn, pending.alert_id, index, settled);
+                    outcomes::store_resolution(conn, pending.alert_id, settled, now);
+                    continue;
+                }
+            }
+            Step::Resolve => {
+                if let Some(settled) = source.fetch_resolution(&trade).await {
+                    outcomes::store_resolution(conn, pending.alert_id, settled, now);
+                    continue;
+                }
+            }
+            Step::Wait => {}
+        }
+        outcomes::set_next_check(conn, pending.alert_id, outcomes::next_check(pending.alerted_at, &pending.prices, now));
+    }
+}
@@ -0,0 +1,474 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use colored::*;
+use rusqlite::Connection;
+use serde_json::json;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinSet;
+
+use super::coordinate::{Coordination, CoordinationTracker};
+use super::impact::ImpactJob;
+use super::link::LinkJob;
+use super::metrics::PipelineMetrics;
+use super::{EnrichedTrade, WatchSettings};
+use crate::alerts::display::{self, print_market_context, print_order_book, print_top_holders, print_trade_alert, print_whale_profile};
+use crate::alerts::{self, anomaly, history, rules, webhook, AlertData, FilterDecision, ImpactStatus};
+use crate::capture;
+use crate::clusters;
+use crate::config::FreshWalletPolicy;
+use crate::db;
+use crate::outcomes;
+use crate::platforms::NormalizedTrade;
+use crate::polygon;
+use crate::types;
+use crate::whale_profile::{WhaleProfile, WhaleProfileCache};
+
+/// How often expired wallet memory, old alerts and cached profiles are pruned
+const PRUNE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Start the alert sink: the single owner of wallet memory, which stores,
+/// displays and records each enriched trade exactly once, then queues its
+/// webhook payload, hands it to the price impact tracker and cluster linker
+/// and checks it for coordinated flow. A fresh wallet flag made before the
+/// wallet's funding lookup finished is re-checked once it does.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_alert_sink(
+    mut rx: mpsc::Receiver<EnrichedTrade>,
+    settings: Arc<WatchSettings>,
+    whale_cache: Arc<Mutex<WhaleProfileCache>>,
+    conn: Connection,
+    webhook_tx: Option<mpsc::Sender<serde_json::Value>>,
+    impact_tx: Option<mpsc::Sender<ImpactJob>>,
+    link_tx: mpsc::Sender<LinkJob>,
+    metrics: Arc<PipelineMetrics>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut wallet_tracker = types::WalletTracker::new();
+        let mut coordination = settings.coordinated_flow().and_then(|(wallets, minutes)| CoordinationTracker::new(wallets, minutes));
+        let mut prune = tokio::time::interval(PRUNE_INTERVAL);
+        let retention = settings.config.as_ref().map(|c| c.history_retention_days).unwrap_or(30);
+        let mut rechecks: JoinSet<(FreshWalletCheck, Option<polygon::Funding>)> = JoinSet::new();
+        let mut open = true;
+
+        loop {
+            if !open && rechecks.is_empty() {
+                return;
+            }
+            tokio::select! {
+                biased;
+
+                finished = rechecks.join_next(), if !rechecks.is_empty() => {
+                    let Some(Ok((check, Some(funding)))) = finished else {
+                        continue;
+                    };
+                    let wallet_id = check.profile.wallet_id.clone();
+                    let funder = funding.funder.clone();
+                    let payload = recheck_fresh_wallet(&conn, check, funding);
+                    if let (Some(tx), Some(payload)) = (webhook_tx.as_ref(), payload) {
+                        if tx.send(payload).await.is_err() {
+                            return;
+                        }
+                    }
+                    if link_tx.send(LinkJob { wallet_id, funded_by: Some(funder) }).await.is_err() {
+                        return;
+                    }
+                }
+                enriched = rx.recv(), if open => {
+                    let Some(enriched) = enriched else {
+                        open = false;
+                        continue;
+                    };
+                    wallet_tracker.maybe_refresh_cache(&conn);
+
+                    let Some((payload, check)) = emit_alert(&conn, &settings, &mut wallet_tracker, &enriched, &metrics) else {
+                        continue;
+                    };
+
+                    // Re-check the flag once a funding lookup that outlasted enrichment finishes
+                    if let Some(check) = check {
+                        if let Some(pending) = whale_cache.lock().await.pending_funding(&check.profile.wallet_id) {
+                            rechecks.spawn(async move { (check, pending.finished().await) });
+                        }
+                    }
+
+                    if let Some(ref tx) = webhook_tx {
+                        if tx.send(payload).await.is_err() {
+                            return;
+                        }
+                    }
+
+                    // Relink the wallet now its alert is stored
+                    if let Some(ref wallet_id) = enriched.trade.wallet_id {
+                        let funded_by = enriched.whale_profile.as_ref().and_then(|p| p.funded_by.clone());
+                        if link_tx.send(LinkJob { wallet_id: wallet_id.clone(), funded_by }).await.is_err() {
+                            return;
+                        }
+                    }
+
+                    // Other wallets may have just hit the same outcome. Accumulation
+                    // alerts summarize flow whose orders were already counted.
+                    let flow_payload = coordination
+                        .as_mut()
+                        .filter(|_| enriched.accumulation.is_none())
+                        .and_then(|c| c.record(&enriched.trade))
+                        .and_then(|c| emit_coordinated_flow(&conn, &c));
+                    if let (Some(tx), Some(payload)) = (webhook_tx.as_ref(), flow_payload) {
+                        if tx.send(payload).await.is_err() {
+                            return;
+                        }
+                    }
+
+                    // Follow the trade's price impact until its window ends
+                    if let (Some(tx), Some(impact)) = (impact_tx.as_ref(), enriched.price_impact.as_ref()) {
+                        if impact.status == ImpactStatus::Measuring {
+                            let job = ImpactJob { source: enriched.source, trade: enriched.trade.clone(), impact: impact.clone() };
+                            if tx.send(job).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+                _ = prune.tick() => {
+                    db::prune_wallet_memory(&conn);
+                    db::prune_baselines(&conn);
+                    db::prune_old_alerts(&conn, retention);
+                    whale_cache.lock().await.prune();
+                    wallet_tracker.maybe_refresh_cache(&conn);
+                }
+            }
+        }
+    })
+}
+
+/// A fresh wallet verdict made without the wallet's funding time
+struct FreshWalletCheck {
+    trade: NormalizedTrade,
+    odds: Option<f64>,
+    ts: i64,
+    profile: WhaleProfile,
+    policy: FreshWalletPolicy,
+    flag: Option<anomaly::FreshWallet>,
+    /// The alert's type without a fresh wallet flag
+    base_type: String,
+}
+
+/// Store, display and record one alert, and start following its outcome.
+/// Returns its webhook payload, and what to re-check if its wallet's funding
+/// turns up later, or `None` if the trade was already alerted.
+fn emit_alert(
+    conn: &Connection,
+    settings: &WatchSettings,
+    wallet_tracker: &mut types::WalletTracker,
+    enriched: &EnrichedTrade,
+    metrics: &PipelineMetrics,
+) -> Option<(serde_json::Value, Option<FreshWalletCheck>)> {
+    let trade = &enriched.trade;
+    let platform = trade.platform.name();
+    let trade_value = trade.value();
+    let order_id = trade.order_id();
+
+    // Another worker may have enriched the same order meanwhile
+    if db::alert_exists(conn, platform, &order_id) {
+        PipelineMetrics::incr(&metrics.duplicates_skipped);
+        return None;
+    }
+
+    // Counted toward the wallet's activity only once the alert is stored
+    let wallet_activity = trade.wallet_id.as_deref().map(|wallet_id| wallet_tracker.activity_with(wallet_id, trade_value));
+
+    // Ingest recorded the order in its market's baseline before the category was known
+    let category = anomaly::baseline_category(&settings.category_registry, trade);
+    if let Some(ref category) = category {
+        db::set_baseline_category(conn, platform, &trade.market_id, category);
+    }
+    let mut recheck = None;
+    let (anomalies, fresh_wallet) = match trade.unix_timestamp() {
+        Some(ts) => {
+            let mut baselines = db::load_baselines(conn, platform, &trade.market_id, category.as_deref(), ts);
+            if enriched.accumulation.is_none() {
+                baselines.market = baselines.market.without(trade_value);
+                baselines.category = baselines.category.map(|(name, sums)| (name, sums.without(trade_value)));
+            }
+            let odds = rules::bought_side_price(&trade.side, trade.outcome.as_deref(), trade.price, enriched.market_context.as_ref());
+            let anomalies =
+                anomaly::detect_anomalies(trade_value, odds, enriched.accumulation.is_some(), wallet_activity.as_ref(), &baselines);
+            let fresh_wallet = match (enriched.whale_profile.as_ref(), settings.fresh_wallet()) {
+                (Some(profile), Some(policy)) => {
+                    let flag = anomaly::fresh_wallet(trade_value, odds, ts, profile, &policy);
+                    if profile.funded_at.is_none() && enriched.accumulation.is_none() {
+                        recheck = Some((odds, ts, profile, policy, flag.clone()));
+                    }
+                    flag
+                }
+                _ => None,
+            };
+            (anomalies, fresh_wallet)
+        }
+        None => (Vec::new(), None),
+    };
+
+    let alert_data = AlertData {
+        platform,
+        trade_id: Some(&trade.trade_id),
+        order_id: Some(&order_id),
+        market_title: trade.market_title.as_deref(),
+        outcome: trade.outcome.as_deref(),
+        side: &trade.side,
+        value: trade_value,
+        price: trade.price,
+        size: trade.size,
+        fill_count: trade.fills,
+        timestamp: &trade.timestamp,
+        wallet_id: trade.wallet_id.as_deref(),
+        wallet_activity: wallet_activity.as_ref(),
+        market_context: enriched.market_context.as_ref(),
+        whale_profile: enriched.whale_profile.as_ref(),
+        order_book: enriched.order_book.as_ref(),
+        top_holders: enriched.top_holders.as_ref(),
+        accumulation: enriched.accumulation.as_ref(),
+        score: None,
+        filter_decisions: &[],
+        threshold: Some(enriched.threshold),
+        band: match enriched.accumulation {
+            Some(_) => None,
+            None => settings.band(trade_value, enriched.threshold),
+        },
+        liquidity: Some(&enriched.liquidity),
+        relative_triggers: &enriched.relative_triggers,
+        price_impact: enriched.price_impact.as_ref(),
+        anomalies: &anomalies,
+        fresh_wallet: fresh_wallet.as_ref(),
+        wallet_cluster: None,
+    };
+    let score = settings.score(&alert_data);
+    let alert_data = AlertData { score: Some(&score), ..alert_data };
+
+    if let Some((rule, reason)) = settings.rejection(&alert_data) {
+        settings.explain_rejection(trade, &format!("rule \"{}\"", rule), &reason);
+        PipelineMetrics::incr(&metrics.trades_filtered);
+        return None;
+    }
+    let mut filter_decisions = enriched.filter_decisions.clone();
+    filter_decisions.extend(settings.rule_names().map(|rule| FilterDecision::passed(format!("rule \"{}\"", rule), None)));
+    let alert_data = AlertData { filter_decisions: &filter_decisions, ..alert_data };
+
+    // The insert is the source of truth: a concurrent watcher may have stored it meanwhile,
+    // and an alert the database failed to store is never sent
+    if !history::log_alert(&alert_data, conn) {
+        PipelineMetrics::incr(&metrics.duplicates_skipped);
+        return None;
+    }
+
+    outcomes::record_alert(
+        conn,
+        trade,
+        category.as_deref(),
+        alert_data.band.map(|b| b.name.as_str()),
+        score.tier.as_str(),
+        capture::now().timestamp(),
+    );
+
+    if let Some(ref wallet_id) = trade.wallet_id {
+        wallet_tracker.record_transaction(wallet_id, trade_value);
+    }
+    // Check for returning whale (12h memory)
+    let whale_scenario = trade.wallet_id.as_deref().and_then(|wid| {
+        wallet_tracker.classify_whale_return(conn, wid, Some(&trade.asset_id), trade.outcome.as_deref())
+    });
+
+    // As of the wallet's last relink; this alert's is queued once it's sent
+    let wallet_cluster = trade.wallet_id.as_deref().and_then(|wallet_id| clusters::cluster_of(conn, wallet_id));
+    let alert_data = AlertData { wallet_cluster: wallet_cluster.as_ref(), ..alert_data };
+
+    // Print returning whale info if detected
+    if let Some(ref scenario) = whale_scenario {
+        display::print_returning_whale(scenario, platform);
+    }
+
+    match enriched.accumulation {
+        Some(ref acc) => display::print_accumulation_alert(trade, acc),
+        None => print_trade_alert(trade, trade_value, wallet_activity.as_ref(), alert_data.band, &anomalies),
+    }
+    if let Some(ref flag) = fresh_wallet {
+        anomaly::print_fresh_wallet(flag);
+    }
+
+    if let Some(ref ctx) = enriched.market_context {
+        print_market_context(ctx);
+    }
+    if let Some(ref profile) = enriched.whale_profile {
+        print_whale_profile(profile);
+    }
+    if let Some(ref cluster) = wallet_cluster {
+        clusters::print_wallet_cluster(cluster);
+    }
+    if let Some(ref ob) = enriched.order_book {
+        print_order_book(ob);
+    }
+    if let Some(ref th) = enriched.top_holders {
+        print_top_holders(th);
+    }
+    display::print_liquidity(&enriched.liquidity, &enriched.relative_triggers);
+    if let Some(ref impact) = enriched.price_impact {
+        display::print_price_impact(impact);
+    }
+    display::print_score(&score);
+
+    // Record to wallet memory DB
+    if let Some(ref wallet_id) = trade.wallet_id {
+        wallet_tracker.record_to_db(
+            conn,
+            wallet_id,
+            trade.market_title.as_deref(),
+            Some(&trade.asset_id),
+            trade.outcome.as_deref(),
+            &trade.side,
+            trade_value,
+            trade.price,
+            platform,
+            capture::now().timestamp(),
+        );
+    }
+
+    PipelineMetrics::incr(&metrics.alerts_emitted);
+    metrics.record_latency(enriched.received_at);
+
+    let check = recheck.map(|(odds, ts, profile, policy, flag)| FreshWalletCheck {
+        trade: trade.clone(),
+        odds,
+        ts,
+        profile: profile.clone(),
+        policy,
+        flag,
+        base_type: AlertData { fresh_wallet: None, ..alert_data }.alert_type(),
+    });
+    Some((alerts::build_alert_payload(&alert_data, true), check))
+}
+
+/// Re-judge a fresh wallet flag with the wallet's funding. When the verdict
+/// changed, updates the stored alert's type and returns a
+/// `FRESH_WALLET_UPDATE` payload.
+fn recheck_fresh_wallet(conn: &Connection, check: FreshWalletCheck, funding: polygon::Funding) -> Option<serde_json::Value> {
+    let profile = WhaleProfile { funded_by: Some(funding.funder), funded_at: funding.funded_at, ..check.profile };
+    let flag = anomaly::fresh_wallet(check.trade.value(), check.odds, check.ts, &profile, &check.policy);
+    // Only the reason's wording changes when funding confirms the age
+    let unchanged = match (&flag, &check.flag) {
+        (Some(now), Some(before)) => now.severity == before.severity && now.age_hours == before.age_hours,
+        (now, before) => now.is_none() && before.is_none(),
+    };
+    if unchanged {
+        return None;
+    }
+
+    let trade = &check.trade;
+    let alert_type = if flag.is_some() { "FRESH_WALLET_ENTRY".to_string() } else { check.base_type };
+    db::update_alert_type(conn, trade.platform.name(), &trade.order_id(), &alert_type);
+    println!(
+        "{} {} {} - {}",
+        "[FRESH WALLET UPDATE]".bright_red().bold(),
+        trade.platform.name(),
+        trade.market_title.as_deref().unwrap_or(&trade.market_id),
+        match flag {
+            Some(ref flag) => flag.reason.clone(),
+            None => format!("no longer flagged, now {}", alert_type),
+        }
+    );
+
+    let rfc3339 = |ts: i64| chrono::DateTime::from_timestamp(ts, 0).map(|dt| dt.to_rfc3339());
+    Some(json!({
+        "platform": trade.platform.name(),
+        "alert_type": "FRESH_WALLET_UPDATE",
+        "updated_alert_type": alert_type,
+        "trade_id": trade.trade_id,
+        "order_id": trade.order_id(),
+        "action": trade.side.to_uppercase(),
+        "value": trade.value(),
+        "market_title": trade.market_title,
+        "outcome": trade.outcome,
+        "timestamp": trade.timestamp,
+        "wallet_id": trade.wallet_id,
+        "funded_by": profile.funded_by,
+        "funded_at": profile.funded_at.and_then(rfc3339),
+        "fresh_wallet": flag,
+    }))
+}
+
+/// Store a coordinated flow. Returns its webhook payload when it is new.
+fn emit_coordinated_flow(conn: &Connection, coordination: &Coordination) -> Option<serde_json::Value> {
+    db::upsert_coordinated_flow(conn, &coordination.key, &coordination.flow);
+    if !coordination.is_new {
+        return None;
+    }
+    display::print_coordinated_flow(&coordination.flow);
+    Some(alerts::build_flow_payload(&coordination.flow))
+}
+
+/// Start the webhook sink: posts queued payloads one at a time, in alert order
+pub fn spawn_webhook_sink(
+    webhook_url: String,
+    mut rx: mpsc::Receiver<serde_json::Value>,
+    metrics: Arc<PipelineMetrics>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        while let Some(payload) = rx.recv().await {
+            if webhook::send_webhook_payload(&webhook_url, &payload).await {
+                PipelineMetrics::incr(&metrics.webhooks_sent);
+            } else {
+                PipelineMetrics::incr(&metrics.webhooks_failed);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn late_funding_updates_a_changed_fresh_wallet_verdict() {
+        let conn = db::open_in_memory();
+        let trade = NormalizedTrade { price: 0.2, size: 150_000.0, wallet_id: Some("0xa".into()), ..NormalizedTrade::test("0xtx") };
+        let ts = trade.unix_timestamp().unwrap();
+        db::insert_alert(
+            &conn, "Polymarket", Some("0xtx"), Some(&trade.order_id()), "FRESH_WALLET_ENTRY", "BUY", 30_000.0, 0.2,
+            150_000.0, 1, None, None, None, Some("0xa"), &trade.timestamp, None, None, None, None, None,
+        );
+        let profile = WhaleProfile {
+            wallet_id: "0xa".into(),
+            portfolio_value: None,
+            positions_count: None,
+            leaderboard_rank: None,
+            leaderboard_profit: None,
+            win_rate: None,
+            markets_traded: None,
+            funded_by: None,
+            first_seen_at: Some(ts - 5 * 3600),
+            lifetime_trades: Some(1),
+            funded_at: None,
+        };
+        let policy = FreshWalletPolicy::default();
+        let check = || FreshWalletCheck {
+            trade: trade.clone(),
+            odds: Some(0.2),
+            ts,
+            profile: profile.clone(),
+            policy: policy.clone(),
+            flag: anomaly::fresh_wallet(30_000.0, Some(0.2), ts, &profile, &policy),
+            base_type: "WHALE_ENTRY".into(),
+        };
+        assert!(check().flag.is_some());
+
+        // Funded alongside its first trade: same verdict, nothing to send
+        let funding = |hours: i64| polygon::Funding { funder: "0xf".into(), funded_at: Some(ts - hours * 3600) };
+        assert!(recheck_fresh_wallet(&conn, check(), funding(5)).is_none());
+
+        // Funded long before it first traded: no longer fresh
+        let payload = recheck_fresh_wallet(&conn, check(), funding(200)).unwrap();
+        assert_eq!(payload["alert_type"], "FRESH_WALLET_UPDATE");
+        assert_eq!(payload["updated_alert_type"], "WHALE_ENTRY");
+        assert!(payload["fresh_wallet"].is_null());
+        let stored: String = conn.query_row("SELECT alert_type FROM alerts", [], |row| row.get(0)).unwrap();
+        assert_eq!(stored, "WHALE_ENTRY");
+    }
+}
@@ -1,13 +1,61 @@
-use crate::config::Config;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tokio::sync::mpsc;
+
+use super::kalshi_auth::KalshiSigner;
+use super::{MarketInfo, MarketSource, NormalizedTrade, Platform, SourceError, TradeBatch, TradeCursor, TradeGap};
+use crate::alerts::{MarketContext, OrderBookSummary, TopHoldersSummary};
+use crate::capture;
+use crate::config;
+use crate::http::{self, retry_hint, HttpError};
+use crate::ws::kalshi::KalshiWsEvent;
+
+/// Path prefix Kalshi expects in request signatures, whatever base URL is configured
+const API_PREFIX: &str = "/trade-api/v2";
+/// Largest page `/markets/trades` accepts
+const BACKFILL_PAGE_SIZE: u32 = 1000;
+/// Upper bound on pages fetched for one backfill
+const MAX_BACKFILL_PAGES: u32 = 20;
+/// Backfill starts this far before the last message, to absorb clock skew
+const BACKFILL_MARGIN_SECS: i64 = 10;
+/// Recent socket trade IDs remembered for de-duplicating backfills
+const SEEN_TRADES_CAPACITY: usize = 20_000;
 
 #[derive(Error, Debug)]
 pub enum KalshiError {
     #[error("HTTP request failed: {0}")]
-    RequestFailed(#[from] reqwest::Error),
-    #[error("Failed to parse response: {0}")]
-    ParseError(String),
+    RequestFailed(reqwest::Error),
+    #[error("Rate limited by Kalshi{}", retry_hint(.0))]
+    RateLimited(Option<Duration>),
+    #[error("Unauthorized (check your Kalshi API key)")]
+    Unauthorized,
+    #[error("Not found")]
+    NotFound,
+    #[error("API returned status: {0}")]
+    Status(u16),
+    #[error("Failed to decode response: {0}")]
+    Decode(String),
+    #[error("Unavailable in replay: {0}")]
+    Unavailable(String),
+}
+
+impl From<HttpError> for KalshiError {
+    fn from(e: HttpError) -> Self {
+        match e {
+            HttpError::RateLimited(retry_after) => KalshiError::RateLimited(retry_after),
+            HttpError::Unauthorized(_) => KalshiError::Unauthorized,
+            HttpError::NotFound => KalshiError::NotFound,
+            HttpError::Status(status) => KalshiError::Status(status.as_u16()),
+            HttpError::Request(e) => KalshiError::RequestFailed(e),
+            HttpError::Decode(msg) => KalshiError::Decode(msg),
+            HttpError::Unavailable(msg) => KalshiError::Unavailable(msg),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -26,53 +74,264 @@ pub struct Trade {
     // Use trade_id as proxy for tracking patterns
 }
 
+impl From<Trade> for NormalizedTrade {
+    fn from(trade: Trade) -> Self {
+        let outcome = parse_ticker_details(&trade.ticker, &trade.taker_side);
+        NormalizedTrade {
+            platform: Platform::Kalshi,
+            trade_id: trade.trade_id,
+            market_id: trade.ticker.clone(),
+            asset_id: trade.ticker,
+            market_title: trade.market_title,
+            category: None,
+            outcome: Some(outcome),
+            side: trade.taker_side.to_uppercase(),
+            price: trade.yes_price / 100.0,
+            size: f64::from(trade.count),
+            timestamp: trade.created_time,
+            wallet_id: None,
+            fills: 1,
+        }
+    }
+}
+
+/// Kalshi trade source: authenticated WebSocket stream with REST polling fallback
+pub struct KalshiSource {
+    signer: Option<Arc<KalshiSigner>>,
+    /// Socket backfills that hit the page limit, until ingest records them
+    stream_gaps: Arc<Mutex<Vec<TradeGap>>>,
+}
+
+impl KalshiSource {
+    pub fn new(signer: Option<Arc<KalshiSigner>>) -> Self {
+        Self { signer, stream_gaps: Arc::default() }
+    }
+}
+
+#[async_trait]
+impl MarketSource for KalshiSource {
+    fn platform(&self) -> Platform {
+        Platform::Kalshi
+    }
+
+    fn stream_trades(&self) -> Option<mpsc::UnboundedReceiver<NormalizedTrade>> {
+        let signer = self.signer.clone();
+        let stream_gaps = Arc::clone(&self.stream_gaps);
+        let mut events = match capture::replayer() {
+            Some(replayer) => crate::ws::kalshi::replay_kalshi_ws(replayer)?,
+            // The WebSocket only accepts authenticated connections
+            None => crate::ws::kalshi::spawn_kalshi_ws(Arc::clone(signer.as_ref()?)),
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        // Forward socket trades, filling reconnect and sequence gaps from REST
+        tokio::spawn(async move {
+            let mut seen = SeenTrades::new(SEEN_TRADES_CAPACITY);
+
+            while let Some(event) = events.recv().await {
+                let trades = match event {
+                    KalshiWsEvent::Trade(trade) => vec![trade],
+                    KalshiWsEvent::Gap { since, reason } => {
+                        eprintln!("[WS] Kalshi {} detected, backfilling trades via REST...", reason);
+                        match fetch_trades_since(since - BACKFILL_MARGIN_SECS, signer.as_deref()).await {
+                            // Oldest first, like the socket
+                            Ok((trades, gap)) => {
+                                if let Some(gap) = gap {
+                                    stream_gaps.lock().unwrap().push(gap);
+                                }
+                                trades.into_iter().rev().collect()
+                            }
+                            Err(e) => {
+                                eprintln!("[WS] Kalshi backfill failed: {}", e);
+                                Vec::new()
+                            }
+                        }
+                    }
+                };
+
+                for trade in trades {
+                    if !seen.insert(&trade.trade_id) {
+                        continue;
+                    }
+                    if tx.send(NormalizedTrade::from(trade)).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Some(rx)
+    }
+
+    async fn fetch_recent_trades(
+        &self,
+        _min_value: u64,
+        cursor: Option<&TradeCursor>,
+    ) -> Result<TradeBatch, SourceError> {
+        // With a cursor, page through everything since it so nothing is skipped
+        let (trades, gap) = match cursor {
+            Some(c) => fetch_trades_since(c.timestamp, self.signer.as_deref()).await?,
+            None => (fetch_recent_trades(self.signer.as_deref()).await?, None),
+        };
+        Ok(TradeBatch {
+            trades: trades
+                .into_iter()
+                .map(NormalizedTrade::from)
+                .filter(|t| cursor.is_none_or(|c| c.is_new(t)))
+                .collect(),
+            gap,
+        })
+    }
+
+    fn take_stream_gaps(&self) -> Vec<TradeGap> {
+        std::mem::take(&mut *self.stream_gaps.lock().unwrap())
+    }
+
+    async fn fetch_market_info(&self, trade: &NormalizedTrade) -> Option<MarketInfo> {
+        fetch_market_info_full(&trade.market_id, self.signer.as_deref()).await
+    }
+
+    async fn fetch_market_context(&self, trade: &NormalizedTrade) -> Option<MarketContext> {
+        fetch_market_context(&trade.market_id, self.signer.as_deref()).await
+    }
+
+    async fn fetch_order_book(&self, trade: &NormalizedTrade) -> Option<OrderBookSummary> {
+        fetch_order_book(&trade.market_id, self.signer.as_deref()).await
+    }
+
+    async fn fetch_top_holders(&self, _trade: &NormalizedTrade) -> Option<TopHoldersSummary> {
+        // Kalshi doesn't publish holders
+        None
+    }
+
+    async fn fetch_resolution(&self, trade: &NormalizedTrade) -> Option<f64> {
+        fetch_resolution(&trade.market_id, self.signer.as_deref()).await
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct TradesResponse {
     #[serde(default)]
     trades: Vec<Trade>,
+    /// Opaque pagination cursor, empty on the last page
+    #[serde(default)]
+    cursor: Option<String>,
 }
 
-pub async fn fetch_recent_trades(config: Option<&Config>) -> Result<Vec<Trade>, KalshiError> {
-    let client = reqwest::Client::new();
+/// Bounded set of recently handled trade IDs, oldest evicted first
+struct SeenTrades {
+    ids: HashSet<String>,
+    order: VecDeque<String>,
+    capacity: usize,
+}
 
-    // Kalshi's public trades endpoint
-    let url = "https://api.elections.kalshi.com/trade-api/v2/markets/trades";
+impl SeenTrades {
+    fn new(capacity: usize) -> Self {
+        Self {
+            ids: HashSet::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
 
+    /// Returns false if the ID was already seen
+    fn insert(&mut self, trade_id: &str) -> bool {
+        if self.ids.contains(trade_id) {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        self.ids.insert(trade_id.to_string());
+        self.order.push_back(trade_id.to_string());
+        true
+    }
+}
+
+/// Build a GET request for an API path (e.g. "/markets/trades"),
+/// signed when credentials are configured
+fn get(client: &reqwest::Client, path: &str, signer: Option<&KalshiSigner>) -> reqwest::RequestBuilder {
     let mut request = client
-        .get(url)
-        .query(&[("limit", "100")])
+        .get(format!("{}{}", config::endpoints().kalshi_api, path))
         .header("Accept", "application/json");
 
-    // Add authentication if credentials are provided
-    if let Some(cfg) = config {
-        if let (Some(key_id), Some(_private_key)) =
-            (&cfg.kalshi_api_key_id, &cfg.kalshi_private_key)
-        {
-            // For simplicity, we'll use basic auth
-            // In production, you'd implement proper HMAC signature
-            request = request.header("KALSHI-ACCESS-KEY", key_id);
+    if let Some(signer) = signer {
+        for (name, value) in signer.auth_headers("GET", &format!("{}{}", API_PREFIX, path)) {
+            request = request.header(name, value);
         }
     }
 
-    let response = request.send().await?;
+    request
+}
 
-    if !response.status().is_success() {
-        return Err(KalshiError::ParseError(format!(
-            "API returned status: {}",
-            response.status()
-        )));
-    }
+pub async fn fetch_recent_trades(signer: Option<&KalshiSigner>) -> Result<Vec<Trade>, KalshiError> {
+    // Kalshi's public trades endpoint
+    let text = http::shared()
+        .get_text(|client| get(client, "/markets/trades", signer).query(&[("limit", "100")]))
+        .await?;
 
-    let text = response.text().await?;
+    let response: TradesResponse = serde_json::from_str(&text).map_err(|e| KalshiError::Decode(e.to_string()))?;
+    Ok(response.trades)
+}
 
-    match serde_json::from_str::<TradesResponse>(&text) {
-        Ok(response) => Ok(response.trades),
-        Err(e) => {
-            // If parsing fails, return empty list to allow tool to continue
-            eprintln!("Warning: Failed to parse Kalshi response: {}", e);
-            Ok(Vec::new())
+/// Fetch every trade at or after `min_ts` (Unix seconds), newest first,
+/// following `cursor` pagination. Stopping at `MAX_BACKFILL_PAGES` returns
+/// a gap between `min_ts` and the oldest trade reached.
+pub async fn fetch_trades_since(
+    min_ts: i64,
+    signer: Option<&KalshiSigner>,
+) -> Result<(Vec<Trade>, Option<TradeGap>), KalshiError> {
+    let mut trades = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    for _ in 0..MAX_BACKFILL_PAGES {
+        let (page, next) = fetch_trades_page(min_ts, None, cursor.as_deref(), signer).await?;
+        trades.extend(page);
+
+        match next {
+            Some(c) => cursor = Some(c),
+            None => return Ok((trades, None)),
         }
     }
+
+    let gap = TradeGap {
+        cursor_timestamp: min_ts,
+        oldest_fetched: trades.iter().filter_map(trade_timestamp).min(),
+        pages: MAX_BACKFILL_PAGES,
+    };
+    Ok((trades, Some(gap)))
+}
+
+fn trade_timestamp(trade: &Trade) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(&trade.created_time).ok().map(|dt| dt.timestamp())
+}
+
+/// Fetch one page of trades between `min_ts` and `max_ts` (Unix seconds),
+/// newest first, plus the cursor for the next page if there is one
+pub async fn fetch_trades_page(
+    min_ts: i64,
+    max_ts: Option<i64>,
+    cursor: Option<&str>,
+    signer: Option<&KalshiSigner>,
+) -> Result<(Vec<Trade>, Option<String>), KalshiError> {
+    let mut query = vec![
+        ("limit", BACKFILL_PAGE_SIZE.to_string()),
+        ("min_ts", min_ts.to_string()),
+    ];
+    if let Some(max_ts) = max_ts {
+        query.push(("max_ts", max_ts.to_string()));
+    }
+    if let Some(c) = cursor {
+        query.push(("cursor", c.to_string()));
+    }
+
+    let page: TradesResponse = http::shared()
+        .get_json(|client| get(client, "/markets/trades", signer).query(&query))
+        .await?;
+
+    Ok((page.trades, page.cursor.filter(|c| !c.is_empty())))
 }
 
 #[derive(Debug, Deserialize)]
@@ -89,27 +348,13 @@ struct MarketData {
     tags: Vec<String>,
 }
 
-/// Market info including title and native category
-pub struct MarketInfo {
-    pub title: String,
-    pub category: Option<String>,
-    pub tags: Vec<String>,
-}
+pub async fn fetch_market_context(ticker: &str, signer: Option<&KalshiSigner>) -> Option<MarketContext> {
+    let path = format!("/markets/{}", ticker);
 
-pub async fn fetch_market_context(ticker: &str) -> Option<crate::alerts::MarketContext> {
-    let client = reqwest::Client::new();
-    let url = format!(
-        "https://api.elections.kalshi.com/trade-api/v2/markets/{}",
-        ticker
-    );
-
-    let response = client.get(&url).send().await.ok()?;
-    if !response.status().is_success() {
-        return None;
-    }
-
-    let text = response.text().await.ok()?;
-    let parsed: serde_json::Value = serde_json::from_str(&text).ok()?;
+    let parsed: serde_json::Value = http::shared()
+        .get_json(|client| get(client, &path, signer))
+        .await
+        .ok()?;
     let market = parsed.get("market")?;
 
     let yes_bid = market.get("yes_bid")
@@ -154,7 +399,7 @@ pub async fn fetch_market_context(ticker: &str) -> Option<crate::alerts::MarketC
         .map(|c| vec![c.to_string()])
         .unwrap_or_default();
 
-    Some(crate::alerts::MarketContext {
+    Some(MarketContext {
         yes_price: yes_bid,
         no_price: no_bid,
         spread,
@@ -166,37 +411,44 @@ pub async fn fetch_market_context(ticker: &str) -> Option<crate::alerts::MarketC
     })
 }
 
-/// Fetch order book from Kalshi public API
-pub async fn fetch_order_book(ticker: &str) -> Option<crate::alerts::OrderBookSummary> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()
-        .ok()?;
-
-    let url = format!(
-        "https://api.elections.kalshi.com/trade-api/v2/markets/{}/orderbook",
-        ticker
-    );
+/// Final YES price once the market has settled
+pub async fn fetch_resolution(ticker: &str, signer: Option<&KalshiSigner>) -> Option<f64> {
+    let path = format!("/markets/{}", ticker);
 
-    let response = client
-        .get(&url)
-        .header("Accept", "application/json")
-        .send()
+    let parsed: serde_json::Value = http::shared()
+        .get_json(|client| get(client, &path, signer))
         .await
         .ok()?;
-
-    if !response.status().is_success() {
+    let market = parsed.get("market")?;
+    let status = market.get("status").and_then(|v| v.as_str())?;
+    if !matches!(status, "determined" | "settled" | "finalized") {
         return None;
     }
+    match market.get("result").and_then(|v| v.as_str())? {
+        "yes" => Some(1.0),
+        "no" => Some(0.0),
+        _ => None,
+    }
+}
 
-    let text = response.text().await.ok()?;
-    let parsed: serde_json::Value = serde_json::from_str(&text).ok()?;
-    let orderbook = parsed.get("orderbook").unwrap_or(&parsed);
+/// Fetch order book from Kalshi public API
+pub async fn fetch_order_book(ticker: &str, signer: Option<&KalshiSigner>) -> Option<OrderBookSummary> {
+    let path = format!("/markets/{}/orderbook", ticker);
 
+    let parsed: serde_json::Value = http::shared()
+        .get_json(|client| get(client, &path, signer).timeout(Duration::from_secs(5)))
+        .await
+        .ok()?;
+    Some(summarize_order_book(parsed.get("orderbook").unwrap_or(&parsed)))
+}
+
+/// Summarize a Kalshi order book: arrays of [price in cents, quantity] bids
+/// for each side. A NO bid at p is a YES ask at 1 - p, so the best ask comes
+/// from the highest NO bid.
+fn summarize_order_book(orderbook: &serde_json::Value) -> OrderBookSummary {
     let yes_bids = orderbook.get("yes").and_then(|v| v.as_array());
     let no_bids = orderbook.get("no").and_then(|v| v.as_array());
 
-    // Kalshi orderbook format: arrays of [price, quantity] for yes and no sides
     let (best_bid, bid_depth, bid_levels) = if let Some(bids) = yes_bids {
         let mut best = 0.0f64;
         let mut depth = 0.0f64;
@@ -217,7 +469,7 @@ pub async fn fetch_order_book(ticker: &str) -> Option<crate::alerts::OrderBookSu
     };
 
     let (best_ask, ask_depth, ask_levels) = if let Some(asks) = no_bids {
-        let mut best = 1.0f64;
+        let mut best = 0.0f64;
         let mut depth = 0.0f64;
         let mut levels = 0u32;
         for entry in asks {
@@ -225,7 +477,7 @@ pub async fn fetch_order_book(ticker: &str) -> Option<crate::alerts::OrderBookSu
             if let Some(arr) = arr {
                 let price = arr.first().and_then(|v| v.as_f64()).unwrap_or(0.0) / 100.0;
                 let qty = arr.get(1).and_then(|v| v.as_f64()).unwrap_or(0.0);
-                if price < best { best = price; }
+                if price > best { best = price; }
                 depth += price * qty;
                 levels += 1;
             }
@@ -236,42 +488,31 @@ pub async fn fetch_order_book(ticker: &str) -> Option<crate::alerts::OrderBookSu
         (1.0, 0.0, 0)
     };
 
-    Some(crate::alerts::OrderBookSummary {
+    OrderBookSummary {
         best_bid,
         best_ask,
         bid_depth_10pct: bid_depth,
         ask_depth_10pct: ask_depth,
         bid_levels,
         ask_levels,
-    })
+    }
 }
 
 /// Fetch full market info including native category and tags
-pub async fn fetch_market_info_full(ticker: &str) -> Option<MarketInfo> {
-    let client = reqwest::Client::new();
-    let url = format!(
-        "https://api.elections.kalshi.com/trade-api/v2/markets/{}",
-        ticker
-    );
-
-    match client.get(&url).send().await {
-        Ok(response) if response.status().is_success() => {
-            if let Ok(text) = response.text().await {
-                if let Ok(market_response) = serde_json::from_str::<MarketResponse>(&text) {
-                    let title = market_response.market.title
-                        .or(market_response.market.subtitle)?;
-                    return Some(MarketInfo {
-                        title,
-                        category: market_response.market.category,
-                        tags: market_response.market.tags,
-                    });
-                }
-            }
-        }
-        _ => {}
-    }
+pub async fn fetch_market_info_full(ticker: &str, signer: Option<&KalshiSigner>) -> Option<MarketInfo> {
+    let path = format!("/markets/{}", ticker);
+
+    let market_response: MarketResponse = http::shared()
+        .get_json(|client| get(client, &path, signer))
+        .await
+        .ok()?;
 
-    None
+    let title = market_response.market.title.or(market_response.market.subtitle)?;
+    Some(MarketInfo {
+        title,
+        category: market_response.market.category,
+        tags: market_response.market.tags,
+    })
 }
 
 pub fn parse_ticker_details(ticker: &str, side: &str) -> String {
@@ -567,3 +808,34 @@ pub fn parse_ticker_details(ticker: &str, side: &str) -> String {
         String::from("NO - check market details")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seen_trades_drops_repeats_and_forgets_the_oldest() {
+        let mut seen = SeenTrades::new(2);
+        assert!(seen.insert("a"));
+        assert!(!seen.insert("a"));
+        assert!(seen.insert("b"));
+        // "a" is evicted to make room, so a late backfill of it goes through again
+        assert!(seen.insert("c"));
+        assert!(!seen.insert("b"));
+        assert!(seen.insert("a"));
+    }
+
+    #[test]
+    fn best_ask_is_one_minus_the_highest_no_bid() {
+        let book = summarize_order_book(&serde_json::json!({
+            "yes": [[30, 100], [38, 50]],
+            "no": [[55, 200], [60, 10], [41, 5]]
+        }));
+        assert!((book.best_bid - 0.38).abs() < 1e-9);
+        assert!((book.best_ask - 0.40).abs() < 1e-9);
+        assert_eq!((book.bid_levels, book.ask_levels), (2, 3));
+
+        let empty = summarize_order_book(&serde_json::json!({"yes": null, "no": []}));
+        assert_eq!((empty.best_bid, empty.best_ask), (0.0, 1.0));
+    }
+}
@@ -1,2 +1,288 @@
 pub mod kalshi;
+pub mod kalshi_auth;
 pub mod polymarket;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::alerts::{MarketContext, OrderBookSummary, TopHoldersSummary};
+use crate::whale_profile::{WhaleProfile, WhaleProfileCache};
+
+/// Prediction market venue a trade came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+    Polymarket,
+    Kalshi,
+}
+
+impl Platform {
+    /// Display name, also used as the `platform` value in the DB and webhook payload
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Polymarket => "Polymarket",
+            Platform::Kalshi => "Kalshi",
+        }
+    }
+}
+
+/// Platform-agnostic trade consumed by the watch pipeline
+#[derive(Debug, Clone)]
+pub struct NormalizedTrade {
+    pub platform: Platform,
+    pub trade_id: String,
+    /// Polymarket condition ID or Kalshi ticker
+    pub market_id: String,
+    /// Polymarket outcome token ID or Kalshi ticker (order book key)
+    pub asset_id: String,
+    pub market_title: Option<String>,
+    /// Native market category, when the venue provides one
+    pub category: Option<String>,
+    /// Human-readable outcome being traded
+    pub outcome: Option<String>,
+    /// "BUY"/"SELL" on Polymarket, "YES"/"NO" on Kalshi
+    pub side: String,
+    /// Price per contract (0.0-1.0)
+    pub price: f64,
+    /// Number of contracts
+    pub size: f64,
+    pub timestamp: String,
+    /// Trader wallet, for venues that expose one
+    pub wallet_id: Option<String>,
+    /// Fills merged into this trade; 1 for a single fill
+    pub fills: u32,
+}
+
+impl NormalizedTrade {
+    /// Notional value in USD
+    pub fn value(&self) -> f64 {
+        self.size * self.price
+    }
+
+    /// Trade time as Unix seconds, if the timestamp parses
+    pub fn unix_timestamp(&self) -> Option<i64> {
+        chrono::DateTime::parse_from_rfc3339(&self.timestamp)
+            .ok()
+            .map(|dt| dt.timestamp())
+    }
+
+    /// Identity of the order this trade belongs to, the alert's dedup key.
+    /// Polymarket trade IDs are transaction hashes, and one transaction can
+    /// carry orders from several wallets, outcomes and sides.
+    pub fn order_id(&self) -> String {
+        match self.platform {
+            Platform::Polymarket => format!(
+                "{}|{}|{}|{}",
+                self.trade_id,
+                self.wallet_id.as_deref().unwrap_or_default(),
+                self.asset_id,
+                self.side
+            ),
+            Platform::Kalshi => self.trade_id.clone(),
+        }
+    }
+
+    /// Whether `other` is this same fill delivered again. Polymarket fills of
+    /// one transaction share a trade ID, so that alone isn't enough.
+    pub fn is_same_fill(&self, other: &NormalizedTrade) -> bool {
+        self.trade_id == other.trade_id
+            && self.asset_id == other.asset_id
+            && self.price == other.price
+            && self.size == other.size
+    }
+}
+
+#[cfg(test)]
+impl NormalizedTrade {
+    /// Test order: 100 contracts bought at 50¢ on Polymarket market "m".
+    /// Override fields with struct update syntax.
+    pub fn test(trade_id: &str) -> Self {
+        NormalizedTrade {
+            platform: Platform::Polymarket,
+            trade_id: trade_id.into(),
+            market_id: "m".into(),
+            asset_id: "a".into(),
+            market_title: None,
+            category: None,
+            outcome: None,
+            side: "BUY".into(),
+            price: 0.5,
+            size: 100.0,
+            timestamp: "2026-10-01T12:00:00+00:00".into(),
+            wallet_id: None,
+            fills: 1,
+        }
+    }
+}
+
+/// Position of the newest trade already handled for a source.
+///
+/// Holds the newest trade time plus every trade ID seen at that second, so
+/// rows sharing a timestamp are deduplicated even if the API reorders them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradeCursor {
+    pub timestamp: i64,
+    pub trade_ids: Vec<String>,
+}
+
+impl TradeCursor {
+    /// Move the cursor forward if `trade` is newer than anything seen so far
+    pub fn advance(&mut self, trade: &NormalizedTrade) {
+        let Some(ts) = trade.unix_timestamp() else {
+            return;
+        };
+
+        if ts > self.timestamp {
+            self.timestamp = ts;
+            self.trade_ids.clear();
+            self.trade_ids.push(trade.trade_id.clone());
+        } else if ts == self.timestamp && !self.trade_ids.contains(&trade.trade_id) {
+            self.trade_ids.push(trade.trade_id.clone());
+        }
+    }
+
+    /// Whether `trade` comes after the cursor and still needs handling
+    pub fn is_new(&self, trade: &NormalizedTrade) -> bool {
+        if self.trade_ids.contains(&trade.trade_id) {
+            return false;
+        }
+        match trade.unix_timestamp() {
+            Some(ts) => ts >= self.timestamp,
+            None => true,
+        }
+    }
+
+    /// Whether `trade` is at or behind the cursor, i.e. polling has caught up
+    pub fn overlaps(&self, trade: &NormalizedTrade) -> bool {
+        self.trade_ids.contains(&trade.trade_id)
+            || trade.unix_timestamp().is_some_and(|ts| ts < self.timestamp)
+    }
+}
+
+/// Trades newer than a cursor, newest first
+pub struct TradeBatch {
+    pub trades: Vec<NormalizedTrade>,
+    /// Set when polling couldn't page back far enough to reach the cursor
+    pub gap: Option<TradeGap>,
+}
+
+/// Polling lost continuity: trades between the cursor and `oldest_fetched` may be missing
+#[derive(Debug, Clone)]
+pub struct TradeGap {
+    pub cursor_timestamp: i64,
+    /// Oldest trade time reached before giving up
+    pub oldest_fetched: Option<i64>,
+    pub pages: u32,
+}
+
+/// Market title and native category
+pub struct MarketInfo {
+    pub title: String,
+    pub category: Option<String>,
+    #[allow(dead_code)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum SourceError {
+    #[error("Polymarket: {0}")]
+    Polymarket(#[from] polymarket::PolymarketError),
+    #[error("Kalshi: {0}")]
+    Kalshi(#[from] kalshi::KalshiError),
+}
+
+/// A venue the watcher can ingest trades from and enrich alerts with
+#[async_trait]
+pub trait MarketSource: Send + Sync {
+    fn platform(&self) -> Platform;
+
+    /// Start a real-time trade stream, if the venue (and our credentials) support one
+    fn stream_trades(&self) -> Option<mpsc::UnboundedReceiver<NormalizedTrade>>;
+
+    /// Poll trades newer than `cursor`, newest first. With no cursor, returns the
+    /// latest page. Used when no stream is available or it goes silent.
+    async fn fetch_recent_trades(
+        &self,
+        min_value: u64,
+        cursor: Option<&TradeCursor>,
+    ) -> Result<TradeBatch, SourceError>;
+
+    /// Gaps the stream found but couldn't fill, for ingest to record
+    fn take_stream_gaps(&self) -> Vec<TradeGap> {
+        Vec::new()
+    }
+
+    /// Look up the market title and native category when the trade doesn't carry them
+    async fn fetch_market_info(&self, trade: &NormalizedTrade) -> Option<MarketInfo>;
+
+    async fn fetch_market_context(&self, trade: &NormalizedTrade) -> Option<MarketContext>;
+
+    async fn fetch_order_book(&self, trade: &NormalizedTrade) -> Option<OrderBookSummary>;
+
+    async fn fetch_top_holders(&self, trade: &NormalizedTrade) -> Option<TopHoldersSummary>;
+
+    /// Settled price of the trade's asset (1.0 or 0.0, in the terms of
+    /// `NormalizedTrade::price`) once its market has resolved
+    async fn fetch_resolution(&self, trade: &NormalizedTrade) -> Option<f64>;
+
+    /// Trader intelligence for venues with public wallets
+    async fn fetch_whale_profile(
+        &self,
+        _wallet_id: &str,
+        _cache: &Mutex<WhaleProfileCache>,
+    ) -> Option<WhaleProfile> {
+        None
+    }
+}
+
+/// Adapt a platform-specific trade stream into a normalized one
+pub fn normalize_stream<T>(mut rx: mpsc::UnboundedReceiver<T>) -> mpsc::UnboundedReceiver<NormalizedTrade>
+where
+    T: Into<NormalizedTrade> + Send + 'static,
+{
+    let (tx, normalized_rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Some(trade) = rx.recv().await {
+            if tx.send(trade.into()).is_err() {
+                break;
+            }
+        }
+    });
+
+    normalized_rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(id: &str, timestamp: &str) -> NormalizedTrade {
+        NormalizedTrade { timestamp: timestamp.into(), ..NormalizedTrade::test(id) }
+    }
+
+    #[test]
+    fn cursor_dedupes_trades_sharing_a_second() {
+        let mut cursor = TradeCursor::default();
+        cursor.advance(&trade("a", "2024-01-01T00:00:10+00:00"));
+        cursor.advance(&trade("b", "2024-01-01T00:00:10+00:00"));
+
+        assert!(!cursor.is_new(&trade("a", "2024-01-01T00:00:10+00:00")));
+        assert!(cursor.is_new(&trade("c", "2024-01-01T00:00:10+00:00")));
+        assert!(cursor.is_new(&trade("d", "2024-01-01T00:00:11+00:00")));
+        assert!(!cursor.is_new(&trade("e", "2024-01-01T00:00:09+00:00")));
+    }
+
+    #[test]
+    fn cursor_overlaps_on_known_id_or_older_trade() {
+        let mut cursor = TradeCursor::default();
+        cursor.advance(&trade("a", "2024-01-01T00:00:10+00:00"));
+        cursor.advance(&trade("old", "2024-01-01T00:00:05+00:00"));
+
+        assert_eq!(cursor.trade_ids, vec!["a".to_string()]);
+        assert!(cursor.overlaps(&trade("a", "2024-01-01T00:00:10+00:00")));
+        assert!(cursor.overlaps(&trade("x", "2024-01-01T00:00:09+00:00")));
+        assert!(!cursor.overlaps(&trade("y", "2024-01-01T00:00:10+00:00")));
+    }
+}
@@ -1,12 +1,52 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tokio::sync::{mpsc, Mutex};
+
+use super::{MarketInfo, MarketSource, NormalizedTrade, Platform, SourceError, TradeBatch, TradeCursor, TradeGap};
+use crate::alerts::{MarketContext, OrderBookSummary, TopHolder, TopHoldersSummary};
+use crate::capture;
+use crate::config;
+use crate::http::{self, retry_hint, HttpError};
+use crate::whale_profile::{self, WhaleProfile, WhaleProfileCache};
+
+/// Rows per Data API page when server-side CASH filtering is on
+pub const TRADES_PAGE_SIZE: u32 = 500;
+/// How far back polling pages before declaring a gap
+const MAX_TRADE_PAGES: u32 = 10;
 
 #[derive(Error, Debug)]
 pub enum PolymarketError {
     #[error("HTTP request failed: {0}")]
-    RequestFailed(#[from] reqwest::Error),
-    #[error("Failed to parse response: {0}")]
-    ParseError(String),
+    RequestFailed(reqwest::Error),
+    #[error("Rate limited by Polymarket{}", retry_hint(.0))]
+    RateLimited(Option<Duration>),
+    #[error("Unauthorized")]
+    Unauthorized,
+    #[error("Not found")]
+    NotFound,
+    #[error("API returned status: {0}")]
+    Status(u16),
+    #[error("Failed to decode response: {0}")]
+    Decode(String),
+    #[error("Unavailable in replay: {0}")]
+    Unavailable(String),
+}
+
+impl From<HttpError> for PolymarketError {
+    fn from(e: HttpError) -> Self {
+        match e {
+            HttpError::RateLimited(retry_after) => PolymarketError::RateLimited(retry_after),
+            HttpError::Unauthorized(_) => PolymarketError::Unauthorized,
+            HttpError::NotFound => PolymarketError::NotFound,
+            HttpError::Status(status) => PolymarketError::Status(status.as_u16()),
+            HttpError::Request(e) => PolymarketError::RequestFailed(e),
+            HttpError::Decode(msg) => PolymarketError::Decode(msg),
+            HttpError::Unavailable(msg) => PolymarketError::Unavailable(msg),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -26,14 +66,91 @@ pub struct Trade {
     pub wallet_id: Option<String>,
 }
 
+impl From<Trade> for NormalizedTrade {
+    fn from(trade: Trade) -> Self {
+        NormalizedTrade {
+            platform: Platform::Polymarket,
+            trade_id: trade.id,
+            market_id: trade.market,
+            asset_id: trade.asset_id,
+            market_title: trade.market_title,
+            category: None,
+            outcome: trade.outcome,
+            side: trade.side,
+            price: trade.price,
+            size: trade.size,
+            timestamp: trade.timestamp,
+            wallet_id: trade.wallet_id,
+            fills: 1,
+        }
+    }
+}
+
+/// Polymarket trade source: real-time activity feed with Data API polling fallback
+pub struct PolymarketSource;
+
+#[async_trait]
+impl MarketSource for PolymarketSource {
+    fn platform(&self) -> Platform {
+        Platform::Polymarket
+    }
+
+    fn stream_trades(&self) -> Option<mpsc::UnboundedReceiver<NormalizedTrade>> {
+        let trades = match capture::replayer() {
+            Some(replayer) => crate::ws::polymarket::replay_polymarket_ws(replayer)?,
+            None => crate::ws::polymarket::spawn_polymarket_ws(),
+        };
+        Some(super::normalize_stream(trades))
+    }
+
+    async fn fetch_recent_trades(
+        &self,
+        min_value: u64,
+        cursor: Option<&TradeCursor>,
+    ) -> Result<TradeBatch, SourceError> {
+        Ok(fetch_trades_since(min_value, cursor).await?)
+    }
+
+    async fn fetch_market_info(&self, _trade: &NormalizedTrade) -> Option<MarketInfo> {
+        // Titles arrive with the trade and Polymarket has no native category
+        None
+    }
+
+    async fn fetch_market_context(&self, trade: &NormalizedTrade) -> Option<MarketContext> {
+        fetch_market_context(&trade.market_id).await
+    }
+
+    async fn fetch_order_book(&self, trade: &NormalizedTrade) -> Option<OrderBookSummary> {
+        fetch_order_book(&trade.asset_id).await
+    }
+
+    async fn fetch_top_holders(&self, trade: &NormalizedTrade) -> Option<TopHoldersSummary> {
+        fetch_top_holders(&trade.market_id).await
+    }
+
+    async fn fetch_resolution(&self, trade: &NormalizedTrade) -> Option<f64> {
+        fetch_resolution(&trade.market_id, trade.outcome.as_deref()?).await
+    }
+
+    async fn fetch_whale_profile(
+        &self,
+        wallet_id: &str,
+        cache: &Mutex<WhaleProfileCache>,
+    ) -> Option<WhaleProfile> {
+        whale_profile::fetch_whale_profile(wallet_id, cache).await
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct TradesResponse {
     #[serde(default)]
     data: Vec<ActivityItem>,
 }
 
+/// Raw trade record shared by the Data API `/trades` endpoint and the
+/// real-time activity feed (`ws::polymarket`)
 #[derive(Debug, Deserialize)]
-struct ActivityItem {
+pub(crate) struct ActivityItem {
     #[serde(rename = "transactionHash")]
     id: String,
     #[serde(rename = "conditionId")]
@@ -54,26 +171,48 @@ struct ActivityItem {
     outcome: Option<String>,
 }
 
-pub async fn fetch_market_context(condition_id: &str) -> Option<crate::alerts::MarketContext> {
-    let client = reqwest::Client::new();
+impl ActivityItem {
+    /// Convert into a `Trade`, skipping records missing critical data
+    pub(crate) fn into_trade(self) -> Option<Trade> {
+        let market = self.market?;
+        let asset_id = self.asset?;
+        let side = self.side?;
+        let size = self.size?;
+        let price = self.price?;
+
+        Some(Trade {
+            timestamp: self
+                .timestamp
+                .and_then(|ts| {
+                    chrono::DateTime::from_timestamp(ts, 0)
+                        .map(|dt| dt.to_rfc3339())
+                })
+                .unwrap_or_else(|| format!("timestamp_error_{}", self.id)),
+            id: self.id,
+            market,
+            asset_id,
+            side,
+            size,
+            price,
+            // New API includes title and outcome directly
+            market_title: self.title,
+            outcome: self.outcome,
+            wallet_id: self.proxy_wallet.or(self.user).or(self.maker),
+        })
+    }
+}
+
+pub async fn fetch_market_context(condition_id: &str) -> Option<MarketContext> {
     let url = format!(
-        "https://gamma-api.polymarket.com/markets?condition_ids={}",
+        "{}/markets?condition_ids={}",
+        config::endpoints().polymarket_gamma_api,
         condition_id
     );
 
-    let response = client
-        .get(&url)
-        .header("Accept", "application/json")
-        .send()
+    let markets: Vec<serde_json::Value> = http::shared()
+        .get_json(|client| client.get(&url).header("Accept", "application/json"))
         .await
         .ok()?;
-
-    if !response.status().is_success() {
-        return None;
-    }
-
-    let text = response.text().await.ok()?;
-    let markets: Vec<serde_json::Value> = serde_json::from_str(&text).ok()?;
     let market = markets.first()?;
 
     let yes_price = market.get("outcomePrices")
@@ -135,7 +274,7 @@ pub async fn fetch_market_context(condition_id: &str) -> Option<crate::alerts::M
         })
         .unwrap_or_default();
 
-    Some(crate::alerts::MarketContext {
+    Some(MarketContext {
         yes_price,
         no_price,
         spread,
@@ -147,27 +286,41 @@ pub async fn fetch_market_context(condition_id: &str) -> Option<crate::alerts::M
     })
 }
 
-/// Fetch order book from CLOB API for a given asset (token) ID
-pub async fn fetch_order_book(asset_id: &str) -> Option<crate::alerts::OrderBookSummary> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()
-        .ok()?;
-
-    let response = client
-        .get("https://clob.polymarket.com/book")
-        .query(&[("token_id", asset_id)])
-        .header("Accept", "application/json")
-        .send()
+/// Final price of `outcome` once the market has closed and settled at 0 or 1
+pub async fn fetch_resolution(condition_id: &str, outcome: &str) -> Option<f64> {
+    let url = format!(
+        "{}/markets?condition_ids={}",
+        config::endpoints().polymarket_gamma_api,
+        condition_id
+    );
+    let markets: Vec<serde_json::Value> = http::shared()
+        .get_json(|client| client.get(&url).header("Accept", "application/json"))
         .await
         .ok()?;
-
-    if !response.status().is_success() {
+    let market = markets.first()?;
+    if !market.get("closed").and_then(|v| v.as_bool()).unwrap_or(false) {
         return None;
     }
 
-    let text = response.text().await.ok()?;
-    let book: serde_json::Value = serde_json::from_str(&text).ok()?;
+    // outcomes and outcomePrices are JSON strings like "[\"Yes\",\"No\"]"
+    let list = |field: &str| -> Option<Vec<String>> { serde_json::from_str(market.get(field)?.as_str()?).ok() };
+    let index = list("outcomes")?.iter().position(|o| o.eq_ignore_ascii_case(outcome))?;
+    let price: f64 = list("outcomePrices")?.get(index)?.parse().ok()?;
+    (price <= 0.01 || price >= 0.99).then(|| price.round())
+}
+
+/// Fetch order book from CLOB API for a given asset (token) ID
+pub async fn fetch_order_book(asset_id: &str) -> Option<OrderBookSummary> {
+    let book: serde_json::Value = http::shared()
+        .get_json(|client| {
+            client
+                .get(format!("{}/book", config::endpoints().polymarket_clob_api))
+                .query(&[("token_id", asset_id)])
+                .header("Accept", "application/json")
+                .timeout(Duration::from_secs(5))
+        })
+        .await
+        .ok()?;
 
     let bids = book.get("bids").and_then(|v| v.as_array())?;
     let asks = book.get("asks").and_then(|v| v.as_array())?;
@@ -213,7 +366,7 @@ pub async fn fetch_order_book(asset_id: &str) -> Option<crate::alerts::OrderBook
         }
     }
 
-    Some(crate::alerts::OrderBookSummary {
+    Some(OrderBookSummary {
         best_bid,
         best_ask,
         bid_depth_10pct: bid_depth,
@@ -224,27 +377,18 @@ pub async fn fetch_order_book(asset_id: &str) -> Option<crate::alerts::OrderBook
 }
 
 /// Fetch top holders for a Polymarket market (by condition ID)
-pub async fn fetch_top_holders(condition_id: &str) -> Option<crate::alerts::TopHoldersSummary> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()
-        .ok()?;
-
-    let response = client
-        .get("https://data-api.polymarket.com/top-holders")
-        .query(&[("market", condition_id)])
-        .header("Accept", "application/json")
-        .send()
+pub async fn fetch_top_holders(condition_id: &str) -> Option<TopHoldersSummary> {
+    let items: Vec<serde_json::Value> = http::shared()
+        .get_json(|client| {
+            client
+                .get(format!("{}/top-holders", config::endpoints().polymarket_data_api))
+                .query(&[("market", condition_id)])
+                .header("Accept", "application/json")
+                .timeout(Duration::from_secs(5))
+        })
         .await
         .ok()?;
 
-    if !response.status().is_success() {
-        return None;
-    }
-
-    let text = response.text().await.ok()?;
-    let items: Vec<serde_json::Value> = serde_json::from_str(&text).ok()?;
-
     if items.is_empty() {
         return None;
     }
@@ -268,7 +412,7 @@ pub async fn fetch_top_holders(condition_id: &str) -> Option<crate::alerts::TopH
             .and_then(|v| v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
             .unwrap_or(0.0);
 
-        holders.push(crate::alerts::TopHolder { wallet, shares, value });
+        holders.push(TopHolder { wallet, shares, value });
     }
 
     // Sum all holders for total
@@ -280,117 +424,106 @@ pub async fn fetch_top_holders(condition_id: &str) -> Option<crate::alerts::TopH
             .unwrap_or(0.0)
     }).sum();
 
-    Some(crate::alerts::TopHoldersSummary {
+    Some(TopHoldersSummary {
         top_holders: holders,
         total_shares: all_total,
     })
 }
 
-pub async fn fetch_recent_trades(min_value: Option<u64>) -> Result<Vec<Trade>, PolymarketError> {
-    let client = reqwest::Client::new();
+/// Page back through the Data API until reaching `cursor`.
+///
+/// Without a cursor only the newest page is returned. If the cursor can't be
+/// reached (the API ran out of rows or `MAX_TRADE_PAGES` was hit), the trades
+/// fetched so far are returned along with a `TradeGap`.
+pub async fn fetch_trades_since(
+    min_value: u64,
+    cursor: Option<&TradeCursor>,
+) -> Result<TradeBatch, PolymarketError> {
+    let mut trades: Vec<NormalizedTrade> = Vec::new();
+    let mut oldest_fetched: Option<i64> = None;
+    let mut pages = 0;
+
+    while pages < MAX_TRADE_PAGES {
+        let page = fetch_trades_page(min_value, pages * TRADES_PAGE_SIZE).await?;
+        pages += 1;
+
+        let exhausted = page.is_empty();
+        let mut overlapped = false;
+
+        for trade in page.into_iter().map(NormalizedTrade::from) {
+            if let Some(ts) = trade.unix_timestamp() {
+                oldest_fetched = Some(oldest_fetched.map_or(ts, |o| o.min(ts)));
+            }
 
-    // Use the Polymarket Data API to fetch recent activity
-    // This is a public endpoint that doesn't require authentication
-    let url = "https://data-api.polymarket.com/trades";
-
-    let mut request = client
-        .get(url)
-        .header("Accept", "application/json");
-
-    // Use server-side CASH filtering when threshold is set
-    // This lets the API pre-filter to only whale-sized trades
-    if let Some(threshold) = min_value {
-        request = request
-            .query(&[("limit", "500"), ("filterType", "CASH"), ("filterAmount", &threshold.to_string()), ("takerOnly", "true")]);
-    } else {
-        request = request.query(&[("limit", "100"), ("takerOnly", "true")]);
-    }
+            match cursor {
+                Some(c) => {
+                    overlapped |= c.overlaps(&trade);
+                    if c.is_new(&trade) && !trades.iter().any(|t| t.is_same_fill(&trade)) {
+                        trades.push(trade);
+                    }
+                }
+                None => trades.push(trade),
+            }
+        }
 
-    let response = request.send().await?;
+        let Some(c) = cursor else {
+            return Ok(TradeBatch { trades, gap: None });
+        };
 
-    if !response.status().is_success() {
-        return Err(PolymarketError::ParseError(format!(
-            "API returned status: {}",
-            response.status()
-        )));
-    }
+        if overlapped {
+            return Ok(TradeBatch { trades, gap: None });
+        }
 
-    let text = response.text().await?;
-
-    // Try to parse as array first (some endpoints return arrays directly)
-    if let Ok(items) = serde_json::from_str::<Vec<ActivityItem>>(&text) {
-        let trades = items
-            .into_iter()
-            .filter_map(|item| {
-                // Skip trades missing critical data
-                let market = item.market?;
-                let asset_id = item.asset?;
-                let side = item.side?;
-                let size = item.size?;
-                let price = item.price?;
-
-                Some(Trade {
-                    id: item.id.clone(),
-                    market,
-                    asset_id,
-                    side,
-                    size,
-                    price,
-                    timestamp: item
-                        .timestamp
-                        .and_then(|ts| {
-                            chrono::DateTime::from_timestamp(ts, 0)
-                                .map(|dt| dt.to_rfc3339())
-                        })
-                        .unwrap_or_else(|| format!("timestamp_error_{}", item.id)),
-                    // New API includes title and outcome directly
-                    market_title: item.title,
-                    outcome: item.outcome,
-                    wallet_id: item.proxy_wallet.or(item.user).or(item.maker),
-                })
-            })
-            .collect();
-        return Ok(trades);
+        // An empty page means the API has nothing older to give us
+        if exhausted {
+            return Ok(TradeBatch {
+                trades,
+                gap: Some(TradeGap {
+                    cursor_timestamp: c.timestamp,
+                    oldest_fetched,
+                    pages,
+                }),
+            });
+        }
     }
 
-    // Try wrapped response format
-    if let Ok(wrapped) = serde_json::from_str::<TradesResponse>(&text) {
-        let trades = wrapped
-            .data
-            .into_iter()
-            .filter_map(|item| {
-                // Skip trades missing critical data
-                let market = item.market?;
-                let asset_id = item.asset?;
-                let side = item.side?;
-                let size = item.size?;
-                let price = item.price?;
-
-                Some(Trade {
-                    id: item.id.clone(),
-                    market,
-                    asset_id,
-                    side,
-                    size,
-                    price,
-                    timestamp: item
-                        .timestamp
-                        .and_then(|ts| {
-                            chrono::DateTime::from_timestamp(ts, 0)
-                                .map(|dt| dt.to_rfc3339())
-                        })
-                        .unwrap_or_else(|| format!("timestamp_error_{}", item.id)),
-                    // New API includes title and outcome directly
-                    market_title: item.title,
-                    outcome: item.outcome,
-                    wallet_id: item.proxy_wallet.or(item.user).or(item.maker),
-                })
-            })
-            .collect();
-        return Ok(trades);
-    }
+    Ok(TradeBatch {
+        trades,
+        gap: cursor.map(|c| TradeGap {
+            cursor_timestamp: c.timestamp,
+            oldest_fetched,
+            pages,
+        }),
+    })
+}
 
-    // If parsing fails, return empty list rather than error
-    // This allows the tool to continue working even if Polymarket API format changes
-    Ok(Vec::new())
+/// Fetch one page of trades worth at least `min_value`, newest first
+pub async fn fetch_trades_page(min_value: u64, offset: u32) -> Result<Vec<Trade>, PolymarketError> {
+    // Use the Polymarket Data API to fetch recent activity
+    // This is a public endpoint that doesn't require authentication
+    // Server-side CASH filtering pre-filters to only whale-sized trades
+    let text = http::shared()
+        .get_text(|client| {
+            client
+                .get(format!("{}/trades", config::endpoints().polymarket_data_api))
+                .header("Accept", "application/json")
+                .query(&[
+                    ("limit", TRADES_PAGE_SIZE.to_string()),
+                    ("offset", offset.to_string()),
+                    ("filterType", "CASH".to_string()),
+                    ("filterAmount", min_value.to_string()),
+                    ("takerOnly", "true".to_string()),
+                ])
+        })
+        .await?;
+
+    // Try to parse as array first (some endpoints return arrays directly), then
+    // the wrapped format. A body that is neither is an error, not an empty page.
+    let items = match serde_json::from_str::<Vec<ActivityItem>>(&text) {
+        Ok(items) => items,
+        Err(_) => serde_json::from_str::<TradesResponse>(&text)
+            .map_err(|e| PolymarketError::Decode(e.to_string()))?
+            .data,
+    };
+    Ok(items.into_iter().filter_map(ActivityItem::into_trade).collect())
 }
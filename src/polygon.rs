@@ -0,0 +1,126 @@
+//! Polygon JSON-RPC: where and when a Polymarket proxy wallet got its first USDC.
+//!
+//! Transfers are searched for in the week of blocks up to the wallet's first
+//! Polymarket transaction, so the node must answer `eth_getLogs` over about
+//! 300k blocks, such as your own node or anvil forking Polygon; public
+//! endpoints with a smaller range cap return nothing here.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::config;
+use crate::http;
+
+/// ERC-20 `Transfer(address,address,uint256)`
+const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+/// Collateral tokens wallets are funded with: bridged USDC.e (Polymarket's collateral) and native USDC
+const USDC_TOKENS: [&str; 2] = [
+    "0x2791bca1f2de4661ed88a30c99a7a9449aa84174",
+    "0x3c499c542cef5e3811e1192ce70d8cc03d5c3359",
+];
+/// Blocks searched back from the first Polymarket transaction: a week at Polygon's ~2s blocks
+const LOOKBACK_BLOCKS: u64 = 7 * 24 * 60 * 30;
+/// Per call; lookups run in the background, so this only bounds a stuck node
+const RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransferLog {
+    topics: Vec<String>,
+    #[serde(default)]
+    block_number: Option<String>,
+    #[serde(default)]
+    log_index: Option<String>,
+    /// Not returned by every node
+    #[serde(default)]
+    block_timestamp: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Transaction {
+    #[serde(default)]
+    block_number: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Block {
+    timestamp: String,
+}
+
+/// Where a wallet's first USDC came from
+#[derive(Debug, Clone)]
+pub struct Funding {
+    pub funder: String,
+    /// Unix time of that transfer, when the node could say
+    pub funded_at: Option<i64>,
+}
+
+async fn rpc_call<T: serde::de::DeserializeOwned>(rpc: &str, method: &str, params: serde_json::Value) -> Option<T> {
+    let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    let response: RpcResponse<T> = http::shared()
+        .get_json(|client| client.post(rpc).json(&body).timeout(RPC_TIMEOUT))
+        .await
+        .ok()?;
+    response.result
+}
+
+fn hex_u64(value: Option<&str>) -> u64 {
+    value
+        .and_then(|v| u64::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+        .unwrap_or(u64::MAX)
+}
+
+/// Address in a 32-byte log topic
+fn topic_address(topic: &str) -> Option<String> {
+    let hex = topic.trim_start_matches("0x");
+    (hex.len() == 64).then(|| format!("0x{}", &hex[24..]).to_lowercase())
+}
+
+/// Sender and time of the wallet's earliest incoming USDC transfer in the
+/// `LOOKBACK_BLOCKS` up to `first_tx` (its first Polymarket transaction), or
+/// `None` when no RPC endpoint is configured or the node can't say
+pub async fn fetch_funding(wallet_id: &str, first_tx: &str) -> Option<Funding> {
+    let rpc = config::endpoints().polygon_rpc.as_deref()?;
+    let wallet_hex = wallet_id.trim_start_matches("0x").to_lowercase();
+    if wallet_hex.len() != 40 {
+        return None;
+    }
+    let tx: Transaction = rpc_call(rpc, "eth_getTransactionByHash", json!([first_tx])).await?;
+    let to_block = hex_u64(tx.block_number.as_deref());
+    if to_block == u64::MAX {
+        return None;
+    }
+    let filter = json!([{
+        "fromBlock": format!("{:#x}", to_block.saturating_sub(LOOKBACK_BLOCKS)),
+        "toBlock": format!("{:#x}", to_block),
+        "address": USDC_TOKENS,
+        "topics": [TRANSFER_TOPIC, null, format!("0x{:0>64}", wallet_hex)],
+    }]);
+
+    let logs: Vec<TransferLog> = rpc_call(rpc, "eth_getLogs", filter).await?;
+    let first = logs
+        .into_iter()
+        .min_by_key(|log| (hex_u64(log.block_number.as_deref()), hex_u64(log.log_index.as_deref())))?;
+    let funder = first.topics.get(1).and_then(|t| topic_address(t))?;
+
+    let timestamp = match first.block_timestamp {
+        Some(ts) => Some(ts),
+        None => match first.block_number {
+            Some(number) => rpc_call::<Block>(rpc, "eth_getBlockByNumber", json!([number, false]))
+                .await
+                .map(|block| block.timestamp),
+            None => None,
+        },
+    };
+    let funded_at = timestamp.map(|ts| hex_u64(Some(&ts))).filter(|&ts| ts != u64::MAX).map(|ts| ts as i64);
+
+    Some(Funding { funder, funded_at })
+}
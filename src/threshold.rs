@@ -0,0 +1,467 @@
+//! Alert thresholds that vary by market, category and platform, and named
+//! size bands for alerts well over their threshold.
+//!
+//! The most specific override wins: market, then subcategory, then
+//! category, then platform, then the watch threshold (`--threshold`).
+//!
+//! Relative triggers alert on trades under their threshold that are large
+//! for their market: a share of its 24h volume, liquidity, open interest or
+//! order book depth.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::{MarketContext, OrderBookSummary};
+use crate::categories::CategoryRegistry;
+use crate::platforms::{NormalizedTrade, Platform};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThresholdPolicy {
+    /// By platform: "polymarket" or "kalshi"
+    pub platforms: BTreeMap<String, u64>,
+    /// By category ("weather") or subcategory ("politics:us_elections")
+    pub categories: BTreeMap<String, u64>,
+    /// By Polymarket condition ID or Kalshi ticker
+    pub markets: BTreeMap<String, u64>,
+    /// Size bands, in any order. An alert takes the largest band it reaches.
+    pub bands: Vec<SizeBand>,
+    /// Triggers relative to market size, for trades under their threshold
+    pub relative: RelativeTriggers,
+}
+
+/// Shares of the market (0.2 = 20%) a trade under its threshold must reach
+/// to alert anyway. Unset triggers are off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelativeTriggers {
+    /// Smallest trade (USD) checked against the triggers, since each one
+    /// costs market lookups
+    pub min_value: u64,
+    pub volume_24h: Option<f64>,
+    pub liquidity: Option<f64>,
+    pub open_interest: Option<f64>,
+    /// Share of the order book within 10% of the best price, on the side the
+    /// trade takes
+    pub depth_10pct: Option<f64>,
+}
+
+impl Default for RelativeTriggers {
+    fn default() -> Self {
+        Self {
+            min_value: 5000,
+            volume_24h: None,
+            liquidity: None,
+            open_interest: None,
+            depth_10pct: None,
+        }
+    }
+}
+
+impl RelativeTriggers {
+    fn minimums(&self) -> [(&'static str, Option<f64>); 4] {
+        [
+            ("volume_24h", self.volume_24h),
+            ("liquidity", self.liquidity),
+            ("open_interest", self.open_interest),
+            ("depth_10pct", self.depth_10pct),
+        ]
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.minimums().iter().any(|(_, min)| min.is_some())
+    }
+
+    /// Whether checking the trade needs its order book
+    pub fn needs_order_book(&self) -> bool {
+        self.depth_10pct.is_some()
+    }
+
+    /// Names of the triggers the ratios reach
+    pub fn fired(&self, ratios: &LiquidityRatios) -> Vec<&'static str> {
+        self.minimums()
+            .into_iter()
+            .zip(ratios.values())
+            .filter_map(|((name, min), (_, ratio))| (ratio? >= min?).then_some(name))
+            .collect()
+    }
+
+    /// Configured triggers as `name=share` pairs
+    pub fn describe(&self) -> String {
+        self.minimums()
+            .into_iter()
+            .filter_map(|(name, min)| Some(format!("{}={}", name, min?)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A trade's value as a share of its market's size, where known. Kalshi
+/// volume and open interest are in contracts, so its trades are compared by
+/// contract count there.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LiquidityRatios {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume_24h: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub liquidity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_interest: Option<f64>,
+    /// Against the depth left on the side the trade took, measured after it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth_10pct: Option<f64>,
+}
+
+impl LiquidityRatios {
+    pub fn measure(trade: &NormalizedTrade, ctx: Option<&MarketContext>, book: Option<&OrderBookSummary>) -> Self {
+        let value = trade.value();
+        let share = |amount: f64, of: f64| (of > 0.0).then(|| amount / of);
+        let kalshi = trade.platform == Platform::Kalshi;
+        // Sells and Kalshi NO orders take the bids; buys and YES orders take the asks
+        let takes_bids = trade.side.eq_ignore_ascii_case("SELL") || trade.side.eq_ignore_ascii_case("NO");
+
+        Self {
+            volume_24h: ctx.and_then(|c| if kalshi { share(trade.size, c.volume_24h) } else { share(value, c.volume_24h) }),
+            // Kalshi reports liquidity in cents
+            liquidity: ctx.and_then(|c| share(value, if kalshi { c.liquidity / 100.0 } else { c.liquidity })),
+            open_interest: ctx
+                .and_then(|c| if kalshi { share(trade.size, c.open_interest) } else { share(value, c.open_interest) }),
+            depth_10pct: book
+                .and_then(|b| share(value, if takes_bids { b.bid_depth_10pct } else { b.ask_depth_10pct })),
+        }
+    }
+
+    fn values(&self) -> [(&'static str, Option<f64>); 4] {
+        [
+            ("24h volume", self.volume_24h),
+            ("liquidity", self.liquidity),
+            ("open interest", self.open_interest),
+            ("10% depth", self.depth_10pct),
+        ]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values().iter().all(|(_, ratio)| ratio.is_none())
+    }
+
+    /// e.g. "32% of 24h volume, 60% of 10% depth"
+    pub fn describe(&self) -> String {
+        self.values()
+            .into_iter()
+            .filter_map(|(label, ratio)| Some(format!("{:.0}% of {}", ratio? * 100.0, label)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A named size class, such as "mega-whale". Alerts in a band get
+/// `<NAME>_ENTRY`/`<NAME>_EXIT` as their alert type and the band's color.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizeBand {
+    pub name: String,
+    /// Trade value (USD) the band starts at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_value: Option<u64>,
+    /// Multiple of the trade's threshold the band starts at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_multiple: Option<f64>,
+    /// Terminal color of the alert header (e.g. "magenta", "bright red")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+impl SizeBand {
+    /// Smallest value in the band for a trade with this threshold
+    fn start(&self, threshold: u64) -> f64 {
+        let by_value = self.min_value.unwrap_or(0) as f64;
+        let by_multiple = self.min_multiple.unwrap_or(0.0) * threshold as f64;
+        by_value.max(by_multiple)
+    }
+
+    /// Alert type prefix: "mega-whale" becomes "MEGA_WHALE"
+    pub fn alert_prefix(&self) -> String {
+        self.name
+            .trim()
+            .to_uppercase()
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+    }
+}
+
+/// The threshold a trade was held to, and the setting it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedThreshold {
+    pub value: u64,
+    /// e.g. "default", "platform kalshi", "category weather"
+    pub source: String,
+}
+
+impl ThresholdPolicy {
+    fn platform_threshold(&self, base: u64, platform: Platform) -> u64 {
+        self.platforms
+            .get(&platform.name().to_lowercase())
+            .copied()
+            .unwrap_or(base)
+    }
+
+    /// Lowest threshold any trade could have, relative triggers included,
+    /// which is what APIs that filter by size must fetch down to
+    pub fn lowest(&self, base: u64) -> u64 {
+        self.platforms
+            .values()
+            .chain(self.categories.values())
+            .chain(self.markets.values())
+            .chain(self.relative_floor().as_ref())
+            .fold(base, |lowest, &t| lowest.min(t))
+    }
+
+    /// Smallest trade checked against relative triggers, when any are set
+    pub fn relative_floor(&self) -> Option<u64> {
+        self.relative.is_enabled().then_some(self.relative.min_value)
+    }
+
+    /// Lowest threshold a trade on this market could have before its category
+    /// is known. Orders under it can't alert on their own.
+    pub fn floor(&self, base: u64, platform: Platform, market_id: &str) -> u64 {
+        if let Some(&t) = self.markets.get(market_id) {
+            return t;
+        }
+        self.categories
+            .values()
+            .fold(self.platform_threshold(base, platform), |lowest, &t| lowest.min(t))
+    }
+
+    /// Threshold of a trade on this market that no category override applies
+    /// to. Orders under it may still be rejected once the category is known.
+    pub fn uncategorized(&self, base: u64, platform: Platform, market_id: &str) -> u64 {
+        self.markets
+            .get(market_id)
+            .copied()
+            .unwrap_or_else(|| self.platform_threshold(base, platform))
+    }
+
+    /// The threshold for a trade whose title and category are known. Where
+    /// several categories match, the lowest of their thresholds applies.
+    pub fn resolve(&self, base: u64, trade: &NormalizedTrade, registry: &CategoryRegistry) -> AppliedThreshold {
+        if let Some(&value) = self.markets.get(&trade.market_id) {
+            return AppliedThreshold { value, source: format!("market {}", trade.market_id) };
+        }
+
+        let title = trade.market_title.as_deref().unwrap_or_default();
+        let matches = |key: &str| {
+            if key.contains(':') {
+                registry.matches_selection(title, &[key.to_string()]).is_some()
+            } else {
+                registry.matches_selection(title, &[format!("{}:all", key)]).is_some()
+                    || trade
+                        .category
+                        .as_deref()
+                        .is_some_and(|native| registry.matches_native_category(native, &[key.to_string()]))
+            }
+        };
+        let lowest_match = |subcategories: bool| {
+            self.categories
+                .iter()
+                .filter(|(key, _)| key.contains(':') == subcategories && matches(key))
+                .min_by_key(|(_, &t)| t)
+        };
+        if let Some((key, &value)) = lowest_match(true).or_else(|| lowest_match(false)) {
+            return AppliedThreshold { value, source: format!("category {}", key) };
+        }
+
+        let platform = trade.platform.name().to_lowercase();
+        match self.platforms.get(&platform) {
+            Some(&value) => AppliedThreshold { value, source: format!("platform {}", platform) },
+            None => AppliedThreshold { value: base, source: "default".into() },
+        }
+    }
+
+    /// Overrides as `key=amount` pairs (the setup wizard's input format)
+    pub fn describe_overrides(&self) -> String {
+        self.platforms
+            .iter()
+            .chain(&self.categories)
+            .chain(&self.markets)
+            .map(|(key, t)| format!("{}={}", key, t))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Bands as `name=start` pairs, smallest first
+    pub fn describe_bands(&self) -> String {
+        let mut bands: Vec<&SizeBand> = self.bands.iter().collect();
+        bands.sort_by(|a, b| a.start(1).total_cmp(&b.start(1)));
+        bands
+            .iter()
+            .map(|b| match (b.min_value, b.min_multiple) {
+                (Some(v), Some(m)) => format!("{}={} and {}x threshold", b.name, v, m),
+                (None, Some(m)) => format!("{}={}x threshold", b.name, m),
+                (v, None) => format!("{}={}", b.name, v.unwrap_or(0)),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Set overrides from `key=amount` pairs. Keys are platforms, then known
+    /// categories and subcategories; anything else is taken as a market ID.
+    /// Returns the entries that couldn't be read.
+    pub fn set_overrides(&mut self, input: &str) -> Vec<String> {
+        self.platforms.clear();
+        self.categories.clear();
+        self.markets.clear();
+
+        let mut invalid = Vec::new();
+        for (key, amount) in parse_pairs(input, &mut invalid) {
+            let Ok(amount) = amount.parse::<u64>() else {
+                invalid.push(format!("{}={}", key, amount));
+                continue;
+            };
+            let lower = key.to_lowercase();
+            if matches!(lower.as_str(), "polymarket" | "kalshi") {
+                self.platforms.insert(lower, amount);
+            } else if is_category(&lower) {
+                self.categories.insert(lower, amount);
+            } else {
+                self.markets.insert(key, amount);
+            }
+        }
+        invalid
+    }
+
+    /// Set bands from `name=min_value` pairs, keeping each existing band's
+    /// color. Returns the entries that couldn't be read.
+    pub fn set_bands(&mut self, input: &str) -> Vec<String> {
+        let mut invalid = Vec::new();
+        let mut bands = Vec::new();
+        for (name, amount) in parse_pairs(input, &mut invalid) {
+            let Ok(min_value) = amount.parse::<u64>() else {
+                invalid.push(format!("{}={}", name, amount));
+                continue;
+            };
+            let color = self.bands.iter().find(|b| b.name == name).and_then(|b| b.color.clone());
+            bands.push(SizeBand { name, min_value: Some(min_value), min_multiple: None, color });
+        }
+        self.bands = bands;
+        invalid
+    }
+
+    /// The largest band a trade of `value` reaches, given its threshold
+    pub fn band(&self, value: f64, threshold: u64) -> Option<&SizeBand> {
+        self.bands
+            .iter()
+            .filter(|b| value >= b.start(threshold))
+            .max_by(|a, b| a.start(threshold).total_cmp(&b.start(threshold)))
+    }
+}
+
+/// `key=value` pairs separated by commas, with `$` and `_` stripped from values
+fn parse_pairs(input: &str, invalid: &mut Vec<String>) -> Vec<(String, String)> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| match entry.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Some((key.trim().to_string(), value.trim().replace(['$', '_'], "")))
+            }
+            _ => {
+                invalid.push(entry.to_string());
+                None
+            }
+        })
+        .collect()
+}
+
+fn is_category(key: &str) -> bool {
+    CategoryRegistry::all_categories().iter().any(|(category, _)| {
+        *category == key || CategoryRegistry::subcategories(category).iter().any(|(sub, _)| *sub == key)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(platform: Platform, market_id: &str, title: &str) -> NormalizedTrade {
+        NormalizedTrade {
+            platform,
+            market_id: market_id.into(),
+            asset_id: market_id.into(),
+            market_title: Some(title.into()),
+            size: 1.0,
+            ..NormalizedTrade::test("t")
+        }
+    }
+
+    #[test]
+    fn most_specific_override_wins_and_bands_scale() {
+        let policy: ThresholdPolicy = serde_json::from_str(
+            r#"{
+                "platforms": {"kalshi": 10000},
+                "categories": {"politics": 100000, "politics:us_elections": 250000, "weather": 5000},
+                "markets": {"KXHIGHNY-26OCT01": 2000},
+                "bands": [
+                    {"name": "mega-whale", "min_multiple": 10},
+                    {"name": "whale", "min_value": 0}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let registry = CategoryRegistry::new();
+        let resolve = |t: &NormalizedTrade| policy.resolve(25_000, t, &registry);
+
+        assert_eq!(resolve(&trade(Platform::Kalshi, "KXHIGHNY-26OCT01", "Highest temperature in NYC")).value, 2_000);
+        let applied = resolve(&trade(Platform::Polymarket, "0x1", "Who will win the presidential election?"));
+        assert_eq!((applied.value, applied.source.as_str()), (250_000, "category politics:us_elections"));
+        assert_eq!(resolve(&trade(Platform::Polymarket, "0x2", "Will the Senate pass the bill?")).value, 100_000);
+        assert_eq!(resolve(&trade(Platform::Kalshi, "KXOTHER", "Something else")).value, 10_000);
+        assert_eq!(resolve(&trade(Platform::Polymarket, "0x3", "Something else")).source, "default");
+
+        assert_eq!(policy.floor(25_000, Platform::Polymarket, "0x3"), 5_000);
+        assert_eq!(policy.lowest(25_000), 2_000);
+
+        assert_eq!(policy.band(60_000.0, 5_000).unwrap().alert_prefix(), "MEGA_WHALE");
+        assert_eq!(policy.band(60_000.0, 25_000).unwrap().alert_prefix(), "WHALE");
+    }
+
+    #[test]
+    fn relative_triggers_compare_like_units() {
+        let ctx = MarketContext {
+            yes_price: 0.5,
+            no_price: 0.5,
+            spread: 0.01,
+            volume_24h: 100_000.0,
+            open_interest: 0.0,
+            price_change_24h: 0.0,
+            liquidity: 4_000_000.0,
+            tags: vec![],
+        };
+        let book = OrderBookSummary {
+            best_bid: 0.49,
+            best_ask: 0.51,
+            bid_depth_10pct: 10_000.0,
+            ask_depth_10pct: 40_000.0,
+            bid_levels: 3,
+            ask_levels: 3,
+        };
+        let triggers: RelativeTriggers = serde_json::from_str(r#"{"volume_24h": 0.2, "depth_10pct": 0.5}"#).unwrap();
+
+        // $10k buy on Polymarket: 10% of volume, 25% of the asks it took
+        let mut t = trade(Platform::Polymarket, "0x1", "t");
+        t.size = 20_000.0;
+        let ratios = LiquidityRatios::measure(&t, Some(&ctx), Some(&book));
+        assert_eq!((ratios.volume_24h, ratios.depth_10pct, ratios.open_interest), (Some(0.1), Some(0.25), None));
+        assert!(triggers.fired(&ratios).is_empty());
+
+        // Selling takes the thinner bids
+        t.side = "SELL".into();
+        assert_eq!(triggers.fired(&LiquidityRatios::measure(&t, Some(&ctx), Some(&book))), ["depth_10pct"]);
+
+        // Kalshi: volume in contracts, liquidity in cents
+        let mut k = trade(Platform::Kalshi, "KX", "t");
+        k.size = 30_000.0;
+        let ratios = LiquidityRatios::measure(&k, Some(&ctx), None);
+        assert_eq!((ratios.volume_24h, ratios.liquidity), (Some(0.3), Some(0.375)));
+        assert_eq!(triggers.fired(&ratios), ["volume_24h"]);
+    }
+}
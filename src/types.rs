@@ -1,10 +1,12 @@
 // Shared types and utilities across modules
 
 use std::collections::{HashMap, HashSet};
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use tokio::time::Instant;
 
 use rusqlite::{Connection, params};
 
+use crate::capture;
 use crate::db;
 
 // ─── Wallet Memory (SQLite-backed with in-memory hot cache) ─────────
@@ -34,10 +36,7 @@ impl WalletTracker {
 
     /// Record a transaction into wallet_memory table and in-memory tracker
     pub fn record_transaction(&mut self, wallet_id: &str, value: f64) {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let timestamp = capture::now().timestamp() as u64;
 
         // In-memory tracker for real-time 1h/24h stats
         self.transactions
@@ -48,7 +47,8 @@ impl WalletTracker {
         self.cleanup_old_transactions();
     }
 
-    /// Record a transaction into the SQLite wallet_memory table
+    /// Record a transaction seen at `seen_at` (Unix seconds) into the SQLite wallet_memory table
+    #[allow(clippy::too_many_arguments)]
     pub fn record_to_db(
         &mut self,
         conn: &Connection,
@@ -60,18 +60,15 @@ impl WalletTracker {
         value: f64,
         price: f64,
         platform: &str,
+        seen_at: i64,
     ) {
         let hash = db::wallet_hash(wallet_id);
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
 
         let result = conn.execute(
             "INSERT OR REPLACE INTO wallet_memory
              (wallet_hash, wallet_id, market_title, market_id, outcome, action, value, price, platform, seen_at)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            params![hash, wallet_id, market_title, market_id, outcome, action, value, price, platform, now],
+            params![hash, wallet_id, market_title, market_id, outcome, action, value, price, platform, seen_at],
         );
 
         if let Err(e) = result {
@@ -90,12 +87,12 @@ impl WalletTracker {
         let result = conn.prepare(
             "SELECT wallet_id, market_title, market_id, outcome, action, value, price, platform, seen_at
              FROM wallet_memory
-             WHERE wallet_hash = ?1 AND seen_at > (strftime('%s', 'now') - 43200)
+             WHERE wallet_hash = ?1 AND seen_at > ?2 - 43200
              ORDER BY seen_at DESC"
         );
 
         if let Ok(mut stmt) = result {
-            let rows = stmt.query_map(params![hash], |row| {
+            let rows = stmt.query_map(params![hash, capture::now().timestamp()], |row| {
                 Ok(WalletMemoryEntry {
                     wallet_id: row.get(0)?,
                     market_title: row.get(1)?,
@@ -136,10 +133,7 @@ impl WalletTracker {
             return None;
         }
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
+        let now = capture::now().timestamp();
 
         let total_volume: f64 = history.iter().map(|e| e.value).sum();
         let total_txns = history.len();
@@ -200,11 +194,11 @@ impl WalletTracker {
 
         let result = conn.prepare(
             "SELECT DISTINCT wallet_hash FROM wallet_memory
-             WHERE seen_at > (strftime('%s', 'now') - 43200)"
+             WHERE seen_at > ?1 - 43200"
         );
 
         if let Ok(mut stmt) = result {
-            let rows = stmt.query_map([], |row| {
+            let rows = stmt.query_map(params![capture::now().timestamp()], |row| {
                 let hash: String = row.get(0)?;
                 Ok(hash)
             });
@@ -220,45 +214,44 @@ impl WalletTracker {
         self.last_cache_refresh = Instant::now();
     }
 
-    /// Get real-time activity stats (from in-memory tracker)
-    pub fn get_activity(&self, wallet_id: &str) -> WalletActivity {
-        if let Some(txns) = self.transactions.get(wallet_id) {
-            let current_time = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-
-            let hour_txns: Vec<&(f64, u64)> = txns
-                .iter()
-                .filter(|(_, ts)| current_time - ts < 3600)
-                .collect();
-
-            let day_txns: Vec<&(f64, u64)> = txns
-                .iter()
-                .filter(|(_, ts)| current_time - ts < 86400)
-                .collect();
-
-            let total_value_hour: f64 = hour_txns.iter().map(|(v, _)| v).sum();
-            let total_value_day: f64 = day_txns.iter().map(|(v, _)| v).sum();
-
-            WalletActivity {
-                transactions_last_hour: hour_txns.len(),
-                transactions_last_day: day_txns.len(),
-                total_value_hour,
-                total_value_day,
-                is_repeat_actor: hour_txns.len() > 1,
-                is_heavy_actor: day_txns.len() >= 5,
-            }
-        } else {
-            WalletActivity::default()
+    /// Real-time activity stats (from the in-memory tracker) counting a
+    /// transaction of `value` now, without recording it
+    pub fn activity_with(&self, wallet_id: &str, value: f64) -> WalletActivity {
+        let current_time = capture::now().timestamp() as u64;
+        let txns: Vec<(f64, u64)> = self
+            .transactions
+            .get(wallet_id)
+            .into_iter()
+            .flatten()
+            .copied()
+            .chain([(value, current_time)])
+            .collect();
+
+        let hour_txns: Vec<&(f64, u64)> = txns
+            .iter()
+            .filter(|(_, ts)| current_time - ts < 3600)
+            .collect();
+
+        let day_txns: Vec<&(f64, u64)> = txns
+            .iter()
+            .filter(|(_, ts)| current_time - ts < 86400)
+            .collect();
+
+        let total_value_hour: f64 = hour_txns.iter().map(|(v, _)| v).sum();
+        let total_value_day: f64 = day_txns.iter().map(|(v, _)| v).sum();
+
+        WalletActivity {
+            transactions_last_hour: hour_txns.len(),
+            transactions_last_day: day_txns.len(),
+            total_value_hour,
+            total_value_day,
+            is_repeat_actor: hour_txns.len() > 1,
+            is_heavy_actor: day_txns.len() >= 5,
         }
     }
 
     fn cleanup_old_transactions(&mut self) {
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let current_time = capture::now().timestamp() as u64;
 
         for txns in self.transactions.values_mut() {
             txns.retain(|(_, ts)| current_time - ts < 86400);
@@ -295,6 +288,7 @@ impl Default for WalletActivity {
 
 #[derive(Debug, Clone)]
 pub struct WalletMemoryEntry {
+    #[allow(dead_code)]
     pub wallet_id: String,
     pub market_title: Option<String>,
     pub market_id: Option<String>,
@@ -302,6 +296,7 @@ pub struct WalletMemoryEntry {
     pub action: Option<String>,
     pub value: f64,
     pub price: f64,
+    #[allow(dead_code)]
     pub platform: String,
     pub seen_at: i64,
 }
@@ -1,10 +1,24 @@
 use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
+use tokio::sync::{watch, Mutex};
+use tokio::time::{self, Instant};
+
+use crate::config;
+use crate::http;
+use crate::polygon;
 
 const PROFILE_TTL: Duration = Duration::from_secs(30 * 60); // 30 min cache
 const LEADERBOARD_TTL: Duration = Duration::from_secs(60 * 60); // 1 hour cache
+/// Activity entries fetched per wallet; older wallets' trade counts stop here
+pub const ACTIVITY_LIMIT: u32 = 500;
+/// Longest a profile fetch waits on a wallet's funding lookup; a slower
+/// lookup keeps running, fills in later profiles and has the alert sink
+/// re-check the alert's fresh wallet flag
+const FUNDING_WAIT: Duration = Duration::from_secs(2);
+/// How long a found funding source is remembered (it never changes)
+const FUNDING_TTL: Duration = Duration::from_secs(24 * 60 * 60);
 
 /// Whale intelligence data fetched from Polymarket Data API
 #[derive(Debug, Clone, Serialize)]
@@ -16,12 +30,72 @@ pub struct WhaleProfile {
     pub leaderboard_profit: Option<f64>,
     pub win_rate: Option<f64>,
     pub markets_traded: Option<u32>,
+    /// Sender of the wallet's first USDC, when a Polygon RPC endpoint is set
+    pub funded_by: Option<String>,
+    /// Unix time of the wallet's first Polymarket activity
+    pub first_seen_at: Option<i64>,
+    /// Trades in the wallet's activity history (at most `ACTIVITY_LIMIT`)
+    pub lifetime_trades: Option<u32>,
+    /// Unix time of the wallet's first USDC transfer, when a Polygon RPC
+    /// endpoint is set
+    pub funded_at: Option<i64>,
+}
+
+impl WhaleProfile {
+    /// Hours from the wallet's first activity, or its funding when a lookup
+    /// found an earlier one, to `ts`
+    pub fn age_hours(&self, ts: i64) -> Option<f64> {
+        let born = match (self.first_seen_at, self.funded_at) {
+            (Some(seen), Some(funded)) => Some(seen.min(funded)),
+            (seen, funded) => seen.or(funded),
+        };
+        born.map(|born| (ts - born).max(0) as f64 / 3600.0)
+    }
+
+    /// Whether `age_hours` counts from the wallet's funding
+    pub fn aged_from_funding(&self) -> bool {
+        self.funded_at.is_some_and(|funded| self.first_seen_at.is_none_or(|seen| funded <= seen))
+    }
 }
 
 /// Cached whale profiles + leaderboard
 pub struct WhaleProfileCache {
     profiles: HashMap<String, (WhaleProfile, Instant)>,
     leaderboard: Option<(Vec<LeaderboardEntry>, Instant)>,
+    /// Funding lookups per wallet, running or finished
+    funding: HashMap<String, (watch::Receiver<FundingLookup>, Instant)>,
+}
+
+/// A wallet's funding lookup, run in its own task so enrichment never waits
+/// on the Polygon node for long
+#[derive(Debug, Clone)]
+enum FundingLookup {
+    Running,
+    Done(Option<polygon::Funding>),
+}
+
+/// A funding lookup still running when a wallet's profile was built
+pub struct PendingFunding(watch::Receiver<FundingLookup>);
+
+impl PendingFunding {
+    /// The wallet's funding source once the lookup finishes, if it found one
+    pub async fn finished(mut self) -> Option<polygon::Funding> {
+        let lookup = self.0.wait_for(|l| matches!(l, FundingLookup::Done(_))).await.ok()?;
+        match &*lookup {
+            FundingLookup::Done(funding) => funding.clone(),
+            FundingLookup::Running => None,
+        }
+    }
+}
+
+/// A wallet's first activity on Polymarket and its trade count
+#[derive(Debug, Clone)]
+pub struct History {
+    /// Unix time of the first activity
+    pub first_seen: i64,
+    /// Transaction of the first activity, where funding lookups start
+    pub first_tx: Option<String>,
+    pub trades: u32,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -41,9 +115,9 @@ struct LeaderboardEntry {
 #[derive(Debug, Deserialize)]
 struct PositionEntry {
     #[serde(rename = "currentValue", default)]
-    current_value: Option<f64>,
-    #[serde(default)]
-    size: Option<f64>,
+    _current_value: Option<f64>,
+    #[serde(rename = "size", default)]
+    _size: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -54,6 +128,16 @@ struct ClosedPositionEntry {
     cash_paid: Option<f64>,
 }
 
+#[derive(Debug, Deserialize)]
+struct ActivityEntry {
+    #[serde(default)]
+    timestamp: Option<i64>,
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    #[serde(rename = "transactionHash", default)]
+    transaction_hash: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct ValueResponse {
     #[serde(rename = "value", default)]
@@ -65,6 +149,7 @@ impl WhaleProfileCache {
         Self {
             profiles: HashMap::new(),
             leaderboard: None,
+            funding: HashMap::new(),
         }
     }
 
@@ -105,70 +190,91 @@ impl WhaleProfileCache {
         None
     }
 
-    /// Refresh leaderboard cache if stale
-    pub async fn refresh_leaderboard_if_needed(&mut self) {
-        let needs_refresh = match &self.leaderboard {
+    /// Whether the cached leaderboard is missing or expired
+    fn leaderboard_stale(&self) -> bool {
+        match &self.leaderboard {
             None => true,
             Some((_, fetched_at)) => fetched_at.elapsed() >= LEADERBOARD_TTL,
-        };
+        }
+    }
 
-        if needs_refresh {
-            if let Some(entries) = fetch_leaderboard().await {
-                self.leaderboard = Some((entries, Instant::now()));
-            }
+    /// Store a freshly fetched leaderboard
+    fn set_leaderboard(&mut self, entries: Vec<LeaderboardEntry>) {
+        self.leaderboard = Some((entries, Instant::now()));
+    }
+
+    /// The wallet's funding lookup, starting one from `first_tx` when none is
+    /// cached. `None` without a Polygon RPC endpoint.
+    fn funding_lookup(&mut self, wallet_id: &str, first_tx: &str) -> Option<watch::Receiver<FundingLookup>> {
+        config::endpoints().polygon_rpc.as_ref()?;
+        if let Some((rx, _)) = self.funding.get(wallet_id) {
+            return Some(rx.clone());
         }
+        let (tx, rx) = watch::channel(FundingLookup::Running);
+        let (wallet, first_tx) = (wallet_id.to_string(), first_tx.to_string());
+        tokio::spawn(async move {
+            let funding = polygon::fetch_funding(&wallet, &first_tx).await;
+            let _ = tx.send(FundingLookup::Done(funding));
+        });
+        self.funding.insert(wallet_id.to_string(), (rx.clone(), Instant::now()));
+        Some(rx)
+    }
+
+    /// The wallet's funding source, if a lookup found one
+    fn funding_found(&self, wallet_id: &str) -> Option<polygon::Funding> {
+        match &*self.funding.get(wallet_id)?.0.borrow() {
+            FundingLookup::Done(funding) => funding.clone(),
+            FundingLookup::Running => None,
+        }
+    }
+
+    /// The wallet's funding lookup, if it is still running
+    pub fn pending_funding(&self, wallet_id: &str) -> Option<PendingFunding> {
+        let (rx, _) = self.funding.get(wallet_id)?;
+        let running = matches!(*rx.borrow(), FundingLookup::Running);
+        running.then(|| PendingFunding(rx.clone()))
     }
 
     /// Clean expired entries
     pub fn prune(&mut self) {
         self.profiles.retain(|_, (_, fetched_at)| fetched_at.elapsed() < PROFILE_TTL);
+        // Misses are retried with the next profile; lookups whose task died are dropped
+        self.funding.retain(|_, (rx, started)| match &*rx.borrow() {
+            FundingLookup::Done(Some(_)) => started.elapsed() < FUNDING_TTL,
+            FundingLookup::Done(None) => started.elapsed() < PROFILE_TTL,
+            FundingLookup::Running => rx.has_changed().is_ok(),
+        });
     }
 }
 
 /// Fetch trader leaderboard (top 500)
 async fn fetch_leaderboard() -> Option<Vec<LeaderboardEntry>> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(10))
-        .build()
-        .ok()?;
-
-    let response = client
-        .get("https://data-api.polymarket.com/leaderboard")
-        .query(&[("limit", "500")])
-        .header("Accept", "application/json")
-        .send()
+    let text = http::shared()
+        .get_text(|client| {
+            client
+                .get(format!("{}/leaderboard", config::endpoints().polymarket_data_api))
+                .query(&[("limit", "500")])
+                .header("Accept", "application/json")
+                .timeout(Duration::from_secs(10))
+        })
         .await
         .ok()?;
-
-    if !response.status().is_success() {
-        return None;
-    }
-
-    let text = response.text().await.ok()?;
     serde_json::from_str(&text).ok()
 }
 
 /// Fetch portfolio total value for a wallet
 async fn fetch_portfolio_value(wallet_id: &str) -> Option<f64> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()
-        .ok()?;
-
-    let response = client
-        .get("https://data-api.polymarket.com/value")
-        .query(&[("user", wallet_id)])
-        .header("Accept", "application/json")
-        .send()
+    let text = http::shared()
+        .get_text(|client| {
+            client
+                .get(format!("{}/value", config::endpoints().polymarket_data_api))
+                .query(&[("user", wallet_id)])
+                .header("Accept", "application/json")
+                .timeout(Duration::from_secs(5))
+        })
         .await
         .ok()?;
 
-    if !response.status().is_success() {
-        return None;
-    }
-
-    let text = response.text().await.ok()?;
-
     // The response might be a direct number or an object
     if let Ok(val) = text.trim().parse::<f64>() {
         return Some(val);
@@ -187,48 +293,32 @@ async fn fetch_portfolio_value(wallet_id: &str) -> Option<f64> {
 
 /// Fetch current open positions count
 async fn fetch_positions_count(wallet_id: &str) -> Option<u32> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()
-        .ok()?;
-
-    let response = client
-        .get("https://data-api.polymarket.com/positions")
-        .query(&[("user", wallet_id), ("limit", "100")])
-        .header("Accept", "application/json")
-        .send()
+    let text = http::shared()
+        .get_text(|client| {
+            client
+                .get(format!("{}/positions", config::endpoints().polymarket_data_api))
+                .query(&[("user", wallet_id), ("limit", "100")])
+                .header("Accept", "application/json")
+                .timeout(Duration::from_secs(5))
+        })
         .await
         .ok()?;
-
-    if !response.status().is_success() {
-        return None;
-    }
-
-    let text = response.text().await.ok()?;
     let positions: Vec<PositionEntry> = serde_json::from_str(&text).ok()?;
     Some(positions.len() as u32)
 }
 
 /// Compute win rate from closed positions
 async fn fetch_win_rate(wallet_id: &str) -> Option<(f64, u32)> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()
-        .ok()?;
-
-    let response = client
-        .get("https://data-api.polymarket.com/closed-positions")
-        .query(&[("user", wallet_id), ("limit", "100")])
-        .header("Accept", "application/json")
-        .send()
+    let text = http::shared()
+        .get_text(|client| {
+            client
+                .get(format!("{}/closed-positions", config::endpoints().polymarket_data_api))
+                .query(&[("user", wallet_id), ("limit", "100")])
+                .header("Accept", "application/json")
+                .timeout(Duration::from_secs(5))
+        })
         .await
         .ok()?;
-
-    if !response.status().is_success() {
-        return None;
-    }
-
-    let text = response.text().await.ok()?;
     let positions: Vec<ClosedPositionEntry> = serde_json::from_str(&text).ok()?;
 
     if positions.is_empty() {
@@ -246,25 +336,88 @@ async fn fetch_win_rate(wallet_id: &str) -> Option<(f64, u32)> {
     Some((rate, total))
 }
 
-/// Fetch full whale profile for a Polymarket wallet (3 parallel API calls + leaderboard lookup)
-pub async fn fetch_whale_profile(wallet_id: &str, cache: &mut WhaleProfileCache) -> Option<WhaleProfile> {
-    // Check cache first
-    if let Some(cached) = cache.get(wallet_id) {
-        return Some(cached.clone());
-    }
+/// First activity and trade count, from the wallet's oldest activity
+pub async fn fetch_history(wallet_id: &str) -> Option<History> {
+    let limit = ACTIVITY_LIMIT.to_string();
+    let text = http::shared()
+        .get_text(|client| {
+            client
+                .get(format!("{}/activity", config::endpoints().polymarket_data_api))
+                .query(&[
+                    ("user", wallet_id),
+                    ("limit", limit.as_str()),
+                    ("sortBy", "TIMESTAMP"),
+                    ("sortDirection", "ASC"),
+                ])
+                .header("Accept", "application/json")
+                .timeout(Duration::from_secs(5))
+        })
+        .await
+        .ok()?;
+    let entries: Vec<ActivityEntry> = serde_json::from_str(&text).ok()?;
+
+    let first = entries.iter().filter(|e| e.timestamp.is_some()).min_by_key(|e| e.timestamp)?;
+    let trades = entries.iter().filter(|e| e.kind.as_deref() == Some("TRADE")).count() as u32;
+    Some(History {
+        first_seen: first.timestamp?,
+        first_tx: first.transaction_hash.clone(),
+        trades,
+    })
+}
 
-    // Refresh leaderboard if needed
-    cache.refresh_leaderboard_if_needed().await;
+/// Fetch full whale profile for a Polymarket wallet (5 parallel API calls + leaderboard lookup,
+/// and the funding source when a Polygon RPC endpoint is set).
+/// The cache lock is only held for lookups and inserts, never across a request.
+/// The funding lookup is waited on for at most `FUNDING_WAIT`.
+pub async fn fetch_whale_profile(wallet_id: &str, cache: &Mutex<WhaleProfileCache>) -> Option<WhaleProfile> {
+    // Check cache first
+    let leaderboard_stale = {
+        let cache = cache.lock().await;
+        if let Some(cached) = cache.get(wallet_id) {
+            let mut profile = cached.clone();
+            if profile.funded_by.is_none() {
+                if let Some(funding) = cache.funding_found(wallet_id) {
+                    profile.funded_at = funding.funded_at;
+                    profile.funded_by = Some(funding.funder);
+                }
+            }
+            return Some(profile);
+        }
+        cache.leaderboard_stale()
+    };
 
-    // Fetch portfolio data in parallel
-    let (value, positions, win_data) = tokio::join!(
+    // Fetch portfolio data (and the leaderboard if stale) in parallel
+    let (leaderboard, value, positions, win_data, history) = tokio::join!(
+        async {
+            if leaderboard_stale {
+                fetch_leaderboard().await
+            } else {
+                None
+            }
+        },
         fetch_portfolio_value(wallet_id),
         fetch_positions_count(wallet_id),
         fetch_win_rate(wallet_id),
+        fetch_history(wallet_id),
     );
 
+    // Funding is looked up from the first activity's transaction
+    let lookup = match history.as_ref().and_then(|h| h.first_tx.as_deref()) {
+        Some(first_tx) => cache.lock().await.funding_lookup(wallet_id, first_tx),
+        None => None,
+    };
+    if let Some(mut rx) = lookup {
+        let _ = time::timeout(FUNDING_WAIT, rx.wait_for(|l| matches!(l, FundingLookup::Done(_)))).await;
+    }
+
+    let mut cache = cache.lock().await;
+    if let Some(entries) = leaderboard {
+        cache.set_leaderboard(entries);
+    }
+
     // Look up in leaderboard
     let lb = cache.leaderboard_lookup(wallet_id);
+    let funding = cache.funding_found(wallet_id);
 
     let (win_rate, markets_traded) = match win_data {
         Some((rate, count)) => (Some(rate), Some(count)),
@@ -279,6 +432,10 @@ pub async fn fetch_whale_profile(wallet_id: &str, cache: &mut WhaleProfileCache)
         leaderboard_profit: lb.map(|(_, profit, _)| profit),
         win_rate,
         markets_traded,
+        funded_at: funding.as_ref().and_then(|f| f.funded_at),
+        funded_by: funding.map(|f| f.funder),
+        first_seen_at: history.as_ref().map(|h| h.first_seen),
+        lifetime_trades: history.map(|h| h.trades),
     };
 
     // Only cache if we got at least some data
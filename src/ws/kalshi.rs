@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use futures_util::{SinkExt, StreamExt};
@@ -5,47 +7,147 @@ use serde::Deserialize;
 use tokio::sync::mpsc;
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
 
-const KALSHI_WS_URL: &str = "wss://api.elections.kalshi.com/trade-api/ws/v2";
+use crate::capture::{self, WsFrame};
+use crate::config;
+use crate::platforms::kalshi::Trade;
+use crate::platforms::kalshi_auth::KalshiSigner;
+
+/// Path Kalshi expects in the handshake signature, whatever URL is configured
+const KALSHI_WS_PATH: &str = "/trade-api/ws/v2";
+/// Source name in capture files
+const CAPTURE_SOURCE: &str = "kalshi";
 const PING_INTERVAL: Duration = Duration::from_secs(10);
 const RECONNECT_BASE: Duration = Duration::from_secs(2);
 const RECONNECT_MAX: Duration = Duration::from_secs(60);
 
-/// A trade received from the Kalshi WebSocket
-#[derive(Debug, Clone)]
-pub struct WsTrade {
-    pub trade_id: String,
-    pub ticker: String,
-    pub count: i32,
-    pub yes_price: f64,
-    pub no_price: f64,
-    pub taker_side: String,
-    pub created_time: String,
-}
-
 #[derive(Debug, Deserialize)]
 struct WsMessage {
     #[serde(rename = "type")]
     msg_type: Option<String>,
+    /// Subscription ID; sequence numbers are per subscription
+    sid: Option<u64>,
+    seq: Option<u64>,
     #[serde(default)]
     msg: Option<WsTradeMsg>,
 }
 
+/// Trade payload: either a batch under `trades` or a single flat trade
 #[derive(Debug, Deserialize)]
 struct WsTradeMsg {
     #[serde(default)]
     trades: Vec<WsTradeEntry>,
+    #[serde(flatten)]
+    single: WsTradeEntry,
 }
 
 #[derive(Debug, Deserialize)]
 struct WsTradeEntry {
     trade_id: Option<String>,
+    #[serde(alias = "market_ticker")]
     ticker: Option<String>,
     count: Option<i32>,
     yes_price: Option<f64>,
     no_price: Option<f64>,
     taker_side: Option<String>,
     created_time: Option<String>,
+    /// Unix seconds, sent instead of `created_time` by the v2 feed
+    ts: Option<i64>,
+}
+
+/// What the Kalshi listener reports to its consumer
+#[derive(Debug)]
+pub enum KalshiWsEvent {
+    Trade(Trade),
+    /// Trades since `since` (Unix seconds) may have been dropped
+    Gap { since: i64, reason: GapReason },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum GapReason {
+    Reconnect,
+    SequenceGap,
+}
+
+impl std::fmt::Display for GapReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GapReason::Reconnect => write!(f, "reconnect"),
+            GapReason::SequenceGap => write!(f, "sequence gap"),
+        }
+    }
+}
+
+/// Continuity state carried across reconnects
+struct StreamState {
+    /// When we last heard from the socket (Unix seconds)
+    last_seen: Option<i64>,
+    /// Last sequence number per subscription on the current connection
+    last_seq: HashMap<u64, u64>,
+}
+
+impl StreamState {
+    fn new() -> Self {
+        Self {
+            last_seen: None,
+            last_seq: HashMap::new(),
+        }
+    }
+
+    /// A fresh subscription restarts sequence numbers. Anything traded while
+    /// we were disconnected has to come from REST.
+    fn on_connected(&mut self) -> Option<KalshiWsEvent> {
+        self.last_seq.clear();
+        let gap = self.last_seen.map(|since| KalshiWsEvent::Gap { since, reason: GapReason::Reconnect });
+        self.last_seen = Some(capture::now().timestamp());
+        gap
+    }
+
+    /// Events carried by one text frame, including a `Gap` if sequence numbers were skipped
+    fn on_text(&mut self, text: &str) -> Vec<KalshiWsEvent> {
+        let previously_seen = self.last_seen.unwrap_or_default();
+        self.last_seen = Some(capture::now().timestamp());
+
+        let mut events = Vec::new();
+
+        // Try to parse as trade message
+        if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(text) {
+            if let (Some(sid), Some(seq)) = (ws_msg.sid, ws_msg.seq) {
+                if self.check_seq(sid, seq) {
+                    events.push(KalshiWsEvent::Gap {
+                        since: previously_seen,
+                        reason: GapReason::SequenceGap,
+                    });
+                }
+            }
+
+            if ws_msg.msg_type.as_deref() == Some("trade") {
+                if let Some(trade_msg) = ws_msg.msg {
+                    let entries = if trade_msg.trades.is_empty() {
+                        vec![trade_msg.single]
+                    } else {
+                        trade_msg.trades
+                    };
+                    events.extend(entries.into_iter().filter_map(parse_ws_trade).map(KalshiWsEvent::Trade));
+                }
+            }
+        }
+        // Also try flat trade format (some WS messages are different shape)
+        else if let Ok(entry) = serde_json::from_str::<WsTradeEntry>(text) {
+            events.extend(parse_ws_trade(entry).map(KalshiWsEvent::Trade));
+        }
+
+        events
+    }
+
+    /// Track a message's sequence number, returning true if messages were skipped
+    fn check_seq(&mut self, sid: u64, seq: u64) -> bool {
+        let skipped = matches!(self.last_seq.get(&sid), Some(&last) if seq > last + 1);
+        self.last_seq.insert(sid, seq);
+        skipped
+    }
 }
 
 /// Subscribe command for Kalshi WebSocket
@@ -61,16 +163,26 @@ fn subscribe_cmd() -> String {
 }
 
 /// Spawn a Kalshi WebSocket listener that sends trades to the returned channel.
-/// The connection auto-reconnects with exponential backoff on failure.
-pub fn spawn_kalshi_ws() -> mpsc::UnboundedReceiver<WsTrade> {
+/// Kalshi requires an authenticated handshake, signed afresh on every connect.
+/// The connection auto-reconnects with exponential backoff on failure, and a
+/// `Gap` event is sent after each reconnect or skipped sequence number so the
+/// consumer can backfill over REST.
+pub fn spawn_kalshi_ws(signer: Arc<KalshiSigner>) -> mpsc::UnboundedReceiver<KalshiWsEvent> {
     let (tx, rx) = mpsc::unbounded_channel();
+    if let Some(recorder) = capture::recorder() {
+        recorder.ws_opened(CAPTURE_SOURCE);
+    }
 
     tokio::spawn(async move {
         let mut backoff = RECONNECT_BASE;
+        let mut state = StreamState::new();
 
         loop {
-            match connect_and_listen(&tx).await {
+            match connect_and_listen(&tx, &signer, &mut state).await {
                 Ok(()) => {
+                    if tx.is_closed() {
+                        break;
+                    }
                     // Clean disconnect — reconnect immediately
                     eprintln!("[WS] Kalshi WebSocket disconnected, reconnecting...");
                     backoff = RECONNECT_BASE;
@@ -87,29 +199,57 @@ pub fn spawn_kalshi_ws() -> mpsc::UnboundedReceiver<WsTrade> {
     rx
 }
 
+/// Replay a recorded Kalshi socket through the same state handling as a live
+/// one, or `None` if the recording had no Kalshi listener
+pub fn replay_kalshi_ws(replayer: &capture::replay::Replayer) -> Option<mpsc::UnboundedReceiver<KalshiWsEvent>> {
+    let frames = replayer.ws_frames(CAPTURE_SOURCE)?;
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let mut state = StreamState::new();
+        for (offset, frame) in frames {
+            capture::sleep_until_offset(offset).await;
+            let events = match frame {
+                WsFrame::Connected => state.on_connected().into_iter().collect(),
+                WsFrame::Text(text) => state.on_text(&text),
+            };
+            for event in events {
+                if tx.send(event).is_err() {
+                    return;
+                }
+            }
+        }
+        // A recording ends with the socket still open
+        std::future::pending::<()>().await;
+    });
+
+    Some(rx)
+}
+
 async fn connect_and_listen(
-    tx: &mpsc::UnboundedSender<WsTrade>,
+    tx: &mpsc::UnboundedSender<KalshiWsEvent>,
+    signer: &KalshiSigner,
+    state: &mut StreamState,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let (ws_stream, _) = connect_async(KALSHI_WS_URL).await?;
+    let mut request = config::endpoints().kalshi_ws.as_str().into_client_request()?;
+    for (name, value) in signer.auth_headers("GET", KALSHI_WS_PATH) {
+        request.headers_mut().insert(name, HeaderValue::from_str(&value)?);
+    }
+
+    let (ws_stream, _) = connect_async(request).await?;
     let (mut write, mut read) = ws_stream.split();
 
     // Subscribe to trade channel
     write.send(Message::Text(subscribe_cmd())).await?;
 
-    // Ping loop
-    let ping_tx = tx.clone();
-    let ping_handle = tokio::spawn(async move {
-        let mut interval = tokio::time::interval(PING_INTERVAL);
-        loop {
-            interval.tick().await;
-            if ping_tx.is_closed() {
-                break;
-            }
-        }
-    });
+    if let Some(recorder) = capture::recorder() {
+        recorder.ws_connected(CAPTURE_SOURCE);
+    }
+    if let Some(gap) = state.on_connected() {
+        let _ = tx.send(gap);
+    }
 
-    // Also spawn a ping sender on the write half
-    // We need to use a channel to coordinate writes
+    // Route all writes through one task so pings and pongs don't race
     let (write_tx, mut write_rx) = mpsc::unbounded_channel::<Message>();
 
     // Spawn writer task
@@ -133,32 +273,33 @@ async fn connect_and_listen(
         }
     });
 
-    // Read loop
+    let result = read_loop(&mut read, tx, &write_tx, state).await;
+
+    ping_task.abort();
+    writer_handle.abort();
+    result
+}
+
+async fn read_loop<S>(
+    read: &mut S,
+    tx: &mpsc::UnboundedSender<KalshiWsEvent>,
+    write_tx: &mpsc::UnboundedSender<Message>,
+    state: &mut StreamState,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
     while let Some(msg) = read.next().await {
         match msg {
             Ok(Message::Text(text)) => {
-                // Try to parse as trade message
-                if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(&text) {
-                    if ws_msg.msg_type.as_deref() == Some("trade") {
-                        if let Some(trade_msg) = ws_msg.msg {
-                            for entry in trade_msg.trades {
-                                if let Some(trade) = parse_ws_trade(entry) {
-                                    if tx.send(trade).is_err() {
-                                        // Receiver dropped
-                                        ping_handle.abort();
-                                        ping_task.abort();
-                                        writer_handle.abort();
-                                        return Ok(());
-                                    }
-                                }
-                            }
-                        }
-                    }
+                if let Some(recorder) = capture::recorder() {
+                    recorder.ws_text(CAPTURE_SOURCE, &text);
                 }
-                // Also try flat trade format (some WS messages are different shape)
-                else if let Ok(entry) = serde_json::from_str::<WsTradeEntry>(&text) {
-                    if let Some(trade) = parse_ws_trade(entry) {
-                        let _ = tx.send(trade);
+
+                for event in state.on_text(&text) {
+                    if tx.send(event).is_err() {
+                        // Receiver dropped
+                        return Ok(());
                     }
                 }
             }
@@ -169,29 +310,59 @@ async fn connect_and_listen(
                 break;
             }
             Err(e) => {
-                ping_handle.abort();
-                ping_task.abort();
-                writer_handle.abort();
                 return Err(Box::new(e));
             }
             _ => {}
         }
     }
 
-    ping_handle.abort();
-    ping_task.abort();
-    writer_handle.abort();
     Ok(())
 }
 
-fn parse_ws_trade(entry: WsTradeEntry) -> Option<WsTrade> {
-    Some(WsTrade {
+fn parse_ws_trade(entry: WsTradeEntry) -> Option<Trade> {
+    let yes_price = entry.yes_price.unwrap_or(0.0);
+    let created_time = entry
+        .created_time
+        .or_else(|| entry.ts.and_then(|ts| chrono::DateTime::from_timestamp(ts, 0)).map(|dt| dt.to_rfc3339()))
+        .unwrap_or_else(|| capture::now().to_rfc3339());
+
+    Some(Trade {
         trade_id: entry.trade_id?,
         ticker: entry.ticker?,
+        price: yes_price / 100.0,
         count: entry.count.unwrap_or(1),
-        yes_price: entry.yes_price.unwrap_or(0.0),
+        yes_price,
         no_price: entry.no_price.unwrap_or(0.0),
         taker_side: entry.taker_side.unwrap_or_else(|| "yes".to_string()),
-        created_time: entry.created_time.unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+        created_time,
+        market_title: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skipped_sequence_numbers_are_gaps_per_subscription() {
+        let mut state = StreamState::new();
+        assert!(!state.check_seq(1, 5));
+        assert!(!state.check_seq(1, 6));
+        // Subscriptions are numbered independently
+        assert!(!state.check_seq(2, 1));
+        assert!(state.check_seq(1, 9));
+        assert!(!state.check_seq(1, 10));
+
+        // Reconnecting restarts the numbering, and reports the time offline as a gap
+        state.last_seen = Some(100);
+        assert!(matches!(
+            state.on_connected(),
+            Some(KalshiWsEvent::Gap { since: 100, reason: GapReason::Reconnect })
+        ));
+        assert!(!state.check_seq(1, 1));
+
+        // A frame after a skipped number carries a gap before its trades
+        let events = state.on_text(r#"{"type":"ticker","sid":1,"seq":3}"#);
+        assert!(matches!(events.as_slice(), [KalshiWsEvent::Gap { reason: GapReason::SequenceGap, .. }]));
+    }
+}
@@ -1 +1,2 @@
 pub mod kalshi;
+pub mod polymarket;
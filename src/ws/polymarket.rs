@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::capture::{self, WsFrame};
+use crate::config;
+use crate::platforms::polymarket::{ActivityItem, Trade};
+
+/// Source name in capture files
+const CAPTURE_SOURCE: &str = "polymarket";
+const PING_INTERVAL: Duration = Duration::from_secs(10);
+const RECONNECT_BASE: Duration = Duration::from_secs(2);
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+
+/// Envelope used by the Polymarket real-time data service
+#[derive(Debug, Deserialize)]
+struct WsMessage {
+    topic: Option<String>,
+    #[serde(rename = "type")]
+    msg_type: Option<String>,
+    #[serde(default)]
+    payload: Option<serde_json::Value>,
+}
+
+/// Subscribe command for the activity feed (every matched trade, all markets)
+fn subscribe_cmd() -> String {
+    serde_json::json!({
+        "action": "subscribe",
+        "subscriptions": [
+            { "topic": "activity", "type": "trades" }
+        ]
+    })
+    .to_string()
+}
+
+/// Spawn a Polymarket WebSocket listener that sends trades to the returned channel.
+/// The connection auto-reconnects with exponential backoff on failure.
+pub fn spawn_polymarket_ws() -> mpsc::UnboundedReceiver<Trade> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    if let Some(recorder) = capture::recorder() {
+        recorder.ws_opened(CAPTURE_SOURCE);
+    }
+
+    tokio::spawn(async move {
+        let mut backoff = RECONNECT_BASE;
+
+        loop {
+            match connect_and_listen(&tx).await {
+                Ok(()) => {
+                    if tx.is_closed() {
+                        break;
+                    }
+                    // Clean disconnect — reconnect immediately
+                    eprintln!("[WS] Polymarket WebSocket disconnected, reconnecting...");
+                    backoff = RECONNECT_BASE;
+                }
+                Err(e) => {
+                    eprintln!("[WS] Polymarket WebSocket error: {}, reconnecting in {:?}...", e, backoff);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(RECONNECT_MAX);
+                }
+            }
+        }
+    });
+
+    rx
+}
+
+/// Replay a recorded Polymarket socket, or `None` if the recording had no Polymarket listener
+pub fn replay_polymarket_ws(replayer: &capture::replay::Replayer) -> Option<mpsc::UnboundedReceiver<Trade>> {
+    let frames = replayer.ws_frames(CAPTURE_SOURCE)?;
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        for (offset, frame) in frames {
+            capture::sleep_until_offset(offset).await;
+            if let WsFrame::Text(text) = frame {
+                if let Some(trade) = parse_ws_trade(&text) {
+                    if tx.send(trade).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+        // A recording ends with the socket still open
+        std::future::pending::<()>().await;
+    });
+
+    Some(rx)
+}
+
+async fn connect_and_listen(
+    tx: &mpsc::UnboundedSender<Trade>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (ws_stream, _) = connect_async(config::endpoints().polymarket_ws.as_str()).await?;
+    let (mut write, mut read) = ws_stream.split();
+
+    // Subscribe to the trade activity feed
+    write.send(Message::Text(subscribe_cmd())).await?;
+    if let Some(recorder) = capture::recorder() {
+        recorder.ws_connected(CAPTURE_SOURCE);
+    }
+
+    // Route all writes through one task so pings and pongs don't race
+    let (write_tx, mut write_rx) = mpsc::unbounded_channel::<Message>();
+
+    let writer_handle = tokio::spawn(async move {
+        while let Some(msg) = write_rx.recv().await {
+            if write.send(msg).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let ping_write_tx = write_tx.clone();
+    let ping_task = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PING_INTERVAL);
+        loop {
+            interval.tick().await;
+            if ping_write_tx.send(Message::Ping(vec![])).is_err() {
+                break;
+            }
+        }
+    });
+
+    let result = read_loop(&mut read, tx, &write_tx).await;
+
+    ping_task.abort();
+    writer_handle.abort();
+    result
+}
+
+async fn read_loop<S>(
+    read: &mut S,
+    tx: &mpsc::UnboundedSender<Trade>,
+    write_tx: &mpsc::UnboundedSender<Message>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    while let Some(msg) = read.next().await {
+        match msg {
+            Ok(Message::Text(text)) => {
+                if let Some(recorder) = capture::recorder() {
+                    recorder.ws_text(CAPTURE_SOURCE, &text);
+                }
+                if let Some(trade) = parse_ws_trade(&text) {
+                    if tx.send(trade).is_err() {
+                        // Receiver dropped
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(Message::Ping(data)) => {
+                let _ = write_tx.send(Message::Pong(data));
+            }
+            Ok(Message::Close(_)) => {
+                break;
+            }
+            Err(e) => {
+                return Err(Box::new(e));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_ws_trade(text: &str) -> Option<Trade> {
+    let ws_msg = serde_json::from_str::<WsMessage>(text).ok()?;
+    if ws_msg.topic.as_deref() != Some("activity") {
+        return None;
+    }
+    if !matches!(ws_msg.msg_type.as_deref(), Some("trades") | Some("orders_matched")) {
+        return None;
+    }
+
+    let item: ActivityItem = serde_json::from_value(ws_msg.payload?).ok()?;
+    item.into_trade()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_trades_from_the_activity_topic_only() {
+        let frame = r#"{"topic":"activity","type":"trades","payload":{"transactionHash":"0xf1","conditionId":"0xcf",
+            "asset":"77","side":"BUY","size":150000,"price":0.2,"timestamp":1790856000,
+            "proxyWallet":"0xab","title":"Will the Fed cut rates?","outcome":"Yes"}}"#;
+        let trade = parse_ws_trade(frame).unwrap();
+        assert_eq!(trade.id, "0xf1");
+        assert_eq!(trade.market, "0xcf");
+        assert_eq!(trade.asset_id, "77");
+        assert_eq!(trade.timestamp, "2026-10-01T12:00:00+00:00");
+        assert_eq!(trade.wallet_id.as_deref(), Some("0xab"));
+        assert_eq!(trade.outcome.as_deref(), Some("Yes"));
+
+        // Other topics, other message types and incomplete payloads are skipped
+        assert!(parse_ws_trade(&frame.replace(r#""topic":"activity""#, r#""topic":"comments""#)).is_none());
+        assert!(parse_ws_trade(&frame.replace(r#""type":"trades""#, r#""type":"reactions""#)).is_none());
+        assert!(parse_ws_trade(&frame.replace(r#""price":0.2,"#, "")).is_none());
+        assert!(parse_ws_trade("not json").is_none());
+    }
+}
@@ -0,0 +1,63 @@
+//! Replays the capture in `tests/fixtures/replay` through the watch pipeline
+//! and checks the alerts it stores.
+//!
+//! The capture holds three Polymarket trades: a freshly funded wallet's
+//! long-shot buy (with its Data API history and Polygon funding lookup), a
+//! buy under the threshold, and an established wallet's sell.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/replay");
+
+/// (alert_type, action, wallet_id, value, order_id) of each stored alert, in order
+type AlertRow = (String, String, Option<String>, f64, String);
+
+/// Copy of the fixture, since a replay writes its database into the capture directory
+fn fixture_copy(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("wwatcher-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for file in ["manifest.json", "events.jsonl"] {
+        std::fs::copy(Path::new(FIXTURE).join(file), dir.join(file)).unwrap();
+    }
+    dir
+}
+
+fn replay(dir: &Path) -> Vec<AlertRow> {
+    let output = Command::new(env!("CARGO_BIN_EXE_wwatcher")).arg("replay").arg(dir).output().unwrap();
+    assert!(output.status.success(), "replay failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let conn = rusqlite::Connection::open(dir.join("replay.db")).unwrap();
+    let mut stmt = conn
+        .prepare("SELECT alert_type, action, wallet_id, value, order_id FROM alerts ORDER BY id")
+        .unwrap();
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
+        .unwrap();
+    rows.map(Result::unwrap).collect()
+}
+
+#[test]
+fn capture_replays_to_the_same_alerts() {
+    let dir = fixture_copy("replay");
+    let alerts = replay(&dir);
+
+    let fresh = format!("0x{}", "ab".repeat(20));
+    let established = format!("0x{}", "ef".repeat(20));
+    let summary: Vec<(&str, &str, Option<&str>, f64)> = alerts
+        .iter()
+        .map(|(alert_type, action, wallet, value, _)| (alert_type.as_str(), action.as_str(), wallet.as_deref(), *value))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("FRESH_WALLET_ENTRY", "BUY", Some(fresh.as_str()), 30_000.0),
+            ("WHALE_EXIT", "SELL", Some(established.as_str()), 60_000.0),
+        ]
+    );
+
+    // A second run starts from a fresh database and stores exactly the same alerts
+    assert_eq!(replay(&dir), alerts);
+    let _ = std::fs::remove_dir_all(&dir);
+}